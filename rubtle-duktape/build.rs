@@ -12,14 +12,69 @@ extern crate bindgen;
 extern crate cc;
 
 use std::env;
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+///
+/// Apply patch to a copy of the given source file
+///
+/// # Arguments
+///
+/// * `src` - Path of the source file
+/// * `patch` - Path of the patch in unified diff format
+/// * `dest` - Path of the patched copy
+///
+/// Only the hunks of the patch are used and each of them must match exactly
+/// once; outdated patches fail the build instead of getting lost silently.
+///
+
+fn apply_patch(src: &str, patch: &str, dest: &Path) {
+    let mut source = fs::read_to_string(src).expect("Unable to read source");
+    let diff = fs::read_to_string(patch).expect("Unable to read patch");
+    let mut hunks: Vec<(String, String)> = Vec::new();
+
+    /* Split hunks into old and new text */
+    for line in diff.lines().skip_while(|line| !line.starts_with("@@")) {
+        if line.starts_with("@@") {
+            hunks.push((String::new(), String::new()));
+
+            continue;
+        }
+
+        if let Some((old, new)) = hunks.last_mut() {
+            let text = line.get(1..).unwrap_or("");
+
+            match line.chars().next() {
+                Some('-') => *old += &format!("{}\n", text),
+                Some('+') => *new += &format!("{}\n", text),
+                Some('\\') => (),
+                _ => {
+                    *old += &format!("{}\n", text);
+                    *new += &format!("{}\n", text);
+                }
+            }
+        }
+    }
+
+    for (old, new) in hunks {
+        if 1 != source.matches(&old).count() {
+            panic!("Patch {} does not apply to {}", patch, src);
+        }
+
+        source = source.replacen(&old, &new, 1);
+    }
+
+    fs::write(dest, source).expect("Unable to write patched source");
+}
 
 fn main() {
     /* Tell cargo to tell rustc to link the static library.*/
     println!("cargo:rustc-link-lib=static=duktape");
 
     /* Tell cargo to invalidate the built crate whenever the wrapper changes */
-    println!("cargo:rerun-if-changed=duktape_wrapper.h");
+    println!("cargo:rerun-if-changed=src/duktape_wrapper.h");
+    println!("cargo:rerun-if-changed=src/duktape_wrapper.c");
+    println!("cargo:rerun-if-changed=patches/finalizer-hook.patch");
 
     /* Try to fix llvm path for macos */
     if cfg!(target_os = "macos") {
@@ -45,13 +100,26 @@ fn main() {
         .write_to_file(out_path.join("bindings.rs"))
         .expect("Couldn't write bindings!");
 
-    /* Build duktape */
+    /* Build duktape along with our helpers */
     let mut builder = cc::Build::new();
 
+    /* Patched amalgamation must be found first */
+    if cfg!(feature = "use-finalizer-hook") {
+        apply_patch(
+            "duktape/duktape.c",
+            "patches/finalizer-hook.patch",
+            &out_path.join("duktape.c"),
+        );
+
+        builder
+            .include(&out_path)
+            .define("RUST_DUK_USE_FINALIZER_HOOK", None);
+    }
+
     builder
         .include("duktape")
         .flag("-std=c99")
-        .file("duktape/duktape.c");
+        .file("src/duktape_wrapper.c");
 
     if cfg!(feature = "use-exec-timeout-check") {
         builder.define("RUST_DUK_USE_EXEC_TIMEOUT_CHECK", None);
@@ -3097,6 +3097,16 @@ typedef struct duk_hthread duk_context;
 
 /* __OVERRIDE_DEFINES__ */
 
+/*
+ *  Rubtle: execution timeout check, provided by rubtle-lib
+ */
+
+#if defined(RUST_DUK_USE_EXEC_TIMEOUT_CHECK)
+#define DUK_USE_INTERRUPT_COUNTER
+#define DUK_USE_EXEC_TIMEOUT_CHECK(udata)  rust_duk_exec_timeout_check((udata))
+extern duk_bool_t rust_duk_exec_timeout_check(void *udata);
+#endif
+
 /*
  *  Conditional includes
  */
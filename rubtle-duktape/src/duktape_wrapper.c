@@ -0,0 +1,214 @@
+/*
+ * @package Rubtle-Duktape
+ *
+ * @file Duktape amalgamation plus helpers that need its internals
+ * @copyright 2020-present Christoph Kappel <christoph@unexist.dev>
+ * @version $Id$
+ *
+ * This program can be distributed under the terms of the GNU GPLv2.
+ * See the file LICENSE for details.
+ */
+#if defined(RUST_DUK_USE_FINALIZER_HOOK)
+/* Provided by rubtle-lib; called from patches/finalizer-hook.patch */
+extern void rust_duk_finalizer_hook(void *udata);
+#endif
+
+/* Either the vendored or the patched amalgamation, see build.rs */
+#include "duktape.c"
+#include "duktape_wrapper.h"
+
+DUK_LOCAL duk_size_t rust_duk__count_list(duk_heap *heap, duk_heaphdr *curr,
+    duk_small_uint_t htype)
+{
+    duk_size_t count = 0;
+
+    DUK_UNREF(heap); /* Only used with pointer compression */
+
+    while (NULL != curr) {
+        if (htype == DUK_HEAPHDR_GET_TYPE(curr)) {
+            count++;
+        }
+
+        curr = DUK_HEAPHDR_GET_NEXT(heap, curr);
+    }
+
+    return count;
+}
+
+void rust_duk_get_heap_stats(duk_context *ctx, rust_duk_heap_stats *stats)
+{
+    duk_heap *heap = ((duk_hthread *) ctx)->heap;
+    duk_uint32_t i;
+
+    DUK_MEMZERO(stats, sizeof(*stats));
+
+    /* Objects and buffers live in heap_allocated, objects awaiting
+     * finalization or refzero processing are kept in separate lists */
+    stats->objects = rust_duk__count_list(heap, heap->heap_allocated,
+        DUK_HTYPE_OBJECT);
+    stats->buffers = rust_duk__count_list(heap, heap->heap_allocated,
+        DUK_HTYPE_BUFFER);
+
+#if defined(DUK_USE_REFERENCE_COUNTING)
+    stats->objects += rust_duk__count_list(heap, heap->refzero_list,
+        DUK_HTYPE_OBJECT);
+#endif
+
+#if defined(DUK_USE_FINALIZER_SUPPORT)
+    stats->pending_finalizers = rust_duk__count_list(heap,
+        heap->finalize_list, DUK_HTYPE_OBJECT);
+    stats->objects += stats->pending_finalizers;
+#endif
+
+    /* Strings are only tracked by the string table */
+    for (i = 0; i < heap->st_size; i++) {
+#if defined(DUK_USE_STRTAB_PTRCOMP)
+        duk_hstring *h = (duk_hstring *) DUK_USE_HEAPPTR_DEC16(
+            heap->heap_udata, heap->strtable16[i]);
+#else
+        duk_hstring *h = heap->strtable[i];
+#endif
+
+        while (NULL != h) {
+            stats->strings++;
+
+            h = h->hdr.h_next;
+        }
+    }
+}
+
+duk_bool_t rust_duk_is_byte_buffer(duk_context *ctx, duk_idx_t idx)
+{
+    duk_hthread *thr = (duk_hthread *) ctx;
+    duk_hobject *h;
+
+    if (DUK_TYPE_BUFFER == duk_get_type(ctx, idx)) {
+        return 1;
+    }
+
+    /* Only byte views; other typed arrays have element types */
+    h = duk_get_hobject(thr, idx);
+
+    if (NULL != h) {
+        switch (DUK_HOBJECT_GET_CLASS_NUMBER(h)) {
+            case DUK_HOBJECT_CLASS_ARRAYBUFFER:
+            case DUK_HOBJECT_CLASS_DATAVIEW:
+            case DUK_HOBJECT_CLASS_UINT8ARRAY: /* Includes Node.js Buffer */
+                return 1;
+        }
+    }
+
+    return 0;
+}
+
+duk_bool_t rust_duk_get_date(duk_context *ctx, duk_idx_t idx, duk_double_t *out_timeval)
+{
+    duk_hthread *thr = (duk_hthread *) ctx;
+    duk_hobject *h = duk_get_hobject(thr, idx);
+
+    if (NULL == h || DUK_HOBJECT_CLASS_DATE != DUK_HOBJECT_GET_CLASS_NUMBER(h)) {
+        return 0;
+    }
+
+    /* Time value is kept in a hidden own property; NaN for invalid dates */
+    idx = duk_normalize_index(ctx, idx);
+
+    duk_require_stack(ctx, 1);
+    duk_xget_owndataprop_stridx_short(thr, idx, DUK_STRIDX_INT_VALUE);
+
+    *out_timeval = duk_get_number(ctx, -1);
+
+    duk_pop(ctx);
+
+    return 1;
+}
+
+void rust_duk_push_date(duk_context *ctx, duk_double_t timeval)
+{
+    duk_hthread *thr = (duk_hthread *) ctx;
+
+    /* Use the built-in constructor; scripts may replace the global one */
+    duk_require_stack(ctx, 2);
+    duk_push_hobject_bidx(thr, DUK_BIDX_DATE_CONSTRUCTOR);
+    duk_push_number(ctx, timeval);
+    duk_new(ctx, 1);
+}
+
+DUK_LOCAL duk_small_uint_t rust_duk__json_flags(duk_hthread *thr,
+    duk_int_t format, duk_bool_t encode)
+{
+    /* Same flags as Duktape.enc and Duktape.dec use */
+    switch (format) {
+        case RUST_DUK_JSON_FORMAT_JSON:
+            return 0;
+#if defined(DUK_USE_JX)
+        case RUST_DUK_JSON_FORMAT_JX:
+            return DUK_JSON_FLAG_EXT_CUSTOM | (encode ?
+                DUK_JSON_FLAG_ASCII_ONLY | DUK_JSON_FLAG_AVOID_KEY_QUOTES : 0);
+#endif
+#if defined(DUK_USE_JC)
+        case RUST_DUK_JSON_FORMAT_JC:
+            return DUK_JSON_FLAG_EXT_COMPATIBLE | (encode ?
+                DUK_JSON_FLAG_ASCII_ONLY : 0);
+#endif
+    }
+
+    DUK_ERROR_UNSUPPORTED(thr);
+    DUK_WO_NORETURN(return 0;);
+}
+
+void rust_duk_json_encode(duk_context *ctx, duk_idx_t idx, duk_int_t format,
+    duk_int_t indent)
+{
+    duk_hthread *thr = (duk_hthread *) ctx;
+    duk_small_uint_t flags = rust_duk__json_flags(thr, format, 1);
+
+    /* Call the codec directly; scripts may replace JSON and Duktape */
+    idx = duk_require_normalize_index(ctx, idx);
+
+    duk_require_stack(ctx, 2);
+    duk_push_int(ctx, indent);
+    duk_bi_json_stringify_helper(thr, idx, DUK_INVALID_INDEX,
+        duk_get_top_index(ctx), flags);
+    duk_replace(ctx, idx);
+    duk_pop(ctx);
+}
+
+void rust_duk_json_decode(duk_context *ctx, duk_idx_t idx, duk_int_t format)
+{
+    duk_hthread *thr = (duk_hthread *) ctx;
+    duk_small_uint_t flags = rust_duk__json_flags(thr, format, 0);
+
+    idx = duk_require_normalize_index(ctx, idx);
+
+    duk_require_stack(ctx, 1);
+    duk_bi_json_parse_helper(thr, idx, DUK_INVALID_INDEX, flags);
+    duk_replace(ctx, idx);
+}
+
+duk_uint32_t rust_duk_get_build_flags(void)
+{
+    duk_uint32_t flags = 0;
+
+#if defined(DUK_USE_PACKED_TVAL)
+    flags |= RUST_DUK_BUILD_PACKED_TVAL;
+#endif
+#if defined(DUK_USE_FASTINT)
+    flags |= RUST_DUK_BUILD_FASTINT;
+#endif
+#if defined(DUK_USE_64BIT_OPS)
+    flags |= RUST_DUK_BUILD_64BIT_OPS;
+#endif
+#if defined(DUK_USE_PC2LINE)
+    flags |= RUST_DUK_BUILD_PC2LINE;
+#endif
+#if defined(DUK_USE_ES6)
+    flags |= RUST_DUK_BUILD_ES6;
+#endif
+
+    /* Byte order of integers and doubles and size of pointers */
+    flags |= RUST_DUK_BUILD_BYTEORDER(DUK_USE_BYTEORDER);
+    flags |= RUST_DUK_BUILD_PTRSIZE(sizeof(void *));
+
+    return flags;
+}
@@ -1 +1,43 @@
 #include "../duktape/duktape.h"
+
+/* Heap statistics gathered from duktape internals */
+typedef struct rust_duk_heap_stats {
+    duk_size_t objects;
+    duk_size_t strings;
+    duk_size_t buffers;
+    duk_size_t pending_finalizers;
+} rust_duk_heap_stats;
+
+void rust_duk_get_heap_stats(duk_context *ctx, rust_duk_heap_stats *stats);
+
+/* Check whether value is a plain buffer or a byte view on one */
+duk_bool_t rust_duk_is_byte_buffer(duk_context *ctx, duk_idx_t idx);
+
+/* Get time value of Date objects */
+duk_bool_t rust_duk_get_date(duk_context *ctx, duk_idx_t idx, duk_double_t *out_timeval);
+
+/* Push Date object with given time value */
+void rust_duk_push_date(duk_context *ctx, duk_double_t timeval);
+
+/* Formats of the JSON codec helpers */
+#define RUST_DUK_JSON_FORMAT_JSON  0
+#define RUST_DUK_JSON_FORMAT_JX    1
+#define RUST_DUK_JSON_FORMAT_JC    2
+
+/* Replace value with its encoding like JSON.stringify or Duktape.enc */
+void rust_duk_json_encode(duk_context *ctx, duk_idx_t idx, duk_int_t format, duk_int_t indent);
+
+/* Replace string with its decoded value like JSON.parse or Duktape.dec */
+void rust_duk_json_decode(duk_context *ctx, duk_idx_t idx, duk_int_t format);
+
+/* Bits of the build configuration that change the bytecode format */
+#define RUST_DUK_BUILD_PACKED_TVAL   (1U << 0)
+#define RUST_DUK_BUILD_FASTINT       (1U << 1)
+#define RUST_DUK_BUILD_64BIT_OPS     (1U << 2)
+#define RUST_DUK_BUILD_PC2LINE       (1U << 3)
+#define RUST_DUK_BUILD_ES6           (1U << 4)
+#define RUST_DUK_BUILD_BYTEORDER(x)  ((duk_uint32_t) (x) << 8)
+#define RUST_DUK_BUILD_PTRSIZE(x)    ((duk_uint32_t) (x) << 16)
+
+/* Get fingerprint of the build configuration */
+duk_uint32_t rust_duk_get_build_flags(void);
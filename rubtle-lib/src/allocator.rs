@@ -0,0 +1,236 @@
+///
+/// @package Rubtle-Lib
+///
+/// @file Allocator functions
+/// @copyright 2020-present Christoph Kappel <christoph@unexist.dev>
+/// @version $Id$
+///
+/// This program can be distributed under the terms of the GNU GPLv2.
+/// See the file LICENSE for details.
+//
+use std::alloc::{self, Layout};
+use std::cell::Cell;
+use std::os::raw::c_void;
+use std::ptr;
+
+use crate::heap::HeapData;
+
+/// Size of the allocation header; keeps payloads aligned like malloc does
+const HEADER: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MemoryUsage {
+    /// Currently allocated bytes
+    pub allocated: usize,
+
+    /// Highest number of allocated bytes so far
+    pub peak: usize,
+
+    /// Hard limit of allocated bytes
+    pub limit: Option<usize>,
+}
+
+pub(crate) struct MemoryTracker {
+    allocated: Cell<usize>,
+    peak: Cell<usize>,
+    limit: Option<usize>,
+
+    /// Whether an allocation has been refused because of the limit
+    pub(crate) exceeded: Cell<bool>,
+}
+
+impl MemoryTracker {
+    pub(crate) fn new(limit: Option<usize>) -> MemoryTracker {
+        MemoryTracker {
+            allocated: Cell::new(0),
+            peak: Cell::new(0),
+            limit,
+            exceeded: Cell::new(false),
+        }
+    }
+
+    ///
+    /// Return current memory usage
+    ///
+    /// # Returns
+    ///
+    /// Snapshot of the usage as `MemoryUsage`
+    ///
+
+    pub(crate) fn usage(&self) -> MemoryUsage {
+        MemoryUsage {
+            allocated: self.allocated.get(),
+            peak: self.peak.get(),
+            limit: self.limit,
+        }
+    }
+
+    ///
+    /// Account for a change of allocated bytes
+    ///
+    /// # Arguments
+    ///
+    /// * `old_size` - Size of the previous allocation
+    /// * `new_size` - Size of the new allocation
+    ///
+    /// # Returns
+    ///
+    /// `true` if the change fits into the limit; otherwise `false`
+    ///
+
+    fn reserve(&self, old_size: usize, new_size: usize) -> bool {
+        let allocated = self.allocated.get() - old_size + new_size;
+
+        if let Some(limit) = self.limit {
+            if new_size > old_size && allocated > limit {
+                self.exceeded.set(true);
+
+                return false;
+            }
+        }
+
+        self.allocated.set(allocated);
+        self.peak.set(self.peak.get().max(allocated));
+
+        true
+    }
+
+    fn release(&self, size: usize) {
+        self.allocated.set(self.allocated.get() - size);
+    }
+
+    fn rollback(&self, old_size: usize, new_size: usize) {
+        self.allocated.set(self.allocated.get() - new_size + old_size);
+    }
+}
+
+///
+/// Get memory tracker from heap udata
+///
+/// # Arguments
+///
+/// * `udata` - Heap data supplied to the heap
+///
+
+unsafe fn tracker<'a>(udata: *mut c_void) -> &'a MemoryTracker {
+    (*(udata as *const HeapData))
+        .memory
+        .as_ref()
+        .expect("Allocator without memory tracker")
+}
+
+///
+/// Create layout of an allocation including its header
+///
+/// # Arguments
+///
+/// * `size` - Payload size
+///
+/// # Returns
+///
+/// `Option` with the layout or `None` if the size overflows
+///
+
+fn layout(size: usize) -> Option<Layout> {
+    HEADER
+        .checked_add(size)
+        .and_then(|total| Layout::from_size_align(total, HEADER).ok())
+}
+
+///
+/// Read size from allocation header
+///
+/// # Arguments
+///
+/// * `ptr` - Payload pointer handed out to duktape
+///
+/// # Returns
+///
+/// Header pointer and payload size
+///
+
+unsafe fn header(ptr: *mut c_void) -> (*mut u8, usize) {
+    let base = (ptr as *mut u8).sub(HEADER);
+
+    (base, *(base as *const usize))
+}
+
+pub(crate) unsafe extern "C" fn alloc_func(udata: *mut c_void, size: ffi::duk_size_t) -> *mut c_void {
+    let size = size as usize;
+
+    let layout = match layout(size) {
+        Some(layout) if 0 < size => layout,
+        _ => return ptr::null_mut(),
+    };
+
+    if !tracker(udata).reserve(0, size) {
+        return ptr::null_mut();
+    }
+
+    let base = alloc::alloc(layout);
+
+    if base.is_null() {
+        tracker(udata).release(size);
+
+        return ptr::null_mut();
+    }
+
+    *(base as *mut usize) = size;
+
+    base.add(HEADER) as *mut c_void
+}
+
+pub(crate) unsafe extern "C" fn realloc_func(
+    udata: *mut c_void,
+    ptr: *mut c_void,
+    size: ffi::duk_size_t,
+) -> *mut c_void {
+    if ptr.is_null() {
+        return alloc_func(udata, size);
+    }
+
+    if 0 == size {
+        free_func(udata, ptr);
+
+        return ptr::null_mut();
+    }
+
+    let size = size as usize;
+    let (base, old_size) = header(ptr);
+
+    let (old_layout, new_layout) = match (layout(old_size), layout(size)) {
+        (Some(old_layout), Some(new_layout)) => (old_layout, new_layout),
+        _ => return ptr::null_mut(),
+    };
+
+    if !tracker(udata).reserve(old_size, size) {
+        return ptr::null_mut();
+    }
+
+    let new_base = alloc::realloc(base, old_layout, new_layout.size());
+
+    if new_base.is_null() {
+        tracker(udata).rollback(old_size, size);
+
+        return ptr::null_mut();
+    }
+
+    *(new_base as *mut usize) = size;
+
+    new_base.add(HEADER) as *mut c_void
+}
+
+pub(crate) unsafe extern "C" fn free_func(udata: *mut c_void, ptr: *mut c_void) {
+    if ptr.is_null() {
+        return;
+    }
+
+    let (base, size) = header(ptr);
+
+    tracker(udata).release(size);
+
+    /* Layout has been valid on allocation, so this always matches */
+    if let Some(layout) = layout(size) {
+        alloc::dealloc(base, layout);
+    }
+}
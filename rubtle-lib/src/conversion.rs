@@ -0,0 +1,356 @@
+///
+/// @package Rubtle-Lib
+///
+/// @file Conversion options
+/// @copyright 2020-present Christoph Kappel <christoph@unexist.dev>
+/// @version $Id$
+///
+/// This program can be distributed under the terms of the GNU GPLv2.
+/// See the file LICENSE for details.
+//
+use std::collections::HashSet;
+use std::os::raw::c_void;
+
+use crate::{Error, Limit};
+
+/// Default maximum nesting of arrays and objects; fits into the stack of
+/// spawned threads even in debug builds
+pub const DEFAULT_MAX_DEPTH: usize = 256;
+
+/// Default maximum number of converted arrays and objects; shared objects
+/// are copied on every visit, so this bounds the size of the result
+pub const DEFAULT_MAX_NODES: usize = 1 << 20;
+
+/// How objects are handled that are reached more than once
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CycleHandling {
+    /// Fail with a conversion error on cycles; shared objects are copied
+    Error,
+
+    /// Keep every object reached again as `Value::Ref` handle
+    Reference,
+}
+
+/// How functions are handled that are found inside of arrays and objects
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FunctionHandling {
+    /// Keep functions as `Value::Function`
+    Keep,
+
+    /// Leave functions out of objects and replace them with null in arrays
+    /// like `JSON.stringify` does
+    Skip,
+
+    /// Fail with a conversion error
+    Error,
+}
+
+/// Options for converting JS values into a `Value`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConversionOptions {
+    pub(crate) cycles: CycleHandling,
+    pub(crate) max_depth: usize,
+    pub(crate) max_nodes: usize,
+    pub(crate) own_only: bool,
+    pub(crate) symbols: bool,
+    pub(crate) non_enumerable: bool,
+    pub(crate) accessors: bool,
+    pub(crate) functions: FunctionHandling,
+}
+
+/// State of a single conversion
+pub(crate) struct ConversionState<'a> {
+    pub(crate) options: &'a ConversionOptions,
+
+    /// Objects currently being converted
+    ancestors: Vec<*mut c_void>,
+
+    /// Objects converted so far; only tracked for references
+    visited: HashSet<*mut c_void>,
+
+    /// Number of arrays and objects converted so far
+    nodes: usize,
+}
+
+impl Default for ConversionOptions {
+    fn default() -> Self {
+        ConversionOptions {
+            cycles: CycleHandling::Error,
+            max_depth: DEFAULT_MAX_DEPTH,
+            max_nodes: DEFAULT_MAX_NODES,
+            own_only: false,
+            symbols: false,
+            non_enumerable: false,
+            accessors: true,
+            functions: FunctionHandling::Keep,
+        }
+    }
+}
+
+impl ConversionOptions {
+    ///
+    /// Create new options with defaults
+    ///
+    /// # Returns
+    ///
+    /// New `ConversionOptions` that fail on cycles, nesting deeper than
+    /// `DEFAULT_MAX_DEPTH` and more than `DEFAULT_MAX_NODES` arrays and
+    /// objects and otherwise convert like `for-in` does
+    ///
+
+    pub fn new() -> ConversionOptions {
+        ConversionOptions::default()
+    }
+
+    ///
+    /// Set how objects are handled that are reached more than once
+    ///
+    /// # Arguments
+    ///
+    /// * `cycles` - Either fail on cycles or keep references
+    ///
+    /// # Example
+    ///
+    ///     use rubtle_lib::{ConversionOptions, CycleHandling, Rubtle};
+    ///
+    ///     let rubtle = Rubtle::new();
+    ///     let options = ConversionOptions::new().with_cycles(CycleHandling::Reference);
+    ///
+    ///     rubtle.eval("var rubtle = {}; void (rubtle.self = rubtle);").unwrap();
+    ///
+    ///     let rval = rubtle.get_global_value_with("rubtle", &options).unwrap();
+    ///
+
+    pub fn with_cycles(mut self, cycles: CycleHandling) -> Self {
+        self.cycles = cycles;
+
+        self
+    }
+
+    ///
+    /// Set maximum nesting of arrays and objects
+    ///
+    /// # Arguments
+    ///
+    /// * `max_depth` - Maximum depth; deeper values fail with `Limit::Depth`
+    ///
+
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+
+        self
+    }
+
+    ///
+    /// Set maximum number of converted arrays and objects
+    ///
+    /// # Arguments
+    ///
+    /// * `max_nodes` - Maximum number; shared objects count on every visit
+    ///   and more fail with `Limit::Nodes`
+    ///
+    /// # Example
+    ///
+    ///     use rubtle_lib::{ConversionOptions, Error, Limit, Rubtle};
+    ///
+    ///     let rubtle = Rubtle::new();
+    ///     let options = ConversionOptions::new().with_max_nodes(100);
+    ///
+    ///     rubtle
+    ///         .eval("var rubtle = []; for (var i = 0; i < 40; i++) void (rubtle = [rubtle, rubtle]);")
+    ///         .unwrap();
+    ///
+    ///     assert_eq!(
+    ///         Err(Error::Limit(Limit::Nodes)),
+    ///         rubtle.get_global_value_with("rubtle", &options)
+    ///     );
+    ///
+
+    pub fn with_max_nodes(mut self, max_nodes: usize) -> Self {
+        self.max_nodes = max_nodes;
+
+        self
+    }
+
+    ///
+    /// Set whether only own properties are converted
+    ///
+    /// # Arguments
+    ///
+    /// * `own_only` - Skip properties inherited from prototypes
+    ///
+    /// # Example
+    ///
+    ///     use rubtle_lib::{ConversionOptions, Rubtle, Value};
+    ///
+    ///     let rubtle = Rubtle::new();
+    ///     let options = ConversionOptions::new().with_own_only(true);
+    ///
+    ///     rubtle.eval("var rubtle = Object.create({ a: 1 }); rubtle.b = 2;").unwrap();
+    ///
+    ///     match rubtle.get_global_value_with("rubtle", &options).unwrap() {
+    ///         Value::Object(hash) => assert!(!hash.contains_key("a")),
+    ///         _ => panic!("Expected object"),
+    ///     }
+    ///
+
+    pub fn with_own_only(mut self, own_only: bool) -> Self {
+        self.own_only = own_only;
+
+        self
+    }
+
+    ///
+    /// Set whether symbol keys are converted
+    ///
+    /// # Arguments
+    ///
+    /// * `symbols` - Include symbol keys as `Symbol(description)`
+    ///
+
+    pub fn with_symbols(mut self, symbols: bool) -> Self {
+        self.symbols = symbols;
+
+        self
+    }
+
+    ///
+    /// Set whether non-enumerable properties are converted
+    ///
+    /// # Arguments
+    ///
+    /// * `non_enumerable` - Include non-enumerable properties
+    ///
+
+    pub fn with_non_enumerable(mut self, non_enumerable: bool) -> Self {
+        self.non_enumerable = non_enumerable;
+
+        self
+    }
+
+    ///
+    /// Set whether getters of accessor properties are invoked
+    ///
+    /// # Arguments
+    ///
+    /// * `accessors` - Invoke getters; otherwise accessor properties are skipped
+    ///   and accessor elements of arrays are converted to `null`
+    ///
+
+    pub fn with_accessors(mut self, accessors: bool) -> Self {
+        self.accessors = accessors;
+
+        self
+    }
+
+    ///
+    /// Set how functions inside of arrays and objects are handled
+    ///
+    /// # Arguments
+    ///
+    /// * `functions` - Either keep, skip or fail on functions
+    ///
+
+    pub fn with_functions(mut self, functions: FunctionHandling) -> Self {
+        self.functions = functions;
+
+        self
+    }
+
+    ///
+    /// Return enum flags for objects
+    ///
+    /// # Returns
+    ///
+    /// Flags for `duk_enum`
+    ///
+
+    pub(crate) fn enum_flags(&self) -> ffi::duk_uint_t {
+        let mut flags = 0;
+
+        if self.own_only {
+            flags |= ffi::DUK_ENUM_OWN_PROPERTIES_ONLY;
+        }
+
+        if self.symbols {
+            flags |= ffi::DUK_ENUM_INCLUDE_SYMBOLS;
+        }
+
+        if self.non_enumerable {
+            flags |= ffi::DUK_ENUM_INCLUDE_NONENUMERABLE;
+        }
+
+        flags
+    }
+}
+
+impl<'a> ConversionState<'a> {
+    ///
+    /// Create state for a new conversion
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - Options of the conversion
+    ///
+    /// # Returns
+    ///
+    /// A new `ConversionState`
+    ///
+
+    pub(crate) fn new(options: &'a ConversionOptions) -> ConversionState<'a> {
+        ConversionState {
+            options,
+            ancestors: Vec::new(),
+            visited: HashSet::new(),
+            nodes: 0,
+        }
+    }
+
+    ///
+    /// Enter object before converting its properties
+    ///
+    /// # Arguments
+    ///
+    /// * `ptr` - Heap pointer of the object
+    ///
+    /// # Returns
+    ///
+    /// `true` if the object must be converted, `false` if it must be kept as
+    /// reference or the `Error` on cycles, too deep nesting and too many nodes
+    ///
+
+    pub(crate) fn enter(&mut self, ptr: *mut c_void) -> Result<bool, Error> {
+        match self.options.cycles {
+            CycleHandling::Error if self.ancestors.contains(&ptr) => {
+                return Err(Error::Conversion {
+                    expected: "acyclic object",
+                    actual: "cyclic object",
+                });
+            }
+            CycleHandling::Reference if !self.visited.insert(ptr) => return Ok(false),
+            _ => (),
+        }
+
+        if self.ancestors.len() >= self.options.max_depth {
+            return Err(Error::Limit(Limit::Depth));
+        }
+
+        if self.nodes >= self.options.max_nodes {
+            return Err(Error::Limit(Limit::Nodes));
+        }
+
+        self.nodes += 1;
+
+        self.ancestors.push(ptr);
+
+        Ok(true)
+    }
+
+    ///
+    /// Leave object after converting its properties
+    ///
+
+    pub(crate) fn leave(&mut self) {
+        self.ancestors.pop();
+    }
+}
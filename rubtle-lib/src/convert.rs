@@ -0,0 +1,755 @@
+///
+/// @package Rubtle-Lib
+///
+/// @file Conversion traits
+/// @copyright 2020-present Christoph Kappel <christoph@unexist.dev>
+/// @version $Id$
+///
+/// This program can be distributed under the terms of the GNU GPLv2.
+/// See the file LICENSE for details.
+//
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+use std::hash::{BuildHasher, Hash};
+use std::os::raw::c_char;
+use std::slice;
+use std::time::SystemTime;
+
+use cesu8::{from_cesu8, to_cesu8};
+
+use crate::{Error, Function, JsFunction, JsObject, Rubtle, Value};
+
+/// Type that can be pushed onto the Duktape stack
+///
+/// Implementations must push exactly one value. User types can delegate to
+/// the impls of the std types or use `Rubtle::push_object_fields`. Handles
+/// of another or a destroyed heap are pushed as undefined.
+pub trait ToJs {
+    ///
+    /// Push value onto the stack
+    ///
+    /// # Arguments
+    ///
+    /// * `rubtle` - Rubtle instance to push onto
+    ///
+
+    fn to_js(&self, rubtle: &Rubtle);
+
+    ///
+    /// Push value onto the stack unless it holds unusable handles
+    ///
+    /// # Arguments
+    ///
+    /// * `rubtle` - Rubtle instance to push onto
+    ///
+    /// # Returns
+    ///
+    /// Either nothing or `Error::Heap` for handles of another or a destroyed
+    /// heap; nothing is pushed on errors
+    ///
+
+    fn try_to_js(&self, rubtle: &Rubtle) -> Result<(), Error> {
+        self.to_js(rubtle);
+
+        Ok(())
+    }
+}
+
+/// Type that can be read from the Duktape stack
+///
+/// Implementations must leave the stack as they found it. User types can
+/// delegate to the impls of the std types or use `Rubtle::get_object_field`.
+pub trait FromJs: Sized {
+    ///
+    /// Read value on given index from the stack
+    ///
+    /// # Arguments
+    ///
+    /// * `rubtle` - Rubtle instance to read from
+    /// * `idx` - Stack index; the value stays on the stack
+    ///
+    /// # Returns
+    ///
+    /// Either the converted value or a conversion `Error`
+    ///
+
+    fn from_js(rubtle: &Rubtle, idx: ffi::duk_idx_t) -> Result<Self, Error>;
+}
+
+///
+/// Return type name of value on given index like `Value::type_name`
+///
+/// # Arguments
+///
+/// * `ctx` - Duktape context
+/// * `idx` - Stack index
+///
+/// # Returns
+///
+/// Name of the type
+///
+
+pub(crate) unsafe fn type_name_at(ctx: *mut ffi::duk_context, idx: ffi::duk_idx_t) -> &'static str {
+    let mut timeval: ffi::duk_double_t = 0.0;
+
+    match ffi::duk_get_type(ctx, idx) as u32 {
+        ffi::DUK_TYPE_UNDEFINED => "undefined",
+        ffi::DUK_TYPE_NULL => "null",
+        ffi::DUK_TYPE_BOOLEAN => "boolean",
+        ffi::DUK_TYPE_NUMBER => "number",
+        ffi::DUK_TYPE_STRING => "string",
+        ffi::DUK_TYPE_BUFFER => "Uint8Array",
+        ffi::DUK_TYPE_POINTER => "pointer",
+        ffi::DUK_TYPE_LIGHTFUNC => "function",
+        ffi::DUK_TYPE_OBJECT => {
+            if 1 == ffi::duk_is_array(ctx, idx) {
+                "array"
+            } else if 1 == ffi::duk_is_function(ctx, idx) {
+                "function"
+            } else if 1 == ffi::rust_duk_get_date(ctx, idx, &mut timeval) {
+                "Date"
+            } else if 1 == ffi::rust_duk_is_byte_buffer(ctx, idx) {
+                "Uint8Array"
+            } else {
+                "object"
+            }
+        }
+        _ => "none",
+    }
+}
+
+///
+/// Create conversion error for value on given index
+///
+/// # Arguments
+///
+/// * `rubtle` - Rubtle instance to read from
+/// * `idx` - Stack index
+/// * `expected` - Name of the expected type
+///
+/// # Returns
+///
+/// A new `Error::Conversion`
+///
+
+fn conversion_error(rubtle: &Rubtle, idx: ffi::duk_idx_t, expected: &'static str) -> Error {
+    Error::Conversion {
+        expected,
+        actual: unsafe { type_name_at(rubtle.ctx, idx) },
+    }
+}
+
+///
+/// Push string onto the stack
+///
+/// # Arguments
+///
+/// * `ctx` - Duktape context
+/// * `val` - String to push; may contain NUL characters
+///
+
+pub(crate) unsafe fn push_str(ctx: *mut ffi::duk_context, val: &str) {
+    let bytes = to_cesu8(val);
+
+    ffi::duk_require_stack(ctx, 1);
+    ffi::duk_push_lstring(ctx, bytes.as_ptr() as *const c_char, bytes.len() as ffi::duk_size_t);
+}
+
+///
+/// Push value onto the stack or undefined if it holds unusable handles
+///
+/// # Arguments
+///
+/// * `rubtle` - Rubtle instance to push onto
+/// * `rval` - Value to push
+///
+
+fn push_or_undefined<T: ToJs + ?Sized>(rubtle: &Rubtle, rval: &T) {
+    if rval.try_to_js(rubtle).is_err() {
+        unsafe {
+            ffi::duk_require_stack(rubtle.ctx, 1);
+            ffi::duk_push_undefined(rubtle.ctx);
+        }
+    }
+}
+
+///
+/// Read string on given index without coercion
+///
+/// # Arguments
+///
+/// * `ctx` - Duktape context
+/// * `idx` - Stack index
+///
+/// # Returns
+///
+/// `Option` with the string if the value is a valid string; otherwise `None`
+///
+
+pub(crate) unsafe fn get_str_at(ctx: *mut ffi::duk_context, idx: ffi::duk_idx_t) -> Option<String> {
+    let mut len = 0;
+
+    if 1 != ffi::duk_is_string(ctx, idx) {
+        return None;
+    }
+
+    let dval = ffi::duk_get_lstring(ctx, idx, &mut len);
+    let bytes = slice::from_raw_parts(dval as *const u8, len as usize);
+
+    from_cesu8(bytes).ok().map(|string| string.into_owned())
+}
+
+///
+/// Read property key on given index
+///
+/// # Arguments
+///
+/// * `ctx` - Duktape context
+/// * `idx` - Stack index
+///
+/// # Returns
+///
+/// `Option` with the key; symbols are named `Symbol(description)` like
+/// `String(symbol)` does
+///
+
+pub(crate) unsafe fn get_key_at(ctx: *mut ffi::duk_context, idx: ffi::duk_idx_t) -> Option<String> {
+    let mut len = 0;
+
+    if 1 != ffi::duk_is_symbol(ctx, idx) {
+        return get_str_at(ctx, idx);
+    }
+
+    /* Symbols start with a marker byte and end the description with 0xFF */
+    let dval = ffi::duk_get_lstring(ctx, idx, &mut len);
+    let bytes = slice::from_raw_parts(dval as *const u8, len as usize);
+    let desc = bytes[1..].split(|&b| 0xFF == b).next().unwrap_or(&[]);
+
+    from_cesu8(desc)
+        .ok()
+        .map(|string| format!("Symbol({})", string))
+}
+
+///
+/// Read primitive value of given Duktape type
+///
+/// # Arguments
+///
+/// * `rubtle` - Rubtle instance to read from
+/// * `idx` - Stack index
+/// * `duk_type` - Expected Duktape type
+/// * `expected` - Name of the expected type
+///
+/// # Returns
+///
+/// Either the converted value or a conversion `Error`
+///
+
+fn primitive_from_js<T>(
+    rubtle: &Rubtle,
+    idx: ffi::duk_idx_t,
+    duk_type: u32,
+    expected: &'static str,
+) -> Result<T, Error>
+where
+    T: TryFrom<Value, Error = Error>,
+{
+    unsafe {
+        if duk_type == ffi::duk_get_type(rubtle.ctx, idx) as u32 {
+            ffi::duk_require_stack(rubtle.ctx, 1);
+            ffi::duk_dup(rubtle.ctx, idx);
+
+            T::try_from(rubtle.pop_value().unwrap_or(Value::None))
+        } else {
+            Err(conversion_error(rubtle, idx, expected))
+        }
+    }
+}
+
+///
+/// Read property on given index and convert it
+///
+/// # Arguments
+///
+/// * `rubtle` - Rubtle instance to read from
+/// * `idx` - Stack index of the array
+/// * `prop_idx` - Index of the property
+///
+/// # Returns
+///
+/// Either the converted value, a conversion `Error` or the exception
+/// thrown by a getter
+///
+
+fn index_from_js<T: FromJs>(
+    rubtle: &Rubtle,
+    idx: ffi::duk_idx_t,
+    prop_idx: usize,
+) -> Result<T, Error> {
+    unsafe {
+        ffi::duk_require_stack(rubtle.ctx, 1);
+        ffi::duk_dup(rubtle.ctx, idx);
+
+        /* Getters and proxy traps may throw */
+        rubtle.get_index_top(prop_idx as ffi::duk_uarridx_t)?;
+
+        let result = T::from_js(rubtle, -1);
+
+        ffi::duk_pop(rubtle.ctx);
+
+        result
+    }
+}
+
+///
+/// Read enumerable properties of object on given index
+///
+/// # Arguments
+///
+/// * `rubtle` - Rubtle instance to read from
+/// * `idx` - Stack index of the object
+/// * `insert` - Called with every key and converted value
+///
+/// # Returns
+///
+/// Either nothing, the first conversion `Error` or the exception thrown by
+/// a getter
+///
+
+fn entries_from_js<V, F>(rubtle: &Rubtle, idx: ffi::duk_idx_t, mut insert: F) -> Result<(), Error>
+where
+    V: FromJs,
+    F: FnMut(String, V),
+{
+    unsafe {
+        if 1 != ffi::duk_is_object(rubtle.ctx, idx) || 1 == ffi::duk_is_array(rubtle.ctx, idx) {
+            return Err(conversion_error(rubtle, idx, "object"));
+        }
+
+        ffi::duk_require_stack(rubtle.ctx, 1);
+        ffi::duk_dup(rubtle.ctx, idx);
+
+        /* Getters and proxy traps may throw */
+        if let Err(err) = rubtle.push_enum_top(0) {
+            ffi::duk_pop(rubtle.ctx);
+
+            return Err(err);
+        }
+
+        let mut result = Ok(());
+
+        loop {
+            match rubtle.next_enum_top(true) {
+                Ok(true) => (),
+                Ok(false) => break,
+                Err(err) => {
+                    result = Err(err);
+
+                    break;
+                }
+            }
+
+            result = V::from_js(rubtle, -1).map(|value| {
+                if let Some(key) = get_str_at(rubtle.ctx, -2) {
+                    insert(key, value);
+                }
+            });
+
+            /* Remove key and value */
+            ffi::duk_pop_2(rubtle.ctx);
+
+            if result.is_err() {
+                break;
+            }
+        }
+
+        /* Remove enum and object */
+        ffi::duk_pop_2(rubtle.ctx);
+
+        result
+    }
+}
+
+impl ToJs for Value {
+    fn to_js(&self, rubtle: &Rubtle) {
+        push_or_undefined(rubtle, self);
+    }
+
+    fn try_to_js(&self, rubtle: &Rubtle) -> Result<(), Error> {
+        rubtle.try_push_value(self)
+    }
+}
+
+impl FromJs for Value {
+    fn from_js(rubtle: &Rubtle, idx: ffi::duk_idx_t) -> Result<Value, Error> {
+        unsafe {
+            ffi::duk_require_stack(rubtle.ctx, 1);
+            ffi::duk_dup(rubtle.ctx, idx);
+
+            match rubtle.pop_value() {
+                Some(value) => Ok(value),
+                None => {
+                    let err = conversion_error(rubtle, -1, "value");
+
+                    ffi::duk_pop(rubtle.ctx);
+
+                    Err(err)
+                }
+            }
+        }
+    }
+}
+
+impl ToJs for () {
+    fn to_js(&self, rubtle: &Rubtle) {
+        unsafe {
+            ffi::duk_require_stack(rubtle.ctx, 1);
+            ffi::duk_push_undefined(rubtle.ctx);
+        }
+    }
+}
+
+impl FromJs for () {
+    fn from_js(rubtle: &Rubtle, idx: ffi::duk_idx_t) -> Result<(), Error> {
+        primitive_from_js(rubtle, idx, ffi::DUK_TYPE_UNDEFINED, "undefined")
+    }
+}
+
+impl ToJs for bool {
+    fn to_js(&self, rubtle: &Rubtle) {
+        unsafe {
+            ffi::duk_require_stack(rubtle.ctx, 1);
+            ffi::duk_push_boolean(rubtle.ctx, *self as ffi::duk_bool_t);
+        }
+    }
+}
+
+impl FromJs for bool {
+    fn from_js(rubtle: &Rubtle, idx: ffi::duk_idx_t) -> Result<bool, Error> {
+        primitive_from_js(rubtle, idx, ffi::DUK_TYPE_BOOLEAN, "boolean")
+    }
+}
+
+macro_rules! convert_number_type {
+    ($num_type: ty) => {
+        impl ToJs for $num_type {
+            fn to_js(&self, rubtle: &Rubtle) {
+                unsafe {
+                    ffi::duk_require_stack(rubtle.ctx, 1);
+                    ffi::duk_push_number(rubtle.ctx, *self as ffi::duk_double_t);
+                }
+            }
+        }
+
+        impl FromJs for $num_type {
+            fn from_js(rubtle: &Rubtle, idx: ffi::duk_idx_t) -> Result<$num_type, Error> {
+                primitive_from_js(rubtle, idx, ffi::DUK_TYPE_NUMBER, "number")
+            }
+        }
+    };
+}
+
+convert_number_type!(i8);
+convert_number_type!(i16);
+convert_number_type!(i32);
+convert_number_type!(i64);
+convert_number_type!(u8);
+convert_number_type!(u16);
+convert_number_type!(u32);
+convert_number_type!(u64);
+convert_number_type!(usize);
+convert_number_type!(f32);
+convert_number_type!(f64);
+
+impl ToJs for str {
+    fn to_js(&self, rubtle: &Rubtle) {
+        unsafe {
+            push_str(rubtle.ctx, self);
+        }
+    }
+}
+
+impl ToJs for String {
+    fn to_js(&self, rubtle: &Rubtle) {
+        self.as_str().to_js(rubtle);
+    }
+}
+
+impl FromJs for String {
+    fn from_js(rubtle: &Rubtle, idx: ffi::duk_idx_t) -> Result<String, Error> {
+        unsafe { get_str_at(rubtle.ctx, idx) }
+            .ok_or_else(|| conversion_error(rubtle, idx, "string"))
+    }
+}
+
+impl ToJs for SystemTime {
+    fn to_js(&self, rubtle: &Rubtle) {
+        push_or_undefined(rubtle, &Value::Date(*self));
+    }
+}
+
+impl FromJs for SystemTime {
+    fn from_js(rubtle: &Rubtle, idx: ffi::duk_idx_t) -> Result<SystemTime, Error> {
+        let mut timeval: ffi::duk_double_t = 0.0;
+
+        if 1 == unsafe { ffi::rust_duk_get_date(rubtle.ctx, idx, &mut timeval) } {
+            SystemTime::try_from(Value::from_js(rubtle, idx)?)
+        } else {
+            Err(conversion_error(rubtle, idx, "Date"))
+        }
+    }
+}
+
+impl ToJs for Function<i8> {
+    fn to_js(&self, rubtle: &Rubtle) {
+        push_or_undefined(rubtle, self);
+    }
+
+    fn try_to_js(&self, rubtle: &Rubtle) -> Result<(), Error> {
+        rubtle.try_push_value(&Value::Function(self.clone()))
+    }
+}
+
+impl ToJs for JsFunction {
+    fn to_js(&self, rubtle: &Rubtle) {
+        push_or_undefined(rubtle, self);
+    }
+
+    fn try_to_js(&self, rubtle: &Rubtle) -> Result<(), Error> {
+        rubtle.try_push_value(&Value::Function(self.clone().into()))
+    }
+}
+
+impl FromJs for JsFunction {
+    fn from_js(rubtle: &Rubtle, idx: ffi::duk_idx_t) -> Result<JsFunction, Error> {
+        unsafe {
+            if 1 == ffi::duk_is_function(rubtle.ctx, idx) {
+                ffi::duk_require_stack(rubtle.ctx, 1);
+                ffi::duk_dup(rubtle.ctx, idx);
+
+                Ok(JsFunction::pop(rubtle.ctx))
+            } else {
+                Err(conversion_error(rubtle, idx, "function"))
+            }
+        }
+    }
+}
+
+impl ToJs for JsObject {
+    fn to_js(&self, rubtle: &Rubtle) {
+        push_or_undefined(rubtle, self);
+    }
+
+    fn try_to_js(&self, rubtle: &Rubtle) -> Result<(), Error> {
+        rubtle.try_push_value(&Value::Ref(self.clone()))
+    }
+}
+
+impl FromJs for JsObject {
+    fn from_js(rubtle: &Rubtle, idx: ffi::duk_idx_t) -> Result<JsObject, Error> {
+        unsafe {
+            if 1 == ffi::duk_is_object(rubtle.ctx, idx) {
+                ffi::duk_require_stack(rubtle.ctx, 1);
+                ffi::duk_dup(rubtle.ctx, idx);
+
+                Ok(JsObject::pop(rubtle.ctx))
+            } else {
+                Err(conversion_error(rubtle, idx, "object"))
+            }
+        }
+    }
+}
+
+impl<'a, T: ToJs + ?Sized> ToJs for &'a T {
+    fn to_js(&self, rubtle: &Rubtle) {
+        (**self).to_js(rubtle);
+    }
+
+    fn try_to_js(&self, rubtle: &Rubtle) -> Result<(), Error> {
+        (**self).try_to_js(rubtle)
+    }
+}
+
+impl<T: ToJs + ?Sized> ToJs for Box<T> {
+    fn to_js(&self, rubtle: &Rubtle) {
+        (**self).to_js(rubtle);
+    }
+
+    fn try_to_js(&self, rubtle: &Rubtle) -> Result<(), Error> {
+        (**self).try_to_js(rubtle)
+    }
+}
+
+impl<T: ToJs> ToJs for Option<T> {
+    fn to_js(&self, rubtle: &Rubtle) {
+        match self {
+            Some(val) => val.to_js(rubtle),
+            None => unsafe {
+                ffi::duk_require_stack(rubtle.ctx, 1);
+                ffi::duk_push_null(rubtle.ctx);
+            },
+        }
+    }
+
+    fn try_to_js(&self, rubtle: &Rubtle) -> Result<(), Error> {
+        match self {
+            Some(val) => val.try_to_js(rubtle),
+            None => {
+                self.to_js(rubtle);
+
+                Ok(())
+            }
+        }
+    }
+}
+
+impl<T: FromJs> FromJs for Option<T> {
+    fn from_js(rubtle: &Rubtle, idx: ffi::duk_idx_t) -> Result<Option<T>, Error> {
+        match unsafe { ffi::duk_get_type(rubtle.ctx, idx) as u32 } {
+            ffi::DUK_TYPE_UNDEFINED | ffi::DUK_TYPE_NULL => Ok(None),
+            _ => T::from_js(rubtle, idx).map(Some),
+        }
+    }
+}
+
+impl<T: ToJs> ToJs for [T] {
+    fn to_js(&self, rubtle: &Rubtle) {
+        unsafe {
+            ffi::duk_require_stack(rubtle.ctx, 2);
+            ffi::duk_push_array(rubtle.ctx);
+
+            for (idx, val) in self.iter().enumerate() {
+                val.to_js(rubtle);
+
+                ffi::duk_put_prop_index(rubtle.ctx, -2, idx as ffi::duk_uarridx_t);
+            }
+        }
+    }
+}
+
+impl<T: ToJs> ToJs for Vec<T> {
+    fn to_js(&self, rubtle: &Rubtle) {
+        self.as_slice().to_js(rubtle);
+    }
+}
+
+impl<T: FromJs> FromJs for Vec<T> {
+    fn from_js(rubtle: &Rubtle, idx: ffi::duk_idx_t) -> Result<Vec<T>, Error> {
+        unsafe {
+            if 1 != ffi::duk_is_array(rubtle.ctx, idx) {
+                return Err(conversion_error(rubtle, idx, "array"));
+            }
+
+            let idx = ffi::duk_normalize_index(rubtle.ctx, idx);
+            let len = ffi::duk_get_length(rubtle.ctx, idx) as usize;
+
+            (0..len).map(|i| index_from_js(rubtle, idx, i)).collect()
+        }
+    }
+}
+
+macro_rules! convert_tuple_type {
+    ($len: literal => $($name: ident: $idx: tt),+) => {
+        impl<$($name: ToJs),+> ToJs for ($($name,)+) {
+            fn to_js(&self, rubtle: &Rubtle) {
+                unsafe {
+                    ffi::duk_require_stack(rubtle.ctx, 2);
+                    ffi::duk_push_array(rubtle.ctx);
+
+                    $(
+                        self.$idx.to_js(rubtle);
+
+                        ffi::duk_put_prop_index(rubtle.ctx, -2, $idx);
+                    )+
+                }
+            }
+        }
+
+        impl<$($name: FromJs),+> FromJs for ($($name,)+) {
+            fn from_js(rubtle: &Rubtle, idx: ffi::duk_idx_t) -> Result<($($name,)+), Error> {
+                unsafe {
+                    if 1 != ffi::duk_is_array(rubtle.ctx, idx)
+                        || $len != ffi::duk_get_length(rubtle.ctx, idx)
+                    {
+                        return Err(conversion_error(rubtle, idx, concat!("array of length ", $len)));
+                    }
+
+                    let idx = ffi::duk_normalize_index(rubtle.ctx, idx);
+
+                    Ok(($(index_from_js::<$name>(rubtle, idx, $idx)?,)+))
+                }
+            }
+        }
+    };
+}
+
+convert_tuple_type!(1 => A: 0);
+convert_tuple_type!(2 => A: 0, B: 1);
+convert_tuple_type!(3 => A: 0, B: 1, C: 2);
+convert_tuple_type!(4 => A: 0, B: 1, C: 2, D: 3);
+convert_tuple_type!(5 => A: 0, B: 1, C: 2, D: 3, E: 4);
+convert_tuple_type!(6 => A: 0, B: 1, C: 2, D: 3, E: 4, F: 5);
+
+///
+/// Push entries as object onto the stack
+///
+/// # Arguments
+///
+/// * `rubtle` - Rubtle instance to push onto
+/// * `entries` - Keys and values of the object
+///
+
+fn push_entries<'a, K, V, I>(rubtle: &Rubtle, entries: I)
+where
+    K: AsRef<str> + 'a,
+    V: ToJs + 'a,
+    I: Iterator<Item = (&'a K, &'a V)>,
+{
+    unsafe {
+        ffi::duk_require_stack(rubtle.ctx, 3);
+        ffi::duk_push_object(rubtle.ctx);
+
+        for (key, val) in entries {
+            push_str(rubtle.ctx, key.as_ref());
+            val.to_js(rubtle);
+
+            ffi::duk_put_prop(rubtle.ctx, -3);
+        }
+    }
+}
+
+impl<K: AsRef<str>, V: ToJs> ToJs for BTreeMap<K, V> {
+    fn to_js(&self, rubtle: &Rubtle) {
+        push_entries(rubtle, self.iter());
+    }
+}
+
+impl<V: FromJs> FromJs for BTreeMap<String, V> {
+    fn from_js(rubtle: &Rubtle, idx: ffi::duk_idx_t) -> Result<BTreeMap<String, V>, Error> {
+        let mut map = BTreeMap::new();
+
+        entries_from_js(rubtle, idx, |key, value| {
+            map.insert(key, value);
+        })?;
+
+        Ok(map)
+    }
+}
+
+impl<K: AsRef<str> + Eq + Hash, V: ToJs, S: BuildHasher> ToJs for HashMap<K, V, S> {
+    fn to_js(&self, rubtle: &Rubtle) {
+        push_entries(rubtle, self.iter());
+    }
+}
+
+impl<V: FromJs, S: BuildHasher + Default> FromJs for HashMap<String, V, S> {
+    fn from_js(rubtle: &Rubtle, idx: ffi::duk_idx_t) -> Result<HashMap<String, V, S>, Error> {
+        let mut map = HashMap::default();
+
+        entries_from_js(rubtle, idx, |key, value| {
+            map.insert(key, value);
+        })?;
+
+        Ok(map)
+    }
+}
@@ -0,0 +1,706 @@
+///
+/// @package Rubtle-Lib
+///
+/// @file Serde deserializer functions
+/// @copyright 2020-present Christoph Kappel <christoph@unexist.dev>
+/// @version $Id$
+///
+/// This program can be distributed under the terms of the GNU GPLv2.
+/// See the file LICENSE for details.
+//
+use std::cell::RefCell;
+use std::fmt;
+use std::slice;
+use std::vec;
+
+use serde::de::{self, Deserialize, DeserializeOwned, DeserializeSeed, IntoDeserializer, Visitor};
+use serde::forward_to_deserialize_any;
+
+use crate::conversion::{ConversionOptions, ConversionState};
+use crate::convert;
+use crate::object_map;
+use crate::value;
+use crate::{Error, ObjectMap, Rubtle, Value};
+
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Error {
+        Error::Serde(msg.to_string())
+    }
+}
+
+///
+/// Deserialize any `Deserialize` type from a `Value`
+///
+/// # Arguments
+///
+/// * `rval` - Value to deserialize
+///
+/// # Returns
+///
+/// Either the deserialized value or the deserialization `Error`
+///
+/// # Example
+///
+///     use rubtle_lib::{from_value, Value};
+///
+///     let rval = Value::Array(vec![Value::from(1), Value::from("rubtle")]);
+///
+///     let (num, name): (u8, String) = from_value(rval).unwrap();
+///
+///     assert_eq!(1, num);
+///     assert_eq!("rubtle", name);
+///
+
+pub fn from_value<T: DeserializeOwned>(rval: Value) -> Result<T, Error> {
+    T::deserialize(rval)
+}
+
+///
+/// Deserialize any `Deserialize` type directly from top of the stack
+///
+/// # Arguments
+///
+/// * `rubtle` - Rubtle instance to pop from
+///
+/// # Returns
+///
+/// Either the deserialized value or the deserialization `Error`; the value
+/// is removed from the stack in both cases
+///
+/// Cycles and limits are handled like the default `ConversionOptions` do.
+///
+
+pub(crate) fn pop_deserialize<T: DeserializeOwned>(rubtle: &Rubtle) -> Result<T, Error> {
+    unsafe {
+        let top = ffi::duk_get_top(rubtle.ctx);
+
+        if 0 == top {
+            return Err(Error::Serde(String::from("Stack is empty")));
+        }
+
+        let options = ConversionOptions::default();
+        let state = RefCell::new(ConversionState::new(&options));
+
+        let result = T::deserialize(StackDeserializer::top(rubtle, &state));
+
+        ffi::duk_set_top(rubtle.ctx, top - 1);
+
+        result
+    }
+}
+
+///
+/// Visit number as integer if it has no fraction
+///
+/// # Arguments
+///
+/// * `val` - Number to visit
+/// * `visitor` - Visitor to call
+///
+/// # Returns
+///
+/// Result of the visitor
+///
+
+fn visit_number<'de, V: Visitor<'de>>(val: f64, visitor: V) -> Result<V::Value, Error> {
+    if 0.0 == val.fract() && val >= i64::MIN as f64 && val < i64::MAX as f64 {
+        if 0.0 > val {
+            visitor.visit_i64(val as i64)
+        } else {
+            visitor.visit_u64(val as u64)
+        }
+    } else {
+        visitor.visit_f64(val)
+    }
+}
+
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Value, D::Error> {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+/// Visitor creating a `Value` from any format
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "any value")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Value, E> {
+        Ok(Value::Boolean(v))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Value, E> {
+        Ok(Value::from(v))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Value, E> {
+        Ok(Value::from(v))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Value, E> {
+        Ok(Value::Number(v))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Value, E> {
+        Ok(Value::from(v))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Value, E> {
+        Ok(Value::Str(v))
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Value, E> {
+        Ok(Value::from(v))
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<Value, E> {
+        Ok(Value::Bytes(v))
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_some<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        Value::deserialize(deserializer)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        let mut vec = Vec::new();
+
+        while let Some(elem) = seq.next_element()? {
+            vec.push(elem);
+        }
+
+        Ok(Value::Array(vec))
+    }
+
+    fn visit_map<A: de::MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+        let mut hash = ObjectMap::new();
+
+        while let Some((key, value)) = map.next_entry::<String, Value>()? {
+            hash.insert(key, value);
+        }
+
+        Ok(Value::Object(hash))
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for Value {
+    type Deserializer = Value;
+
+    fn into_deserializer(self) -> Value {
+        self
+    }
+}
+
+impl<'de> de::Deserializer<'de> for Value {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Value::None | Value::Null => visitor.visit_unit(),
+            Value::Boolean(val) => visitor.visit_bool(val),
+            Value::Number(val) => visit_number(val, visitor),
+            Value::Str(val) => visitor.visit_string(val),
+            Value::Array(val) => visitor.visit_seq(ValueSeq {
+                iter: val.into_iter(),
+            }),
+            Value::Object(val) => visitor.visit_map(ValueMap {
+                iter: val.into_iter(),
+                value: None,
+            }),
+            Value::Bytes(val) => visitor.visit_byte_buf(val),
+            Value::Date(val) => visitor.visit_f64(value::to_time_value(&val)),
+            Value::Function(_) | Value::External(_) | Value::Ref(_) => Err(Error::Serde(format!(
+                "Cannot deserialize {}",
+                self.type_name()
+            ))),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Value::None | Value::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        match self {
+            Value::Str(variant) => visitor.visit_enum(variant.into_deserializer()),
+            Value::Object(hash) if 1 == hash.len() => {
+                let (variant, value) = hash.into_iter().next().unwrap();
+
+                visitor.visit_enum(ValueEnum { variant, value })
+            }
+            _ => Err(self.conversion_error("enum")),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+/// Access to the elements of a `Value::Array`
+struct ValueSeq {
+    iter: vec::IntoIter<Value>,
+}
+
+impl<'de> de::SeqAccess<'de> for ValueSeq {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Error> {
+        match self.iter.next() {
+            Some(value) => seed.deserialize(value).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+/// Access to the entries of a `Value::Object`
+struct ValueMap {
+    iter: object_map::IntoIter,
+
+    /// Value of the pending entry
+    value: Option<Value>,
+}
+
+impl<'de> de::MapAccess<'de> for ValueMap {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, Error> {
+        match self.iter.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+
+                seed.deserialize(Value::Str(key)).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        seed.deserialize(self.value.take().unwrap_or(Value::None))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+/// Access to an enum wrapped into an object with the variant as key
+struct ValueEnum {
+    variant: String,
+    value: Value,
+}
+
+impl<'de> de::EnumAccess<'de> for ValueEnum {
+    type Error = Error;
+    type Variant = Value;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Value), Error> {
+        let variant = seed.deserialize(Value::Str(self.variant))?;
+
+        Ok((variant, self.value))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for Value {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        match self {
+            Value::None | Value::Null => Ok(()),
+            _ => Err(self.conversion_error("null")),
+        }
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Error> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Error> {
+        de::Deserializer::deserialize_seq(self, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        de::Deserializer::deserialize_map(self, visitor)
+    }
+}
+
+/// Deserializer directly from the stack
+struct StackDeserializer<'a> {
+    rubtle: &'a Rubtle,
+
+    /// Absolute stack index of the value
+    idx: ffi::duk_idx_t,
+
+    /// State shared by all nested deserializers
+    state: &'a RefCell<ConversionState<'a>>,
+}
+
+impl<'a> StackDeserializer<'a> {
+    ///
+    /// Create deserializer for value on top of the stack
+    ///
+    /// # Arguments
+    ///
+    /// * `rubtle` - Rubtle instance to read from
+    /// * `state` - State of the deserialization
+    ///
+    /// # Returns
+    ///
+    /// A new `StackDeserializer`
+    ///
+
+    fn top(
+        rubtle: &'a Rubtle,
+        state: &'a RefCell<ConversionState<'a>>,
+    ) -> StackDeserializer<'a> {
+        StackDeserializer {
+            rubtle,
+            idx: unsafe { ffi::duk_get_top_index(rubtle.ctx) },
+            state,
+        }
+    }
+
+    ///
+    /// Visit array or object and track cycles and limits
+    ///
+    /// # Arguments
+    ///
+    /// * `visit` - Closure that visits the value
+    ///
+    /// # Returns
+    ///
+    /// Result of the closure or the `Error` on cycles and exceeded limits
+    ///
+
+    unsafe fn visit_nested<T, F>(&self, visit: F) -> Result<T, Error>
+    where
+        F: FnOnce() -> Result<T, Error>,
+    {
+        let ptr = ffi::duk_get_heapptr(self.rubtle.ctx, self.idx);
+
+        self.state.borrow_mut().enter(ptr)?;
+
+        let result = visit();
+
+        self.state.borrow_mut().leave();
+
+        result
+    }
+
+    fn conversion_error(&self, expected: &'static str) -> Error {
+        Error::Conversion {
+            expected,
+            actual: unsafe { convert::type_name_at(self.rubtle.ctx, self.idx) },
+        }
+    }
+}
+
+impl<'de, 'a> de::Deserializer<'de> for StackDeserializer<'a> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let ctx = self.rubtle.ctx;
+
+        unsafe {
+            match ffi::duk_get_type(ctx, self.idx) as u32 {
+                ffi::DUK_TYPE_UNDEFINED | ffi::DUK_TYPE_NULL => visitor.visit_unit(),
+                ffi::DUK_TYPE_BOOLEAN => visitor.visit_bool(0 != ffi::duk_get_boolean(ctx, self.idx)),
+                ffi::DUK_TYPE_NUMBER => visit_number(ffi::duk_get_number(ctx, self.idx), visitor),
+                ffi::DUK_TYPE_STRING => match convert::get_str_at(ctx, self.idx) {
+                    Some(val) => visitor.visit_string(val),
+                    None => Err(Error::Serde(String::from("Invalid string"))),
+                },
+                ffi::DUK_TYPE_BUFFER => self.visit_buffer(visitor),
+                ffi::DUK_TYPE_OBJECT => {
+                    let mut timeval: ffi::duk_double_t = 0.0;
+
+                    if 1 == ffi::duk_is_array(ctx, self.idx) {
+                        self.visit_nested(|| {
+                            visitor.visit_seq(StackSeq {
+                                rubtle: self.rubtle,
+                                idx: self.idx,
+                                pos: 0,
+                                len: ffi::duk_get_length(ctx, self.idx) as usize,
+                                state: self.state,
+                            })
+                        })
+                    } else if 1 == ffi::rust_duk_get_date(ctx, self.idx, &mut timeval) {
+                        visitor.visit_f64(timeval)
+                    } else if 1 == ffi::rust_duk_is_byte_buffer(ctx, self.idx) {
+                        self.visit_buffer(visitor)
+                    } else if 1 == ffi::duk_is_function(ctx, self.idx) {
+                        Err(Error::Serde(String::from("Cannot deserialize function")))
+                    } else {
+                        self.visit_nested(|| {
+                            ffi::duk_require_stack(ctx, 1);
+                            ffi::duk_enum(ctx, self.idx, 0);
+
+                            let result = visitor.visit_map(StackMap {
+                                rubtle: self.rubtle,
+                                enum_idx: ffi::duk_get_top_index(ctx),
+                                state: self.state,
+                            });
+
+                            ffi::duk_pop(ctx);
+
+                            result
+                        })
+                    }
+                }
+                _ => Err(self.conversion_error("value")),
+            }
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match unsafe { ffi::duk_get_type(self.rubtle.ctx, self.idx) as u32 } {
+            ffi::DUK_TYPE_UNDEFINED | ffi::DUK_TYPE_NULL => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        let ctx = self.rubtle.ctx;
+
+        unsafe {
+            if let Some(variant) = convert::get_str_at(ctx, self.idx) {
+                return visitor.visit_enum(variant.into_deserializer());
+            }
+
+            if 1 != ffi::duk_is_object(ctx, self.idx) || 1 == ffi::duk_is_array(ctx, self.idx) {
+                return Err(self.conversion_error("enum"));
+            }
+
+            /* Fetch first own key and value */
+            ffi::duk_require_stack(ctx, 3);
+            ffi::duk_enum(ctx, self.idx, ffi::DUK_ENUM_OWN_PROPERTIES_ONLY);
+
+            if 0 == ffi::duk_next(ctx, -1, 1) {
+                ffi::duk_pop(ctx);
+
+                return Err(self.conversion_error("enum"));
+            }
+
+            let variant = convert::get_str_at(ctx, -2).unwrap_or_default();
+
+            /* Keep value only */
+            ffi::duk_remove(ctx, -2);
+            ffi::duk_remove(ctx, -2);
+
+            let result = visitor.visit_enum(StackEnum {
+                variant,
+                value: StackDeserializer::top(self.rubtle, self.state),
+            });
+
+            ffi::duk_pop(ctx);
+
+            result
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+impl<'a> StackDeserializer<'a> {
+    fn visit_buffer<'de, V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        unsafe {
+            let mut len: ffi::duk_size_t = 0;
+            let data = ffi::duk_get_buffer_data(self.rubtle.ctx, self.idx, &mut len);
+
+            if data.is_null() {
+                visitor.visit_bytes(&[])
+            } else {
+                visitor.visit_bytes(slice::from_raw_parts(data as *const u8, len as usize))
+            }
+        }
+    }
+}
+
+/// Access to the elements of an array on the stack
+struct StackSeq<'a> {
+    rubtle: &'a Rubtle,
+    idx: ffi::duk_idx_t,
+
+    /// Index of the next element
+    pos: usize,
+    len: usize,
+
+    state: &'a RefCell<ConversionState<'a>>,
+}
+
+impl<'de, 'a> de::SeqAccess<'de> for StackSeq<'a> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Error> {
+        if self.pos >= self.len {
+            return Ok(None);
+        }
+
+        unsafe {
+            ffi::duk_require_stack(self.rubtle.ctx, 1);
+            ffi::duk_get_prop_index(self.rubtle.ctx, self.idx, self.pos as ffi::duk_uarridx_t);
+
+            self.pos += 1;
+
+            let result = seed.deserialize(StackDeserializer::top(self.rubtle, self.state));
+
+            ffi::duk_pop(self.rubtle.ctx);
+
+            result.map(Some)
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.len - self.pos)
+    }
+}
+
+/// Access to the enumerable properties of an object on the stack
+struct StackMap<'a> {
+    rubtle: &'a Rubtle,
+
+    /// Stack index of the enumerator
+    enum_idx: ffi::duk_idx_t,
+
+    state: &'a RefCell<ConversionState<'a>>,
+}
+
+impl<'de, 'a> de::MapAccess<'de> for StackMap<'a> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, Error> {
+        unsafe {
+            ffi::duk_require_stack(self.rubtle.ctx, 2);
+
+            if 0 == ffi::duk_next(self.rubtle.ctx, self.enum_idx, 1) {
+                return Ok(None);
+            }
+
+            seed.deserialize(StackDeserializer {
+                rubtle: self.rubtle,
+                idx: ffi::duk_get_top_index(self.rubtle.ctx) - 1,
+                state: self.state,
+            })
+            .map(Some)
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        let result = seed.deserialize(StackDeserializer::top(self.rubtle, self.state));
+
+        /* Remove key and value */
+        unsafe {
+            ffi::duk_pop_2(self.rubtle.ctx);
+        }
+
+        result
+    }
+}
+
+/// Access to an enum wrapped into an object on the stack
+struct StackEnum<'a> {
+    variant: String,
+    value: StackDeserializer<'a>,
+}
+
+impl<'de, 'a> de::EnumAccess<'de> for StackEnum<'a> {
+    type Error = Error;
+    type Variant = StackDeserializer<'a>;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, StackDeserializer<'a>), Error> {
+        let variant = seed.deserialize(Value::Str(self.variant))?;
+
+        Ok((variant, self.value))
+    }
+}
+
+impl<'de, 'a> de::VariantAccess<'de> for StackDeserializer<'a> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        match unsafe { ffi::duk_get_type(self.rubtle.ctx, self.idx) as u32 } {
+            ffi::DUK_TYPE_UNDEFINED | ffi::DUK_TYPE_NULL => Ok(()),
+            _ => Err(self.conversion_error("null")),
+        }
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Error> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Error> {
+        de::Deserializer::deserialize_seq(self, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        de::Deserializer::deserialize_map(self, visitor)
+    }
+}
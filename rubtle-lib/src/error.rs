@@ -11,28 +11,186 @@
 use std::fmt;
 use std::error::Error as StdError;
 
-#[derive(Debug)]
-pub struct Error {
-    pub details: String,
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    /// Script failed to compile
+    Syntax(JsError),
+
+    /// Script threw an exception while running
+    Exception(JsError),
+
+    /// Host callback failed
+    Callback(String),
+
+    /// Value cannot be converted to the requested type
+    Conversion {
+        expected: &'static str,
+        actual: &'static str,
+    },
+
+    /// Script or conversion was aborted by a resource limit
+    Limit(Limit),
+
+    /// Bytecode cannot be loaded
+    Bytecode(String),
+
+    /// Value cannot be serialized or deserialized
+    Serde(String),
+
+    /// Handle belongs to another heap or its heap is gone
+    Heap(&'static str),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsError {
+    /// Constructor name of the exception, e.g. TypeError
+    pub name: String,
+
+    /// Message of the exception
+    pub message: String,
+
+    /// Stack trace of the exception
+    pub stack: Option<String>,
+
+    /// Name of the file the exception was thrown in
+    pub file_name: Option<String>,
+
+    /// Line number the exception was thrown at
+    pub line_number: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Limit {
+    /// Execution time limit exceeded
+    Time,
+
+    /// Memory limit exceeded
+    Memory,
+
+    /// Maximum conversion depth exceeded
+    Depth,
+
+    /// Maximum number of converted arrays and objects exceeded
+    Nodes,
 }
 
 impl Error {
-    #[allow(dead_code)]
-    fn new(details: &str) -> Error {
-        Error {
-            details: details.to_string(),
+    ///
+    /// Create a new JS error with given constructor name
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Constructor name, e.g. RangeError or a custom global class
+    /// * `message` - Message of the error
+    ///
+    /// # Example
+    ///
+    ///     use rubtle_lib::Error;
+    ///
+    ///     let err = Error::js("ParseError", "Unexpected token");
+    ///
+
+    pub fn js(name: &str, message: &str) -> Error {
+        Error::Exception(JsError::new(name, message))
+    }
+
+    ///
+    /// Create a new JS TypeError
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - Message of the error
+    ///
+
+    pub fn type_error(message: &str) -> Error {
+        Error::js("TypeError", message)
+    }
+
+    ///
+    /// Create a new JS RangeError
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - Message of the error
+    ///
+
+    pub fn range_error(message: &str) -> Error {
+        Error::js("RangeError", message)
+    }
+
+    ///
+    /// Return inner JS error
+    ///
+    /// Returns
+    ///
+    /// `Option` either with the JS error or without
+    ///
+
+    pub fn as_js_error(&self) -> Option<&JsError> {
+        match self {
+            Error::Syntax(err) | Error::Exception(err) => Some(err),
+            _ => None,
         }
     }
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.details)
+        match self {
+            Error::Syntax(err) | Error::Exception(err) => write!(f, "{}", err),
+            Error::Callback(details) => write!(f, "{}", details),
+            Error::Conversion { expected, actual } => {
+                write!(f, "Expected {} but got {}", expected, actual)
+            }
+            Error::Limit(limit) => write!(f, "{}", limit),
+            Error::Bytecode(details) | Error::Serde(details) => write!(f, "{}", details),
+            Error::Heap(details) => write!(f, "{}", details),
+        }
+    }
+}
+
+impl StdError for Error {}
+
+impl JsError {
+    ///
+    /// Create a new JS error without location
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Constructor name of the error
+    /// * `message` - Message of the error
+    ///
+
+    pub fn new(name: &str, message: &str) -> JsError {
+        JsError {
+            name: name.to_string(),
+            message: message.to_string(),
+            stack: None,
+            file_name: None,
+            line_number: None,
+        }
+    }
+}
+
+impl fmt::Display for JsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.name, self.message)?;
+
+        if let Some(ref file_name) = self.file_name {
+            write!(f, " ({}:{})", file_name, self.line_number.unwrap_or(0))?;
+        }
+
+        Ok(())
     }
 }
 
-impl StdError for Error {
-    fn description(&self) -> &str {
-        &self.details
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Limit::Time => write!(f, "Execution time limit exceeded"),
+            Limit::Memory => write!(f, "Memory limit exceeded"),
+            Limit::Depth => write!(f, "Maximum conversion depth exceeded"),
+            Limit::Nodes => write!(f, "Maximum number of converted objects exceeded"),
+        }
     }
 }
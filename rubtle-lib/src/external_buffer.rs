@@ -0,0 +1,167 @@
+///
+/// @package Rubtle-Lib
+///
+/// @file External buffer functions
+/// @copyright 2020-present Christoph Kappel <christoph@unexist.dev>
+/// @version $Id$
+///
+/// This program can be distributed under the terms of the GNU GPLv2.
+/// See the file LICENSE for details.
+//
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::marker::PhantomData;
+use std::ptr;
+use std::rc::Rc;
+
+use crate::stash::StashRef;
+use crate::Value;
+
+/// State shared between the guard and all handles
+struct ExternalState {
+    /// Lent memory; null once the guard is gone
+    data: Cell<*mut u8>,
+    len: Cell<usize>,
+
+    /// Plain buffers pushed so far; detached along with the guard
+    buffers: RefCell<Vec<StashRef>>,
+}
+
+/// Guard of Rust memory lent to JS without copying
+///
+/// Guards are only handed out by `Rubtle::with_external_buffer`, which drops
+/// them before it returns. All JS buffers created from it become zero-length
+/// then, so scripts never see dangling memory; views on them read zeros and
+/// ignore writes. The memory is borrowed mutably, because scripts can write
+/// into it.
+pub struct ExternalBuffer<'a> {
+    state: Rc<ExternalState>,
+    borrow: PhantomData<&'a mut [u8]>,
+}
+
+/// Handle to push lent memory as part of a `Value`
+#[derive(Clone)]
+pub struct ExternalBytes {
+    state: Rc<ExternalState>,
+}
+
+impl<'a> ExternalBuffer<'a> {
+    ///
+    /// Lend memory to JS
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - Memory to lend; borrowed until the guard is dropped
+    ///
+    /// # Returns
+    ///
+    /// A new `ExternalBuffer`
+    ///
+
+    pub(crate) fn new(data: &'a mut [u8]) -> ExternalBuffer<'a> {
+        ExternalBuffer {
+            state: Rc::new(ExternalState {
+                data: Cell::new(data.as_mut_ptr()),
+                len: Cell::new(data.len()),
+                buffers: RefCell::new(Vec::new()),
+            }),
+            borrow: PhantomData,
+        }
+    }
+
+    ///
+    /// Create value that pushes the lent memory
+    ///
+    /// # Returns
+    ///
+    /// A new `Value::External`
+    ///
+
+    pub fn to_value(&self) -> Value {
+        Value::External(ExternalBytes {
+            state: self.state.clone(),
+        })
+    }
+}
+
+impl<'a> Drop for ExternalBuffer<'a> {
+    fn drop(&mut self) {
+        self.state.data.set(ptr::null_mut());
+        self.state.len.set(0);
+
+        for buf in self.state.buffers.borrow_mut().drain(..) {
+            /* Heap takes care of everything when it is gone */
+            unsafe {
+                if buf.push(buf.ctx()).is_ok() {
+                    ffi::duk_config_buffer(buf.ctx(), -1, ptr::null_mut(), 0);
+                    ffi::duk_pop(buf.ctx());
+                }
+            }
+        }
+    }
+}
+
+impl ExternalBytes {
+    ///
+    /// Return length of the lent memory
+    ///
+    /// # Returns
+    ///
+    /// Length in bytes; zero once the guard is gone
+    ///
+
+    pub fn len(&self) -> usize {
+        self.state.len.get()
+    }
+
+    ///
+    /// Check whether the lent memory is empty
+    ///
+    /// # Returns
+    ///
+    /// `true` if the memory is empty or the guard is gone; otherwise `false`
+    ///
+
+    pub fn is_empty(&self) -> bool {
+        0 == self.len()
+    }
+
+    ///
+    /// Push lent memory as external plain buffer
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - Duktape context
+    ///
+
+    pub(crate) unsafe fn push(&self, ctx: *mut ffi::duk_context) {
+        ffi::duk_require_stack(ctx, 2);
+        ffi::duk_push_buffer_raw(ctx, 0, ffi::DUK_BUF_FLAG_DYNAMIC | ffi::DUK_BUF_FLAG_EXTERNAL);
+
+        if !self.state.data.get().is_null() {
+            ffi::duk_config_buffer(
+                ctx,
+                -1,
+                self.state.data.get() as *mut _,
+                self.state.len.get() as ffi::duk_size_t,
+            );
+
+            /* Remember buffer to detach it later */
+            ffi::duk_dup(ctx, -1);
+
+            self.state.buffers.borrow_mut().push(StashRef::pop(ctx));
+        }
+    }
+}
+
+impl fmt::Debug for ExternalBytes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "external({})", self.len())
+    }
+}
+
+impl PartialEq for ExternalBytes {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.state, &other.state)
+    }
+}
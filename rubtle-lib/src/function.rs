@@ -10,21 +10,142 @@
 ///
 
 use std::fmt;
+use std::rc::Rc;
 
-use crate::{Value, Invocation};
+use crate::{Error, Value, Invocation, JsFunction, Rubtle, ToJs};
 use crate::types::{Callback, CallbackResult};
 
 pub struct Function<T> {
-    pub callback: Callback<T>,
+    pub(crate) kind: FunctionKind<T>,
+}
+
+pub(crate) enum FunctionKind<T> {
+    /// Rust closure; shared with all clones and pushed JS functions
+    Rust(Rc<Callback<T>>),
+
+    /// JS function living on the heap
+    Js(JsFunction),
+}
+
+///
+/// Box closure/function as callback
+///
+/// # Arguments
+///
+/// * `func` - Closure/function to box; its result gets pushed via `ToJs`
+///
+/// # Returns
+///
+/// A new `Callback`
+///
+
+pub(crate) fn box_callback<T, F, R>(func: F) -> Callback<T>
+where
+    F: 'static + Fn(Invocation<T>) -> CallbackResult<R>,
+    R: 'static + ToJs,
+{
+    Box::new(move |inv: Invocation<T>| func(inv).map(|rval| Box::new(rval) as Box<dyn ToJs>))
 }
 
 impl<T> Function<T> {
-    pub fn from<'a, F>(func: F) -> Function<T>
+    pub fn from<'a, F, R>(func: F) -> Function<T>
     where
-        F: 'static + Fn(Invocation<T>) -> CallbackResult<Value>,
+        F: 'static + Fn(Invocation<T>) -> CallbackResult<R>,
+        R: 'static + ToJs,
     {
         Function {
-            callback: Box::new(func) as Callback<T>,
+            kind: FunctionKind::Rust(Rc::new(box_callback(func))),
+        }
+    }
+
+    ///
+    /// Check whether function is a JS function
+    ///
+    /// Returns
+    ///
+    /// `true` if the function lives on the heap; otherwise `false`
+    ///
+
+    pub fn is_js_function(&self) -> bool {
+        if let FunctionKind::Js(_) = self.kind {
+            true
+        } else {
+            false
+        }
+    }
+
+    ///
+    /// Return inner JS function
+    ///
+    /// Returns
+    ///
+    /// `Option` either with the handle or without
+    ///
+
+    pub fn as_js_function(&self) -> Option<&JsFunction> {
+        if let FunctionKind::Js(ref func) = self.kind {
+            Some(func)
+        } else {
+            None
+        }
+    }
+}
+
+impl Function<i8> {
+    ///
+    /// Call function
+    ///
+    /// # Arguments
+    ///
+    /// * `rubtle` - Rubtle instance to call the function in
+    /// * `this` - Value to bind to `this`
+    /// * `args` - Arguments to pass
+    ///
+    /// # Returns
+    ///
+    /// Return value of the function as `Value` or the thrown exception as `Error`
+    ///
+    /// # Example
+    ///
+    ///     use rubtle_lib::{Rubtle, Value, Function, Invocation, CallbackResult};
+    ///
+    ///     let rubtle = Rubtle::new();
+    ///
+    ///     let func = Function::from(|inv: Invocation<i8>| -> CallbackResult<Value> {
+    ///         Ok(Value::from(inv.args.unwrap().len() as i32))
+    ///     });
+    ///
+    ///     assert_eq!(Value::from(2), func.call(&rubtle, &Value::None,
+    ///         &[Value::from(1), Value::from(2)]).unwrap());
+    ///
+
+    pub fn call(&self, rubtle: &Rubtle, this: &Value, args: &[Value]) -> Result<Value, Error> {
+        match self.kind {
+            FunctionKind::Js(ref func) => func.call(rubtle, this, args),
+            FunctionKind::Rust(ref callback) => unsafe {
+                let top = ffi::duk_get_top(rubtle.ctx);
+
+                ffi::duk_require_stack(rubtle.ctx, args.len() as ffi::duk_idx_t + 2);
+
+                rubtle.push_callback(callback);
+
+                let result = rubtle
+                    .try_push_value(this)
+                    .and_then(|_| args.iter().try_for_each(|arg| rubtle.try_push_value(arg)))
+                    .and_then(|_| rubtle.call_top(args.len() as ffi::duk_idx_t));
+
+                ffi::duk_set_top(rubtle.ctx, top);
+
+                result
+            },
+        }
+    }
+}
+
+impl<T> From<JsFunction> for Function<T> {
+    fn from(src: JsFunction) -> Self {
+        Function {
+            kind: FunctionKind::Js(src),
         }
     }
 }
@@ -37,12 +158,25 @@ impl<T> fmt::Debug for Function<T> {
 
 impl<T> Clone for Function<T> {
     fn clone(&self) -> Self {
-        unimplemented!();
+        match self.kind {
+            FunctionKind::Js(ref func) => Function {
+                kind: FunctionKind::Js(func.clone()),
+            },
+            FunctionKind::Rust(ref callback) => Function {
+                kind: FunctionKind::Rust(callback.clone()),
+            },
+        }
     }
 }
 
 impl<T> PartialEq for Function<T> {
-    fn eq(&self, _other: &Self) -> bool {
-        false
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.kind, &other.kind) {
+            (FunctionKind::Js(func), FunctionKind::Js(other_func)) => func == other_func,
+            (FunctionKind::Rust(callback), FunctionKind::Rust(other_callback)) => {
+                Rc::ptr_eq(callback, other_callback)
+            }
+            _ => false,
+        }
     }
 }
@@ -0,0 +1,277 @@
+///
+/// @package Rubtle-Lib
+///
+/// @file Heap functions
+/// @copyright 2020-present Christoph Kappel <christoph@unexist.dev>
+/// @version $Id$
+///
+/// This program can be distributed under the terms of the GNU GPLv2.
+/// See the file LICENSE for details.
+//
+use std::cell::{Cell, RefCell};
+use std::mem;
+use std::os::raw::c_void;
+use std::rc::{Rc, Weak};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::allocator::MemoryTracker;
+use crate::value::BufferType;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HeapStats {
+    /// Allocated bytes; only tracked for heaps created by `RubtleBuilder`
+    /// with memory tracking or a memory limit, otherwise always `None`
+    pub allocated_bytes: Option<usize>,
+
+    /// Number of live objects, including functions and threads
+    pub objects: usize,
+
+    /// Number of interned strings
+    pub strings: usize,
+
+    /// Number of buffers
+    pub buffers: usize,
+
+    /// Number of objects waiting for their finalizer to run
+    pub pending_finalizers: usize,
+
+    /// Number of finalizers run since the heap has been created
+    pub finalized: usize,
+}
+
+pub(crate) struct HeapData {
+    /// Liveness token; dropped right before the heap is destroyed
+    alive: RefCell<Option<Rc<()>>>,
+
+    /// Next free slot for stash references; never reused while the heap lives
+    next_ref: Cell<u64>,
+
+    /// Interrupt flag shared with interrupt handles
+    pub(crate) interrupt: Arc<AtomicBool>,
+
+    /// Time limit of top-level runs
+    pub(crate) time_limit: Cell<Option<Duration>>,
+
+    /// Deadline of the current top-level run
+    deadline: Cell<Option<Instant>>,
+
+    /// Whether the current top-level run has been aborted
+    timed_out: Cell<bool>,
+
+    /// Depth of nested runs
+    depth: Cell<u32>,
+
+    /// Memory tracker of the Rust allocator, if installed
+    pub(crate) memory: Option<MemoryTracker>,
+
+    /// JS type binary data is pushed as
+    pub(crate) buffer_type: Cell<BufferType>,
+
+    /// Number of finalizers run so far
+    pub(crate) finalized: Cell<usize>,
+}
+
+impl HeapData {
+    pub(crate) fn new(memory: Option<MemoryTracker>) -> HeapData {
+        HeapData {
+            alive: RefCell::new(Some(Rc::new(()))),
+            next_ref: Cell::new(0),
+            interrupt: Arc::new(AtomicBool::new(false)),
+            time_limit: Cell::new(None),
+            deadline: Cell::new(None),
+            timed_out: Cell::new(false),
+            depth: Cell::new(0),
+            memory,
+            buffer_type: Cell::new(BufferType::default()),
+            finalized: Cell::new(0),
+        }
+    }
+
+    ///
+    /// Get heap data of given context
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - Duktape context
+    ///
+    /// # Returns
+    ///
+    /// Heap data stored as heap udata
+    ///
+
+    pub(crate) unsafe fn from_ctx<'a>(ctx: *mut ffi::duk_context) -> &'a HeapData {
+        &*HeapData::ptr_from_ctx(ctx)
+    }
+
+    ///
+    /// Get raw pointer to heap data of given context
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - Duktape context
+    ///
+    /// # Returns
+    ///
+    /// Pointer to the heap data as boxed on heap creation
+    ///
+
+    pub(crate) unsafe fn ptr_from_ctx(ctx: *mut ffi::duk_context) -> *mut HeapData {
+        let mut funcs: ffi::duk_memory_functions = mem::zeroed();
+
+        ffi::duk_get_memory_functions(ctx, &mut funcs);
+
+        assert!(!funcs.udata.is_null(), "Null heap data pointer");
+
+        funcs.udata as *mut HeapData
+    }
+
+    ///
+    /// Create weak reference to the liveness token
+    ///
+    /// # Returns
+    ///
+    /// A `Weak` that fails to upgrade once the heap is gone
+    ///
+
+    pub(crate) fn watch(&self) -> Weak<()> {
+        match *self.alive.borrow() {
+            Some(ref alive) => Rc::downgrade(alive),
+            None => Weak::new(),
+        }
+    }
+
+    ///
+    /// Mark heap as dead, e.g. right before it is destroyed
+    ///
+
+    pub(crate) fn kill(&self) {
+        self.alive.borrow_mut().take();
+    }
+
+    ///
+    /// Reserve next stash slot
+    ///
+    /// # Returns
+    ///
+    /// Free slot in the heap stash
+    ///
+
+    pub(crate) fn next_ref(&self) -> u64 {
+        let slot = self.next_ref.get();
+
+        /* Slots are pushed as numbers, so stay below 2^53 to keep them exact */
+        assert!(slot < (1 << 53), "Out of stash slots");
+
+        self.next_ref.set(slot + 1);
+
+        slot
+    }
+
+    ///
+    /// Enter a run; top-level runs start the clock
+    ///
+
+    pub(crate) fn enter(&self) {
+        if 0 == self.depth.get() {
+            self.deadline
+                .set(self.time_limit.get().map(|limit| Instant::now() + limit));
+
+            if let Some(ref memory) = self.memory {
+                memory.exceeded.set(false);
+            }
+        }
+
+        self.depth.set(self.depth.get() + 1);
+    }
+
+    ///
+    /// Leave a run; top-level runs reset all timeout state
+    ///
+
+    pub(crate) fn leave(&self) {
+        self.depth.set(self.depth.get() - 1);
+
+        if 0 == self.depth.get() {
+            self.deadline.set(None);
+            self.timed_out.set(false);
+        }
+    }
+
+    ///
+    /// Check whether the current run has been aborted
+    ///
+    /// # Returns
+    ///
+    /// `true` if the run has been aborted; otherwise `false`
+    ///
+
+    pub(crate) fn is_timed_out(&self) -> bool {
+        self.timed_out.get()
+    }
+
+    ///
+    /// Check whether an allocation has been refused by the memory limit
+    ///
+    /// # Returns
+    ///
+    /// `true` if the limit has been exceeded; otherwise `false`
+    ///
+
+    pub(crate) fn is_memory_exceeded(&self) -> bool {
+        match self.memory {
+            Some(ref memory) => memory.exceeded.get(),
+            None => false,
+        }
+    }
+
+    ///
+    /// Check whether the current run must be aborted
+    ///
+    /// # Returns
+    ///
+    /// `true` if the run must be aborted; otherwise `false`
+    ///
+    /// Duktape requires this to stay `true` until the run has fully
+    /// bubbled out, so the state is latched until the run is left.
+    /// Pending interrupts are consumed here, so an interrupt requested
+    /// while idle aborts the next run instead of getting lost.
+    ///
+
+    pub(crate) fn check_timeout(&self) -> bool {
+        if !self.timed_out.get() {
+            let expired = match self.deadline.get() {
+                Some(deadline) => Instant::now() >= deadline,
+                None => false,
+            };
+
+            let interrupted = self.interrupt.swap(false, Ordering::SeqCst);
+
+            if expired || interrupted {
+                self.timed_out.set(true);
+            }
+        }
+
+        self.timed_out.get()
+    }
+}
+
+///
+/// Count finalizer that is about to run
+///
+/// # Arguments
+///
+/// * `udata` - Heap data supplied to the heap
+///
+
+#[no_mangle]
+pub unsafe extern "C" fn rust_duk_finalizer_hook(udata: *mut c_void) {
+    if udata.is_null() {
+        return;
+    }
+
+    let heap = &*(udata as *const HeapData);
+
+    heap.finalized.set(heap.finalized.get() + 1);
+}
@@ -0,0 +1,68 @@
+///
+/// @package Rubtle-Lib
+///
+/// @file Interrupt functions
+/// @copyright 2020-present Christoph Kappel <christoph@unexist.dev>
+/// @version $Id$
+///
+/// This program can be distributed under the terms of the GNU GPLv2.
+/// See the file LICENSE for details.
+//
+use std::os::raw::c_void;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use crate::heap::HeapData;
+
+/// Handle to abort the running script from another thread
+#[derive(Debug, Clone)]
+pub struct InterruptHandle {
+    pub(crate) flag: Arc<AtomicBool>,
+}
+
+impl InterruptHandle {
+    ///
+    /// Abort the currently running script
+    ///
+    /// Calls while no script is running stay pending and abort the next
+    /// script; each interrupt aborts at most one run.
+    ///
+    /// # Example
+    ///
+    ///     use rubtle_lib::Rubtle;
+    ///
+    ///     let rubtle = Rubtle::new();
+    ///     let handle = rubtle.interrupt_handle();
+    ///
+    ///     std::thread::spawn(move || handle.interrupt());
+    ///
+
+    pub fn interrupt(&self) {
+        self.flag.store(true, Ordering::SeqCst);
+    }
+}
+
+///
+/// Check whether duktape must abort the running script
+///
+/// # Arguments
+///
+/// * `udata` - Heap data supplied to the heap
+///
+/// # Returns
+///
+/// `1` if the script must be aborted; otherwise `0`
+///
+
+#[no_mangle]
+pub unsafe extern "C" fn rust_duk_exec_timeout_check(udata: *mut c_void) -> ffi::duk_bool_t {
+    if udata.is_null() {
+        return 0;
+    }
+
+    if (*(udata as *const HeapData)).check_timeout() {
+        1
+    } else {
+        0
+    }
+}
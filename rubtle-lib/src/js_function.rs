@@ -0,0 +1,123 @@
+///
+/// @package Rubtle-Lib
+///
+/// @file JS function functions
+/// @copyright 2020-present Christoph Kappel <christoph@unexist.dev>
+/// @version $Id$
+///
+/// This program can be distributed under the terms of the GNU GPLv2.
+/// See the file LICENSE for details.
+//
+use std::fmt;
+use std::os::raw::c_void;
+use std::rc::Rc;
+
+use crate::stash::StashRef;
+use crate::{Error, Rubtle, Value};
+
+/// Handle of a JS function pinned in the heap stash
+#[derive(Clone)]
+pub struct JsFunction {
+    /// Function pinned in the heap stash; shared between clones
+    func: Rc<StashRef>,
+
+    /// Heap pointer of the function; stable while it is pinned
+    ptr: *mut c_void,
+}
+
+impl JsFunction {
+    ///
+    /// Pop function from top of the stack and pin it in the heap stash
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - Duktape context
+    ///
+    /// # Returns
+    ///
+    /// A new `JsFunction`
+    ///
+
+    pub(crate) unsafe fn pop(ctx: *mut ffi::duk_context) -> JsFunction {
+        let ptr = ffi::duk_get_heapptr(ctx, -1);
+
+        JsFunction {
+            func: Rc::new(StashRef::pop(ctx)),
+            ptr,
+        }
+    }
+
+    ///
+    /// Push pinned function onto the stack
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - Duktape context to push onto; must share the heap
+    ///
+    /// # Returns
+    ///
+    /// Either nothing or `Error::Heap` for dead or foreign heaps
+    ///
+
+    pub(crate) unsafe fn push(&self, ctx: *mut ffi::duk_context) -> Result<(), Error> {
+        self.func.push(ctx)
+    }
+
+    ///
+    /// Call function
+    ///
+    /// # Arguments
+    ///
+    /// * `rubtle` - Rubtle instance the function belongs to
+    /// * `this` - Value to bind to `this`
+    /// * `args` - Arguments to pass
+    ///
+    /// # Returns
+    ///
+    /// Return value of the function as `Value` or the thrown exception as `Error`
+    ///
+    /// # Example
+    ///
+    ///     use rubtle_lib::{Rubtle, Value};
+    ///
+    ///     let rubtle = Rubtle::new();
+    ///
+    ///     let rval = rubtle.eval("(function(a, b) { return a + b; })").unwrap();
+    ///     let func = rval.as_function().unwrap().as_js_function().unwrap();
+    ///
+    ///     let sum = func.call(&rubtle, &Value::None, &[Value::from(1), Value::from(2)]);
+    ///
+    ///     assert_eq!(Value::from(3), sum.unwrap());
+    ///
+
+    pub fn call(&self, rubtle: &Rubtle, this: &Value, args: &[Value]) -> Result<Value, Error> {
+        unsafe {
+            let top = ffi::duk_get_top(rubtle.ctx);
+
+            ffi::duk_require_stack(rubtle.ctx, args.len() as ffi::duk_idx_t + 2);
+
+            self.push(rubtle.ctx)?;
+
+            let result = rubtle
+                .try_push_value(this)
+                .and_then(|_| args.iter().try_for_each(|arg| rubtle.try_push_value(arg)))
+                .and_then(|_| rubtle.call_top(args.len() as ffi::duk_idx_t));
+
+            ffi::duk_set_top(rubtle.ctx, top);
+
+            result
+        }
+    }
+}
+
+impl fmt::Debug for JsFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "func")
+    }
+}
+
+impl PartialEq for JsFunction {
+    fn eq(&self, other: &Self) -> bool {
+        self.ptr == other.ptr && self.func.is_alive()
+    }
+}
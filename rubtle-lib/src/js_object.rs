@@ -0,0 +1,405 @@
+///
+/// @package Rubtle-Lib
+///
+/// @file JS object functions
+/// @copyright 2020-present Christoph Kappel <christoph@unexist.dev>
+/// @version $Id$
+///
+/// This program can be distributed under the terms of the GNU GPLv2.
+/// See the file LICENSE for details.
+//
+use std::fmt;
+use std::os::raw::c_void;
+use std::rc::Rc;
+
+use crate::stash::StashRef;
+use crate::{Error, Rubtle, Value};
+
+/// Handle of a live JS object or array pinned in the heap stash
+#[derive(Clone)]
+pub struct JsObject {
+    /// Object pinned in the heap stash; shared between clones
+    obj: Rc<StashRef>,
+
+    /// Heap pointer of the object; stable while it is pinned
+    ptr: *mut c_void,
+}
+
+impl JsObject {
+    ///
+    /// Pop object from top of the stack and pin it in the heap stash
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - Duktape context
+    ///
+    /// # Returns
+    ///
+    /// A new `JsObject`
+    ///
+
+    pub(crate) unsafe fn pop(ctx: *mut ffi::duk_context) -> JsObject {
+        let ptr = ffi::duk_get_heapptr(ctx, -1);
+
+        JsObject {
+            obj: Rc::new(StashRef::pop(ctx)),
+            ptr,
+        }
+    }
+
+    ///
+    /// Push pinned object onto the stack
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - Duktape context to push onto; must share the heap
+    ///
+    /// # Returns
+    ///
+    /// Either nothing or `Error::Heap` for dead or foreign heaps
+    ///
+
+    pub(crate) unsafe fn push(&self, ctx: *mut ffi::duk_context) -> Result<(), Error> {
+        self.obj.push(ctx)
+    }
+
+    ///
+    /// Get value of property
+    ///
+    /// # Arguments
+    ///
+    /// * `rubtle` - Rubtle instance the object belongs to
+    /// * `key` - Name of the property
+    ///
+    /// # Returns
+    ///
+    /// Copy of the property as `Value` or the thrown exception as `Error`
+    ///
+    /// # Example
+    ///
+    ///     use rubtle_lib::{Rubtle, Value};
+    ///
+    ///     let rubtle = Rubtle::new();
+    ///
+    ///     rubtle.eval("var rubtle = { name: 'rubtle' };").unwrap();
+    ///
+    ///     let obj = rubtle.get_global_js_object("rubtle").unwrap();
+    ///
+    ///     assert_eq!(Value::from("rubtle"), obj.get(&rubtle, "name").unwrap());
+    ///
+
+    pub fn get(&self, rubtle: &Rubtle, key: &str) -> Result<Value, Error> {
+        unsafe {
+            let top = ffi::duk_get_top(rubtle.ctx);
+
+            let result = self
+                .get_top(rubtle, key)
+                .map(|_| rubtle.pop_value().unwrap_or(Value::None));
+
+            ffi::duk_set_top(rubtle.ctx, top);
+
+            result
+        }
+    }
+
+    ///
+    /// Get handle of nested object without copying it
+    ///
+    /// # Arguments
+    ///
+    /// * `rubtle` - Rubtle instance the object belongs to
+    /// * `key` - Name of the property
+    ///
+    /// # Returns
+    ///
+    /// `Option` with the handle if the property is an object; otherwise the
+    /// thrown exception as `Error`
+    ///
+
+    pub fn get_object(&self, rubtle: &Rubtle, key: &str) -> Result<Option<JsObject>, Error> {
+        unsafe {
+            let top = ffi::duk_get_top(rubtle.ctx);
+
+            let result = self
+                .get_top(rubtle, key)
+                .map(|_| rubtle.pop_js_object());
+
+            ffi::duk_set_top(rubtle.ctx, top);
+
+            result
+        }
+    }
+
+    ///
+    /// Set value of property
+    ///
+    /// # Arguments
+    ///
+    /// * `rubtle` - Rubtle instance the object belongs to
+    /// * `key` - Name of the property
+    /// * `rval` - Value to set
+    ///
+    /// # Returns
+    ///
+    /// Either nothing or the thrown exception as `Error`
+    ///
+
+    pub fn set(&self, rubtle: &Rubtle, key: &str, rval: &Value) -> Result<(), Error> {
+        unsafe {
+            let top = ffi::duk_get_top(rubtle.ctx);
+
+            ffi::duk_require_stack(rubtle.ctx, 3);
+
+            self.push(rubtle.ctx)?;
+            rubtle.push_value(&Value::from(key));
+
+            let result = rubtle.try_push_value(rval).and_then(|_| {
+                rubtle.safe_call(3, 1, |ctx| {
+                    ffi::duk_put_prop(ctx, -3);
+
+                    0
+                })
+            });
+
+            ffi::duk_set_top(rubtle.ctx, top);
+
+            result
+        }
+    }
+
+    ///
+    /// Check whether object has property, including inherited ones
+    ///
+    /// # Arguments
+    ///
+    /// * `rubtle` - Rubtle instance the object belongs to
+    /// * `key` - Name of the property
+    ///
+    /// # Returns
+    ///
+    /// `true` if the property exists; otherwise `false` or the thrown
+    /// exception as `Error`
+    ///
+
+    pub fn has(&self, rubtle: &Rubtle, key: &str) -> Result<bool, Error> {
+        unsafe {
+            let top = ffi::duk_get_top(rubtle.ctx);
+
+            ffi::duk_require_stack(rubtle.ctx, 2);
+
+            self.push(rubtle.ctx)?;
+            rubtle.push_value(&Value::from(key));
+
+            let result = rubtle
+                .safe_call(2, 1, |ctx| {
+                    let found = ffi::duk_has_prop(ctx, -2);
+
+                    ffi::duk_push_boolean(ctx, found);
+
+                    1
+                })
+                .map(|_| 0 != ffi::duk_get_boolean(rubtle.ctx, -1));
+
+            ffi::duk_set_top(rubtle.ctx, top);
+
+            result
+        }
+    }
+
+    ///
+    /// Delete property
+    ///
+    /// # Arguments
+    ///
+    /// * `rubtle` - Rubtle instance the object belongs to
+    /// * `key` - Name of the property
+    ///
+    /// # Returns
+    ///
+    /// Either nothing or the thrown exception as `Error`, e.g. for
+    /// non-configurable properties
+    ///
+
+    pub fn delete(&self, rubtle: &Rubtle, key: &str) -> Result<(), Error> {
+        unsafe {
+            let top = ffi::duk_get_top(rubtle.ctx);
+
+            ffi::duk_require_stack(rubtle.ctx, 2);
+
+            self.push(rubtle.ctx)?;
+            rubtle.push_value(&Value::from(key));
+
+            let result = rubtle.safe_call(2, 1, |ctx| {
+                ffi::duk_del_prop(ctx, -2);
+
+                0
+            });
+
+            ffi::duk_set_top(rubtle.ctx, top);
+
+            result
+        }
+    }
+
+    ///
+    /// Return own enumerable property names
+    ///
+    /// # Arguments
+    ///
+    /// * `rubtle` - Rubtle instance the object belongs to
+    ///
+    /// # Returns
+    ///
+    /// Names in enumeration order or the thrown exception as `Error`
+    ///
+
+    pub fn keys(&self, rubtle: &Rubtle) -> Result<Vec<String>, Error> {
+        unsafe {
+            let top = ffi::duk_get_top(rubtle.ctx);
+
+            ffi::duk_require_stack(rubtle.ctx, 1);
+
+            self.push(rubtle.ctx)?;
+
+            let result = rubtle
+                .safe_call(1, 1, |ctx| {
+                    let mut idx = 0;
+
+                    ffi::duk_require_stack(ctx, 3);
+                    ffi::duk_enum(ctx, -1, ffi::DUK_ENUM_OWN_PROPERTIES_ONLY);
+                    ffi::duk_push_array(ctx);
+
+                    while 0 != ffi::duk_next(ctx, -2, 0) {
+                        ffi::duk_put_prop_index(ctx, -2, idx);
+
+                        idx += 1;
+                    }
+
+                    1
+                })
+                .map(|_| match rubtle.pop_value() {
+                    Some(Value::Array(keys)) => keys
+                        .into_iter()
+                        .filter_map(|key| key.as_string().cloned())
+                        .collect(),
+                    _ => Vec::new(),
+                });
+
+            ffi::duk_set_top(rubtle.ctx, top);
+
+            result
+        }
+    }
+
+    ///
+    /// Return length of arrays or number of own enumerable properties of objects
+    ///
+    /// # Arguments
+    ///
+    /// * `rubtle` - Rubtle instance the object belongs to
+    ///
+    /// # Returns
+    ///
+    /// Length or the thrown exception as `Error`
+    ///
+
+    pub fn len(&self, rubtle: &Rubtle) -> Result<usize, Error> {
+        if self.is_array(rubtle) {
+            unsafe {
+                self.push(rubtle.ctx)?;
+
+                let len = ffi::duk_get_length(rubtle.ctx, -1);
+
+                ffi::duk_pop(rubtle.ctx);
+
+                Ok(len as usize)
+            }
+        } else {
+            self.keys(rubtle).map(|keys| keys.len())
+        }
+    }
+
+    ///
+    /// Check whether object is an array
+    ///
+    /// # Arguments
+    ///
+    /// * `rubtle` - Rubtle instance the object belongs to
+    ///
+    /// # Returns
+    ///
+    /// `true` if the object is an array; otherwise `false`
+    ///
+
+    pub fn is_array(&self, rubtle: &Rubtle) -> bool {
+        unsafe {
+            if self.push(rubtle.ctx).is_err() {
+                return false;
+            }
+
+            let is_array = 1 == ffi::duk_is_array(rubtle.ctx, -1);
+
+            ffi::duk_pop(rubtle.ctx);
+
+            is_array
+        }
+    }
+
+    ///
+    /// Copy object into a `Value`
+    ///
+    /// # Arguments
+    ///
+    /// * `rubtle` - Rubtle instance the object belongs to
+    ///
+    /// # Returns
+    ///
+    /// Deep copy of the object as `Value`
+    ///
+
+    pub fn to_value(&self, rubtle: &Rubtle) -> Option<Value> {
+        unsafe {
+            self.push(rubtle.ctx).ok()?;
+        }
+
+        rubtle.pop_value()
+    }
+
+    ///
+    /// Push object and get property onto the stack
+    ///
+    /// # Arguments
+    ///
+    /// * `rubtle` - Rubtle instance the object belongs to
+    /// * `key` - Name of the property
+    ///
+    /// # Returns
+    ///
+    /// Either nothing or the thrown exception as `Error`
+    ///
+
+    unsafe fn get_top(&self, rubtle: &Rubtle, key: &str) -> Result<(), Error> {
+        ffi::duk_require_stack(rubtle.ctx, 2);
+
+        self.push(rubtle.ctx)?;
+        rubtle.push_value(&Value::from(key));
+
+        rubtle.safe_call(2, 1, |ctx| {
+            ffi::duk_get_prop(ctx, -2);
+
+            1
+        })
+    }
+}
+
+impl fmt::Debug for JsObject {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "object")
+    }
+}
+
+impl PartialEq for JsObject {
+    fn eq(&self, other: &Self) -> bool {
+        self.ptr == other.ptr && self.obj.is_alive()
+    }
+}
@@ -0,0 +1,122 @@
+///
+/// @package Rubtle-Lib
+///
+/// @file JSON options
+/// @copyright 2020-present Christoph Kappel <christoph@unexist.dev>
+/// @version $Id$
+///
+/// This program can be distributed under the terms of the GNU GPLv2.
+/// See the file LICENSE for details.
+//
+
+/// Format of the JSON codec
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JsonFormat {
+    /// Standard JSON like `JSON.stringify` and `JSON.parse`
+    Json,
+
+    /// Extended JSON of duktape; keeps undefined, buffers and special numbers
+    Jx,
+
+    /// Compatible JSON of duktape; like JX but valid JSON
+    Jc,
+}
+
+/// Options for encoding and decoding JSON
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonOptions {
+    pub(crate) format: JsonFormat,
+    pub(crate) indent: usize,
+}
+
+impl Default for JsonOptions {
+    fn default() -> Self {
+        JsonOptions {
+            format: JsonFormat::Json,
+            indent: 0,
+        }
+    }
+}
+
+impl JsonFormat {
+    ///
+    /// Return format number for the codec helpers of the wrapper
+    ///
+    /// # Returns
+    ///
+    /// Value of the matching `RUST_DUK_JSON_FORMAT_*` define
+    ///
+
+    pub(crate) fn codec_format(&self) -> ffi::duk_int_t {
+        match self {
+            JsonFormat::Json => 0,
+            JsonFormat::Jx => 1,
+            JsonFormat::Jc => 2,
+        }
+    }
+}
+
+impl JsonOptions {
+    ///
+    /// Create new options with defaults
+    ///
+    /// # Returns
+    ///
+    /// New `JsonOptions` for compact standard JSON
+    ///
+
+    pub fn new() -> JsonOptions {
+        JsonOptions::default()
+    }
+
+    ///
+    /// Set format of the codec
+    ///
+    /// # Arguments
+    ///
+    /// * `format` - Either JSON, JX or JC
+    ///
+    /// # Example
+    ///
+    ///     use rubtle_lib::{JsonFormat, JsonOptions, Rubtle, Value};
+    ///
+    ///     let rubtle = Rubtle::new();
+    ///     let options = JsonOptions::new().with_format(JsonFormat::Jx);
+    ///
+    ///     let json = rubtle.json_encode_with(&Value::from(&vec![1, 2]), &options).unwrap();
+    ///
+    ///     assert_eq!("[1,2]", json);
+    ///
+
+    pub fn with_format(mut self, format: JsonFormat) -> Self {
+        self.format = format;
+
+        self
+    }
+
+    ///
+    /// Set indentation for pretty printing
+    ///
+    /// # Arguments
+    ///
+    /// * `indent` - Number of spaces per level; 0 for compact output and
+    ///   at most 10 like `JSON.stringify` allows
+    ///
+    /// # Example
+    ///
+    ///     use rubtle_lib::{JsonOptions, Rubtle, Value};
+    ///
+    ///     let rubtle = Rubtle::new();
+    ///     let options = JsonOptions::new().with_indent(2);
+    ///
+    ///     let json = rubtle.json_encode_with(&Value::from(&vec![1]), &options).unwrap();
+    ///
+    ///     assert_eq!("[\n  1\n]", json);
+    ///
+
+    pub fn with_indent(mut self, indent: usize) -> Self {
+        self.indent = indent;
+
+        self
+    }
+}
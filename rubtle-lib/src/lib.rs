@@ -14,11 +14,28 @@ extern crate rubtle_duktape as ffi;
 #[macro_use]
 mod util;
 
+mod allocator;
+mod conversion;
+mod convert;
+#[cfg(feature = "serde")]
+mod de;
 mod debug;
 mod error;
+mod external_buffer;
+mod heap;
+mod interrupt;
 mod invocation;
+mod js_function;
+mod js_object;
+mod json;
 mod object_builder;
+mod object_map;
 mod rubtle;
+mod rubtle_builder;
+mod script;
+#[cfg(feature = "serde")]
+mod ser;
+mod stash;
 mod types;
 mod value;
 mod function;
@@ -26,10 +43,28 @@ mod function;
 #[cfg(test)]
 mod tests;
 
-pub use error::Error;
+pub use allocator::MemoryUsage;
+pub use conversion::{
+    ConversionOptions, CycleHandling, FunctionHandling, DEFAULT_MAX_DEPTH, DEFAULT_MAX_NODES,
+};
+pub use convert::{FromJs, ToJs};
+#[cfg(feature = "serde")]
+pub use de::from_value;
+pub use error::{Error, JsError, Limit};
+pub use external_buffer::{ExternalBuffer, ExternalBytes};
+pub use heap::HeapStats;
+pub use interrupt::InterruptHandle;
 pub use invocation::Invocation;
+pub use js_function::JsFunction;
+pub use js_object::JsObject;
+pub use json::{JsonFormat, JsonOptions};
 pub use object_builder::{Object, ObjectBuilder};
+pub use object_map::ObjectMap;
 pub use rubtle::Rubtle;
+pub use rubtle_builder::RubtleBuilder;
+pub use script::Script;
+#[cfg(feature = "serde")]
+pub use ser::to_value;
 pub use types::{Callback, CallbackResult};
-pub use value::Value;
+pub use value::{BufferType, Value};
 pub use function::Function;
@@ -0,0 +1,373 @@
+///
+/// @package Rubtle-Lib
+///
+/// @file Object map functions
+/// @copyright 2020-present Christoph Kappel <christoph@unexist.dev>
+/// @version $Id$
+///
+/// This program can be distributed under the terms of the GNU GPLv2.
+/// See the file LICENSE for details.
+//
+use std::collections::HashMap;
+use std::fmt;
+use std::iter::FromIterator;
+use std::ops::Index;
+use std::{slice, vec};
+
+use crate::Value;
+
+/// Properties of a `Value::Object` in ECMAScript enumeration order
+///
+/// Array index keys come first in ascending order, all other keys follow in
+/// insertion order. Equality ignores the order like JS deep equality does.
+#[derive(Clone, Default)]
+pub struct ObjectMap {
+    entries: Vec<(String, Value)>,
+
+    /// Position of every key in entries
+    indices: HashMap<String, usize>,
+
+    /// Sorted array indices of the leading array index keys
+    index_keys: Vec<u32>,
+}
+
+/// Iterator over the properties of an `ObjectMap`
+pub struct Iter<'a> {
+    inner: slice::Iter<'a, (String, Value)>,
+}
+
+/// Owning iterator over the properties of an `ObjectMap`
+pub struct IntoIter {
+    inner: vec::IntoIter<(String, Value)>,
+}
+
+///
+/// Parse key as array index like ECMAScript does
+///
+/// # Arguments
+///
+/// * `key` - Key to parse
+///
+/// # Returns
+///
+/// `Option` with the index if the key is a canonical array index; otherwise `None`
+///
+
+fn array_index(key: &str) -> Option<u32> {
+    match key.parse::<u32>() {
+        Ok(idx) if u32::MAX != idx && idx.to_string() == key => Some(idx),
+        _ => None,
+    }
+}
+
+impl ObjectMap {
+    ///
+    /// Create a new empty map
+    ///
+    /// # Returns
+    ///
+    /// A new `ObjectMap`
+    ///
+
+    pub fn new() -> ObjectMap {
+        ObjectMap::default()
+    }
+
+    ///
+    /// Return number of properties
+    ///
+    /// # Returns
+    ///
+    /// Number of properties
+    ///
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    ///
+    /// Check whether map is empty
+    ///
+    /// # Returns
+    ///
+    /// `true` if the map has no properties; otherwise `false`
+    ///
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    ///
+    /// Check whether map has property
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - Name of the property
+    ///
+    /// # Returns
+    ///
+    /// `true` if the property exists; otherwise `false`
+    ///
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.indices.contains_key(key)
+    }
+
+    ///
+    /// Get value of property
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - Name of the property
+    ///
+    /// # Returns
+    ///
+    /// `Option` either with the value or without
+    ///
+
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.indices.get(key).map(|&pos| &self.entries[pos].1)
+    }
+
+    ///
+    /// Get mutable value of property
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - Name of the property
+    ///
+    /// # Returns
+    ///
+    /// `Option` either with the value or without
+    ///
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Value> {
+        match self.indices.get(key) {
+            Some(&pos) => Some(&mut self.entries[pos].1),
+            None => None,
+        }
+    }
+
+    ///
+    /// Insert property; existing properties keep their position
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - Name of the property
+    /// * `value` - Value of the property
+    ///
+    /// # Returns
+    ///
+    /// `Option` with the replaced value or without
+    ///
+    /// # Example
+    ///
+    ///     use rubtle_lib::{ObjectMap, Value};
+    ///
+    ///     let mut map = ObjectMap::new();
+    ///
+    ///     map.insert("b", Value::from(1));
+    ///     map.insert("a", Value::from(2));
+    ///     map.insert("1", Value::from(3));
+    ///
+    ///     assert_eq!(vec!["1", "b", "a"], map.keys().collect::<Vec<_>>());
+    ///
+
+    pub fn insert<K: Into<String>>(&mut self, key: K, value: Value) -> Option<Value> {
+        let key = key.into();
+
+        if let Some(&pos) = self.indices.get(&key) {
+            return Some(std::mem::replace(&mut self.entries[pos].1, value));
+        }
+
+        let pos = match array_index(&key) {
+            Some(idx) => {
+                let pos = self.index_keys.partition_point(|&other| other < idx);
+
+                self.index_keys.insert(pos, idx);
+
+                pos
+            }
+            None => self.entries.len(),
+        };
+
+        self.entries.insert(pos, (key.clone(), value));
+        self.indices.insert(key, pos);
+        self.reindex(pos + 1);
+
+        None
+    }
+
+    ///
+    /// Remove property
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - Name of the property
+    ///
+    /// # Returns
+    ///
+    /// `Option` with the removed value or without
+    ///
+
+    pub fn remove(&mut self, key: &str) -> Option<Value> {
+        let pos = self.indices.remove(key)?;
+
+        if pos < self.index_keys.len() {
+            self.index_keys.remove(pos);
+        }
+
+        let (_, value) = self.entries.remove(pos);
+
+        self.reindex(pos);
+
+        Some(value)
+    }
+
+    ///
+    /// Return iterator over the names of the properties
+    ///
+    /// # Returns
+    ///
+    /// Iterator in enumeration order
+    ///
+
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|(k, _)| k.as_str())
+    }
+
+    ///
+    /// Return iterator over the values of the properties
+    ///
+    /// # Returns
+    ///
+    /// Iterator in enumeration order
+    ///
+
+    pub fn values(&self) -> impl Iterator<Item = &Value> {
+        self.entries.iter().map(|(_, v)| v)
+    }
+
+    ///
+    /// Return iterator over the properties
+    ///
+    /// # Returns
+    ///
+    /// Iterator in enumeration order
+    ///
+
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            inner: self.entries.iter(),
+        }
+    }
+
+    ///
+    /// Update positions of keys starting at given position
+    ///
+    /// # Arguments
+    ///
+    /// * `start` - First position to update
+    ///
+
+    fn reindex(&mut self, start: usize) {
+        for (pos, (key, _)) in self.entries.iter().enumerate().skip(start) {
+            if let Some(idx) = self.indices.get_mut(key) {
+                *idx = pos;
+            }
+        }
+    }
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = (&'a String, &'a Value);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(k, v)| (k, v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl Iterator for IntoIter {
+    type Item = (String, Value);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a> ExactSizeIterator for Iter<'a> {}
+
+impl ExactSizeIterator for IntoIter {}
+
+impl<'a> IntoIterator for &'a ObjectMap {
+    type Item = (&'a String, &'a Value);
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+impl IntoIterator for ObjectMap {
+    type Item = (String, Value);
+    type IntoIter = IntoIter;
+
+    fn into_iter(self) -> IntoIter {
+        IntoIter {
+            inner: self.entries.into_iter(),
+        }
+    }
+}
+
+impl<K: Into<String>> FromIterator<(K, Value)> for ObjectMap {
+    fn from_iter<I: IntoIterator<Item = (K, Value)>>(iter: I) -> ObjectMap {
+        let mut map = ObjectMap::new();
+
+        map.extend(iter);
+
+        map
+    }
+}
+
+impl<K: Into<String>> Extend<(K, Value)> for ObjectMap {
+    fn extend<I: IntoIterator<Item = (K, Value)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl From<HashMap<String, Value>> for ObjectMap {
+    fn from(src: HashMap<String, Value>) -> Self {
+        src.into_iter().collect()
+    }
+}
+
+impl<'a> Index<&'a str> for ObjectMap {
+    type Output = Value;
+
+    fn index(&self, key: &str) -> &Value {
+        self.get(key).expect("Property not found")
+    }
+}
+
+impl PartialEq for ObjectMap {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len()
+            && self.iter().all(|(k, v)| other.get(k).map_or(false, |other_v| v == other_v))
+    }
+}
+
+impl fmt::Debug for ObjectMap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
@@ -8,18 +8,32 @@
 /// This program can be distributed under the terms of the GNU GPLv2.
 /// See the file LICENSE for details.
 ///
-use std::{process, ptr, slice};
+use std::{mem, process, ptr, slice};
 
+use std::any::Any;
 use std::ffi::{CStr, CString};
 use std::os::raw::{c_char, c_void};
 use std::panic::{catch_unwind, AssertUnwindSafe};
-use std::collections::HashMap;
+use std::rc::Rc;
+use std::time::Duration;
 
 use cesu8::{from_cesu8, to_cesu8};
 
+use crate::allocator::{self, MemoryUsage};
+use crate::conversion::{ConversionOptions, ConversionState, FunctionHandling};
+use crate::convert;
+use crate::function::{self, FunctionKind};
+use crate::heap::{HeapData, HeapStats};
+use crate::json::JsonOptions;
 use crate::object_builder::Object;
+use crate::script::{self, Script};
+use crate::stash::StashRef;
+use crate::value;
 use crate::types::{Callback, CallbackResult, ObjectBuilderCallback, ObjectBuilderCtor};
-use crate::{Invocation, Value};
+use crate::{
+    BufferType, Error, ExternalBuffer, FromJs, Invocation, InterruptHandle, JsError, JsFunction,
+    JsObject, Limit, ObjectMap, ToJs, Value,
+};
 
 #[allow(unused_imports)]
 use crate::debug::*;
@@ -51,183 +65,248 @@ impl Rubtle {
 
     pub fn new() -> Rubtle {
         Rubtle {
-            ctx: unsafe { Self::create_heap() },
+            ctx: unsafe { Self::create_heap(HeapData::new(None)).expect("Failed to create heap") },
             drop_ctx: true,
         }
     }
 
     ///
-    /// Push value onto duktape stack
+    /// Limit execution time of top-level runs
     ///
     /// # Arguments
     ///
-    /// * `rval` - String value to push
+    /// * `limit` - Maximum time each eval or script run may take
     ///
     /// # Example
     ///
-    ///     use rubtle_lib::{Rubtle, Value};
+    ///     use std::time::Duration;
+    ///
+    ///     use rubtle_lib::{Rubtle, Error, Limit};
     ///
     ///     let rubtle = Rubtle::new();
-    ///     let rval = Value::from(4);
     ///
-    ///     rubtle.push_value(&rval);
+    ///     rubtle.set_time_limit(Duration::from_millis(10));
+    ///
+    ///     assert_eq!(Err(Error::Limit(Limit::Time)), rubtle.eval("while(true) {}"));
     ///
 
-    pub fn push_value(&self, rval: &Value) {
+    pub fn set_time_limit(&self, limit: Duration) {
         unsafe {
-            match rval {
-                Value::Boolean(val) => {
-                    ffi::duk_require_stack(self.ctx, 1);
-                    ffi::duk_push_boolean(self.ctx, if *val { 1 } else { 0 });
-                }
-
-                Value::Number(val) => {
-                    ffi::duk_require_stack(self.ctx, 1);
-                    ffi::duk_push_number(self.ctx, *val);
-                }
-
-                Value::Str(val) => {
-                    let cstr = CString::new(to_cesu8(&val[..]));
-
-                    match cstr {
-                        Ok(cval) => {
-                            ffi::duk_require_stack(self.ctx, 1);
-                            ffi::duk_push_lstring(
-                                self.ctx,
-                                cval.as_ptr(),
-                                cval.as_bytes().len() as u64,
-                            );
-                        }
-                        Err(_) => unimplemented!(),
-                    }
-                },
-
-                Value::None => {
-                    ffi::duk_require_stack(self.ctx, 1);
-                    ffi::duk_push_undefined(self.ctx);
-                },
-
-                Value::Array(val) => {
-                    let ary_idx = ffi::duk_push_array(self.ctx);
-                    let mut pos = 0;
-
-                    for v in val {
-                        self.push_value(v);
-                        ffi::duk_put_prop_index(self.ctx, ary_idx, pos);
-                        pos += 1;
-                    }
-                },
+            HeapData::from_ctx(self.ctx).time_limit.set(Some(limit));
+        }
+    }
 
-                Value::Object(val) => {
-                    let obj_idx = ffi::duk_push_object(self.ctx);
+    ///
+    /// Remove execution time limit
+    ///
 
-                    for (k, v) in val {
-                        let cstr = CString::new(to_cesu8(&k[..]));
+    pub fn clear_time_limit(&self) {
+        unsafe {
+            HeapData::from_ctx(self.ctx).time_limit.set(None);
+        }
+    }
 
-                        match cstr {
-                            Ok(cval) => {
-                                self.push_value(v);
-                                ffi::duk_put_prop_lstring(
-                                    self.ctx,
-                                    obj_idx,
-                                    cval.as_ptr(),
-                                    cval.as_bytes().len() as u64,
-                                );
-                            }
-                            Err(_) => unimplemented!(),
-                        }
-                    }
-                },
+    ///
+    /// Set JS type binary data is pushed as
+    ///
+    /// # Arguments
+    ///
+    /// * `buffer_type` - Type to use for `Value::Bytes`; defaults to `Uint8Array`
+    ///   and can be overridden per call with `push_bytes_as`
+    ///
+    /// # Example
+    ///
+    ///     use rubtle_lib::{BufferType, Rubtle, Value};
+    ///
+    ///     let rubtle = Rubtle::new();
+    ///
+    ///     rubtle.set_buffer_type(BufferType::ArrayBuffer);
+    ///     rubtle.set_global_value("rubtle", &Value::from(vec![1u8, 2, 3]));
+    ///
+    ///     assert_eq!(Value::from(true), rubtle.eval("rubtle instanceof ArrayBuffer").unwrap());
+    ///
 
-                Value::Function(_) => {
-                    unimplemented!();
-                }
-            }
+    pub fn set_buffer_type(&self, buffer_type: BufferType) {
+        unsafe {
+            HeapData::from_ctx(self.ctx).buffer_type.set(buffer_type);
         }
     }
 
     ///
-    /// Pop most recent value from duktape stack
+    /// Lend memory to JS without copying for the duration of the closure
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - Memory to lend
+    /// * `func` - Closure that gets the guard of the memory
     ///
     /// # Returns
     ///
-    /// Any value on top of the stack as `Value`
+    /// Result of the closure
+    ///
+    /// All JS buffers created from the guard are detached before this returns,
+    /// even if the closure panics.
     ///
     /// # Example
     ///
     ///     use rubtle_lib::{Rubtle, Value};
     ///
     ///     let rubtle = Rubtle::new();
-    ///     let rval = Value::from(4);
+    ///     let mut data = vec![1u8, 2, 3];
     ///
-    ///     rubtle.push_value(&rval);
+    ///     rubtle.with_external_buffer(&mut data, |buf| {
+    ///         rubtle.set_global_value("rubtle", &buf.to_value());
+    ///         rubtle.eval("rubtle[0] = 4;").unwrap();
+    ///     });
     ///
-    ///     let rval2 = rubtle.pop_value().unwrap();
+    ///     assert_eq!(vec![4u8, 2, 3], data);
+    ///     assert_eq!(Value::from(0), rubtle.eval("rubtle[0]").unwrap());
     ///
 
-    pub fn pop_value(&self) -> Option<Value> {
-        self.pop_value_at(-1)
+    pub fn with_external_buffer<F, R>(&self, data: &mut [u8], func: F) -> R
+    where
+        F: FnOnce(&ExternalBuffer) -> R,
+    {
+        let buf = ExternalBuffer::new(data);
+
+        func(&buf)
     }
 
-    fn handle_objects(&self) -> Option<Value> {
-        unsafe {
-            /* Handle arrays */
-            if 1 == ffi::duk_is_array(self.ctx, -1) {
-                let mut vec: Vec<Value> = Vec::new();
+    ///
+    /// Create handle to interrupt running scripts from other threads
+    ///
+    /// # Returns
+    ///
+    /// A new `InterruptHandle`
+    ///
 
-                ffi::duk_enum(self.ctx, -1, ffi::DUK_ENUM_ARRAY_INDICES_ONLY);
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        unsafe {
+            InterruptHandle {
+                flag: HeapData::from_ctx(self.ctx).interrupt.clone(),
+            }
+        }
+    }
 
-                while 0 != ffi::duk_next(self.ctx, -1, 1) {
-                    match self.pop_value_at(-1) {
-                        Some(val) => vec.push(Value::from(val)),
-                        None => ffi::duk_pop(self.ctx),
-                    }
+    ///
+    /// Return memory usage of heaps with Rust allocator
+    ///
+    /// # Returns
+    ///
+    /// `Option` with the usage; `None` for heaps with the default allocator
+    ///
+    /// # Example
+    ///
+    ///     use rubtle_lib::RubtleBuilder;
+    ///
+    ///     let rubtle = RubtleBuilder::new()
+    ///         .with_memory_limit(1024 * 1024)
+    ///         .build();
+    ///
+    ///     let usage = rubtle.memory_usage().unwrap();
+    ///
+    ///     assert!(usage.allocated <= usage.peak);
+    ///
 
-                    /* Remove iter from stack*/
-                    ffi::duk_pop(self.ctx);
-                }
+    pub fn memory_usage(&self) -> Option<MemoryUsage> {
+        unsafe {
+            HeapData::from_ctx(self.ctx)
+                .memory
+                .as_ref()
+                .map(|memory| memory.usage())
+        }
+    }
 
-                /* Remove enum */
-                ffi::duk_pop(self.ctx);
+    ///
+    /// Run a full garbage collection
+    ///
+    /// Duktape needs two passes to free objects with finalizers, because
+    /// they are rescued once to run the finalizer, so this does both.
+    ///
+    /// # Example
+    ///
+    ///     use rubtle_lib::Rubtle;
+    ///
+    ///     let rubtle = Rubtle::new();
+    ///
+    ///     rubtle.eval("var garbage = [1, 2, 3]; garbage = null;").unwrap();
+    ///     rubtle.gc();
+    ///
 
-                Some(Value::Array(vec))
-            } else if 1 == ffi::duk_is_object(self.ctx, -1) {
-                let mut hash = HashMap::new();
+    pub fn gc(&self) {
+        self.gc_with_flags(0);
+    }
 
-                ffi::duk_enum(self.ctx, -1, 0);
+    ///
+    /// Run a full garbage collection and compact objects afterwards
+    ///
+    /// Compaction shrinks property tables of all live objects to their
+    /// minimum size, which frees memory at the cost of a slower run.
+    ///
 
-                while 0 != ffi::duk_next(self.ctx, -1, 1) {
-                    /* Pop value and key in reverse */
-                    let value = self.pop_value_at(-1);
-                    let key = self.pop_value_at(-1);
+    pub fn gc_compact(&self) {
+        self.gc_with_flags(ffi::DUK_GC_COMPACT);
+    }
 
-                    if !key.is_none() && !value.is_none() {
-                        hash.insert(key.unwrap().as_string().unwrap().clone(), value.unwrap());
-                    } else {
-                        ffi::duk_pop_2(self.ctx);
-                    }
-                }
+    fn gc_with_flags(&self, flags: ffi::duk_uint_t) {
+        unsafe {
+            ffi::duk_gc(self.ctx, flags);
+            ffi::duk_gc(self.ctx, flags);
+        }
+    }
 
-                /* Remove enum */
-                ffi::duk_pop(self.ctx);
+    ///
+    /// Return statistics about the objects on the heap
+    ///
+    /// # Returns
+    ///
+    /// Snapshot of the heap as `HeapStats`; allocated bytes are only known
+    /// for heaps with memory tracking, see `RubtleBuilder::with_memory_tracking`
+    ///
+    /// # Example
+    ///
+    ///     use rubtle_lib::Rubtle;
+    ///
+    ///     let rubtle = Rubtle::new();
+    ///
+    ///     rubtle.eval("var objs = [{}, {}, {}];").unwrap();
+    ///
+    ///     let stats = rubtle.heap_stats();
+    ///
+    ///     assert!(4 <= stats.objects);
+    ///     assert_eq!(None, stats.allocated_bytes);
+    ///
 
-                Some(Value::Object(hash))
-            } else {
-                Some(Value::None)
+    pub fn heap_stats(&self) -> HeapStats {
+        unsafe {
+            let mut stats: ffi::rust_duk_heap_stats = mem::zeroed();
+
+            ffi::rust_duk_get_heap_stats(self.ctx, &mut stats);
+
+            HeapStats {
+                allocated_bytes: HeapData::from_ctx(self.ctx)
+                    .memory
+                    .as_ref()
+                    .map(|memory| memory.usage().allocated),
+                objects: stats.objects as usize,
+                strings: stats.strings as usize,
+                buffers: stats.buffers as usize,
+                pending_finalizers: stats.pending_finalizers as usize,
+                finalized: HeapData::from_ctx(self.ctx).finalized.get(),
             }
         }
     }
 
     ///
-    /// Pop value on given index from duktape stack
+    /// Push value onto duktape stack
     ///
     /// # Arguments
     ///
-    /// * `idx` - Stack index; -1 for top
+    /// * `rval` - String value to push
     ///
-    /// # Returns
+    /// # Panics
     ///
-    /// Any value on top of the stack as `Option<Value>`
+    /// Panics if the value holds handles of another or a destroyed heap
     ///
     /// # Example
     ///
@@ -238,530 +317,2417 @@ impl Rubtle {
     ///
     ///     rubtle.push_value(&rval);
     ///
-    ///     let rval2 = rubtle.pop_value_at(-1).unwrap();
-    //
 
-    pub fn pop_value_at(&self, idx: ffi::duk_idx_t) -> Option<Value> {
+    pub fn push_value(&self, rval: &Value) {
+        if let Err(err) = self.try_push_value(rval) {
+            panic!("{}", err);
+        }
+    }
+
+    ///
+    /// Push value onto duktape stack unless it holds unusable handles
+    ///
+    /// # Arguments
+    ///
+    /// * `rval` - Value to push
+    ///
+    /// # Returns
+    ///
+    /// Either nothing or `Error::Heap` for handles of another or a destroyed
+    /// heap; the stack is left untouched on errors
+    ///
+
+    pub(crate) fn try_push_value(&self, rval: &Value) -> Result<(), Error> {
         unsafe {
-            match ffi::duk_get_type(self.ctx, idx) as u32 {
-                ffi::DUK_TYPE_BOOLEAN => {
-                    let dval = ffi::duk_get_boolean(self.ctx, idx);
+            let top = ffi::duk_get_top(self.ctx);
 
-                    ffi::duk_remove(self.ctx, idx);
+            let result = self.push_value_top(rval);
 
-                    Some(Value::Boolean(0 != dval))
-                },
+            if result.is_err() {
+                ffi::duk_set_top(self.ctx, top);
+            }
 
-                ffi::DUK_TYPE_NUMBER => {
-                    let dval = ffi::duk_get_number(self.ctx, idx);
+            result
+        }
+    }
 
-                    ffi::duk_remove(self.ctx, idx);
+    ///
+    /// Push value onto duktape stack
+    ///
+    /// # Arguments
+    ///
+    /// * `rval` - Value to push
+    ///
+    /// # Returns
+    ///
+    /// Either nothing or `Error::Heap`; partially pushed values are left on
+    /// the stack on errors
+    ///
 
-                    Some(Value::Number(dval))
-                },
+    unsafe fn push_value_top(&self, rval: &Value) -> Result<(), Error> {
+        match rval {
+            Value::Boolean(val) => {
+                ffi::duk_require_stack(self.ctx, 1);
+                ffi::duk_push_boolean(self.ctx, if *val { 1 } else { 0 });
+            }
 
-                ffi::DUK_TYPE_STRING => {
-                    let mut len = 0;
+            Value::Number(val) => {
+                ffi::duk_require_stack(self.ctx, 1);
+                ffi::duk_push_number(self.ctx, *val);
+            }
 
-                    let dval = ffi::duk_get_lstring(self.ctx, idx, &mut len);
+            Value::Str(val) => convert::push_str(self.ctx, val),
 
-                    assert!(!dval.is_null(), "String is null");
+            Value::None => {
+                ffi::duk_require_stack(self.ctx, 1);
+                ffi::duk_push_undefined(self.ctx);
+            },
 
-                    ffi::duk_remove(self.ctx, idx);
+            Value::Null => {
+                ffi::duk_require_stack(self.ctx, 1);
+                ffi::duk_push_null(self.ctx);
+            },
 
-                    let bytes = slice::from_raw_parts(dval as *const u8, len as usize);
+            Value::Array(val) => {
+                let ary_idx = ffi::duk_push_array(self.ctx);
+                let mut pos = 0;
 
-                    match from_cesu8(bytes) {
-                        Ok(string) => Some(Value::Str(string.into_owned())),
-                        Err(_) => None,
+                for v in val {
+                    self.try_push_value(v)?;
+                    ffi::duk_put_prop_index(self.ctx, ary_idx, pos);
+                    pos += 1;
+                }
+            },
+
+            Value::Object(val) => {
+                let obj_idx = ffi::duk_push_object(self.ctx);
+
+                for (k, v) in val {
+                    let cstr = CString::new(to_cesu8(&k[..]));
+
+                    match cstr {
+                        Ok(cval) => {
+                            self.try_push_value(v)?;
+                            ffi::duk_put_prop_lstring(
+                                self.ctx,
+                                obj_idx,
+                                cval.as_ptr(),
+                                cval.as_bytes().len() as u64,
+                            );
+                        }
+                        Err(_) => unimplemented!(),
                     }
-                },
+                }
+            },
+
+            Value::Bytes(val) => {
+                self.push_bytes_top(val, HeapData::from_ctx(self.ctx).buffer_type.get());
+            },
 
-                ffi::DUK_TYPE_OBJECT => self.handle_objects(),
+            Value::External(val) => {
+                val.push(self.ctx);
 
-                ffi::DUK_TYPE_UNDEFINED => {
-                    Some(Value::None)
-                },
+                self.wrap_buffer_top(val.len(), HeapData::from_ctx(self.ctx).buffer_type.get());
+            },
 
-                _ => None,
-            }
+            Value::Date(val) => {
+                ffi::rust_duk_push_date(self.ctx, value::to_time_value(val));
+            },
+
+            Value::Function(val) => match val.kind {
+                FunctionKind::Js(ref func) => func.push(self.ctx)?,
+                FunctionKind::Rust(ref callback) => self.push_callback(callback),
+            },
+
+            Value::Ref(val) => val.push(self.ctx)?,
         }
+
+        Ok(())
     }
 
     ///
-    /// Set value to context and assign a global reachable name
+    /// Push bytes onto duktape stack as given buffer type
     ///
     /// # Arguments
     ///
-    /// `name`- Name of the value
-    /// `rval` - The actual value
+    /// * `bytes` - Bytes to push
+    /// * `buffer_type` - Type to use instead of the one of the heap
     ///
     /// # Example
     ///
-    ///     use rubtle_lib::{Rubtle, Value};
+    ///     use rubtle_lib::{BufferType, Rubtle, Value};
     ///
     ///     let rubtle = Rubtle::new();
-    ///     let rval = Value::from(4);
     ///
-    ///     rubtle.set_global_value("rubtle", &rval);
+    ///     rubtle.push_bytes_as(&[1, 2, 3], BufferType::ArrayBuffer);
+    ///
+    ///     assert_eq!(Some(Value::from(vec![1u8, 2, 3])), rubtle.pop_value());
     ///
 
-    pub fn set_global_value(&self, name: &str, rval: &Value) {
+    pub fn push_bytes_as(&self, bytes: &[u8], buffer_type: BufferType) {
         unsafe {
-            let cstr = CString::new(to_cesu8(name));
+            self.push_bytes_top(bytes, buffer_type);
+        }
+    }
 
-            match cstr {
-                Ok(cval) => {
-                    self.push_value(rval);
+    unsafe fn push_bytes_top(&self, bytes: &[u8], buffer_type: BufferType) {
+        ffi::duk_require_stack(self.ctx, 2);
 
-                    ffi::duk_require_stack(self.ctx, 1);
-                    ffi::duk_put_global_lstring(
-                        self.ctx,
-                        cval.as_ptr(),
-                        cval.as_bytes().len() as u64,
-                    );
-                }
-                Err(_) => unimplemented!(),
-            }
+        let buf = ffi::duk_push_buffer_raw(self.ctx, bytes.len() as u64, 0);
+
+        if !bytes.is_empty() {
+            ptr::copy_nonoverlapping(bytes.as_ptr(), buf as *mut u8, bytes.len());
         }
+
+        self.wrap_buffer_top(bytes.len(), buffer_type);
     }
 
     ///
-    /// Get value from context for given global reachable name
+    /// Wrap plain buffer on top of the stack into given buffer type
     ///
     /// # Arguments
     ///
-    /// `name`- Name of the value
+    /// * `len` - Length of the buffer
+    /// * `buffer_type` - Type to wrap the buffer into
+    ///
+
+    unsafe fn wrap_buffer_top(&self, len: usize, buffer_type: BufferType) {
+        let flags = match buffer_type {
+            BufferType::Plain => return,
+            BufferType::ArrayBuffer => ffi::DUK_BUFOBJ_ARRAYBUFFER,
+            BufferType::Uint8Array => ffi::DUK_BUFOBJ_UINT8ARRAY,
+            BufferType::DataView => ffi::DUK_BUFOBJ_DATAVIEW,
+            BufferType::NodeBuffer => ffi::DUK_BUFOBJ_NODEJS_BUFFER,
+        };
+
+        ffi::duk_require_stack(self.ctx, 1);
+        ffi::duk_push_buffer_object(self.ctx, -1, 0, len as u64, flags);
+        ffi::duk_remove(self.ctx, -2);
+    }
+
+    ///
+    /// Pop most recent value from duktape stack
     ///
     /// # Returns
     ///
-    /// Any value on top of the stack as `Option<Value>`
+    /// Any value on top of the stack as `Value`
     ///
     /// # Example
     ///
     ///     use rubtle_lib::{Rubtle, Value};
     ///
     ///     let rubtle = Rubtle::new();
+    ///     let rval = Value::from(4);
     ///
-    ///     rubtle.get_global_value("rubtle");
+    ///     rubtle.push_value(&rval);
+    ///
+    ///     let rval2 = rubtle.pop_value().unwrap();
     ///
 
-    pub fn get_global_value(&self, name: &str) -> Option<Value> {
-        unsafe {
-            let cstr = CString::new(to_cesu8(name));
+    pub fn pop_value(&self) -> Option<Value> {
+        self.pop_value_at(-1)
+    }
 
-            match cstr {
-                Ok(cval) => {
-                    ffi::duk_get_global_lstring(
-                        self.ctx,
-                        cval.as_ptr(),
-                        cval.as_bytes().len() as u64,
-                    );
+    ///
+    /// Coerce value to string like `String()` does in this heap
+    ///
+    /// # Arguments
+    ///
+    /// * `rval` - Value to coerce
+    ///
+    /// # Returns
+    ///
+    /// Either the coerced `String` or the `Error`; unlike
+    /// `Value::coerce_string` this handles dates, buffers and JS handles and
+    /// calls custom `toString` methods
+    ///
+    /// # Example
+    ///
+    ///     use std::time::{Duration, UNIX_EPOCH};
+    ///
+    ///     use rubtle_lib::{Rubtle, Value};
+    ///
+    ///     let rubtle = Rubtle::new();
+    ///     let date = Value::Date(UNIX_EPOCH + Duration::from_secs(86400 * 365));
+    ///
+    ///     assert!(rubtle.coerce_string(&date).unwrap().starts_with("1971"));
+    ///     assert_eq!("[object Uint8Array]", rubtle.coerce_string(&Value::from(vec![1u8])).unwrap());
+    ///
 
-                    self.pop_value()
-                }
-                Err(_) => None,
+    pub fn coerce_string(&self, rval: &Value) -> Result<String, Error> {
+        self.try_push_value(rval)?;
+
+        unsafe {
+            self.safe_call(1, 1, |ctx| {
+                ffi::duk_to_string(ctx, -1);
+
+                1
+            })?;
+
+            let string = convert::get_str_at(self.ctx, -1).unwrap_or_default();
+
+            ffi::duk_pop(self.ctx);
+
+            Ok(string)
+        }
+    }
+
+    ///
+    /// Push any `ToJs` type onto duktape stack
+    ///
+    /// # Arguments
+    ///
+    /// * `rval` - The value to push
+    ///
+    /// # Example
+    ///
+    ///     use rubtle_lib::Rubtle;
+    ///
+    ///     let rubtle = Rubtle::new();
+    ///
+    ///     rubtle.push(&vec![vec![1, 2], vec![3]]);
+    ///
+
+    pub fn push<T: ToJs + ?Sized>(&self, rval: &T) {
+        rval.to_js(self);
+    }
+
+    ///
+    /// Pop value from top of duktape stack as any `FromJs` type
+    ///
+    /// # Returns
+    ///
+    /// Either the converted value or a conversion `Error`; the value is
+    /// removed from the stack in both cases
+    ///
+    /// # Example
+    ///
+    ///     use rubtle_lib::Rubtle;
+    ///
+    ///     let rubtle = Rubtle::new();
+    ///
+    ///     rubtle.push(&(4, Some("rubtle")));
+    ///
+    ///     let (num, name): (u8, Option<String>) = rubtle.pop().unwrap();
+    ///
+    ///     assert_eq!(4, num);
+    ///     assert_eq!(Some(String::from("rubtle")), name);
+    ///
+
+    pub fn pop<T: FromJs>(&self) -> Result<T, Error> {
+        let result = T::from_js(self, -1);
+
+        unsafe {
+            ffi::duk_pop(self.ctx);
+        }
+
+        result
+    }
+
+    ///
+    /// Push object with given fields onto duktape stack
+    ///
+    /// # Arguments
+    ///
+    /// * `fields` - Names and values of the fields
+    ///
+    /// # Example
+    ///
+    ///     use rubtle_lib::{Rubtle, ToJs};
+    ///
+    ///     struct Point {
+    ///         x: i32,
+    ///         y: i32,
+    ///     }
+    ///
+    ///     impl ToJs for Point {
+    ///         fn to_js(&self, rubtle: &Rubtle) {
+    ///             rubtle.push_object_fields(&[("x", &self.x), ("y", &self.y)]);
+    ///         }
+    ///     }
+    ///
+    ///     let rubtle = Rubtle::new();
+    ///
+    ///     rubtle.set_global_value("point", &Point { x: 1, y: 2 });
+    ///
+
+    pub fn push_object_fields(&self, fields: &[(&str, &dyn ToJs)]) {
+        unsafe {
+            ffi::duk_require_stack(self.ctx, 3);
+            ffi::duk_push_object(self.ctx);
+
+            for (key, val) in fields {
+                convert::push_str(self.ctx, key);
+                val.to_js(self);
+
+                ffi::duk_put_prop(self.ctx, -3);
             }
         }
     }
 
     ///
-    /// Set closure/function as a global function to call from JS
+    /// Get field of object on given index as any `FromJs` type
     ///
     /// # Arguments
     ///
-    /// * `name` - Name of the global
-    /// * `func`- Closure/function to call
+    /// * `idx` - Stack index of the object
+    /// * `key` - Name of the field
+    ///
+    /// # Returns
+    ///
+    /// Either the converted field, a conversion `Error` or the exception thrown
+    /// by a getter as `Error::Exception`
     ///
     /// # Example
     ///
-    ///     use rubtle_lib::{Rubtle, Value, Invocation, CallbackResult};
+    ///     use rubtle_lib::{Error, FromJs, Rubtle};
+    ///
+    ///     struct Point {
+    ///         x: i32,
+    ///         y: i32,
+    ///     }
+    ///
+    ///     impl FromJs for Point {
+    ///         fn from_js(rubtle: &Rubtle, idx: i32) -> Result<Point, Error> {
+    ///             Ok(Point {
+    ///                 x: rubtle.get_object_field(idx, "x")?,
+    ///                 y: rubtle.get_object_field(idx, "y")?,
+    ///             })
+    ///         }
+    ///     }
     ///
     ///     let rubtle = Rubtle::new();
     ///
-    ///     let printer = |inv: Invocation<i8>| -> CallbackResult<Value> {
-    ///         let args = inv.args.unwrap();
-    ///         let s = args.first().unwrap();
+    ///     rubtle.eval("var point = { x: 1, y: 2 };").unwrap();
     ///
-    ///         println!("{:?}", s.as_string().unwrap());
+    ///     let point: Point = rubtle.get_global("point").unwrap();
     ///
-    ///         Ok(Value::from(true))
-    ///     };
+    ///     assert_eq!(2, point.y);
     ///
-    ///     rubtle.set_global_function("print", printer);
+
+    pub fn get_object_field<T: FromJs>(&self, idx: ffi::duk_idx_t, key: &str) -> Result<T, Error> {
+        unsafe {
+            if 1 != ffi::duk_is_object(self.ctx, idx) {
+                return Err(Error::Conversion {
+                    expected: "object",
+                    actual: convert::type_name_at(self.ctx, idx),
+                });
+            }
+
+            ffi::duk_require_stack(self.ctx, 1);
+            ffi::duk_dup(self.ctx, idx);
+
+            self.get_prop_top(key)?;
+
+            self.pop()
+        }
+    }
+
+    ///
+    /// Serialize any `Serialize` type directly onto duktape stack
+    ///
+    /// # Arguments
+    ///
+    /// * `rval` - The value to serialize
+    ///
+    /// # Returns
+    ///
+    /// Either nothing or the serialization `Error`
+    ///
+    /// # Example
+    ///
+    ///     use rubtle_lib::Rubtle;
+    ///
+    ///     let rubtle = Rubtle::new();
+    ///
+    ///     rubtle.push_serialize(&vec![(1, "rubtle")]).unwrap();
     ///
 
-    pub fn set_global_function<F>(&self, name: &str, func: F)
-    where
-        F: 'static + Fn(Invocation<i8>) -> CallbackResult<Value>,
-    {
-        unsafe extern "C" fn wrapper<T>(ctx: *mut ffi::duk_context) -> ffi::duk_ret_t {
-            /* Get arguments from stack */
-            let rubtle = Rubtle {
-                ctx: ctx,
-                drop_ctx: false,
-            };
-            let nargs = ffi::duk_get_top(ctx) as usize;
-            let mut args = Vec::with_capacity(nargs);
+    #[cfg(feature = "serde")]
+    pub fn push_serialize<T: serde::Serialize + ?Sized>(&self, rval: &T) -> Result<(), Error> {
+        crate::ser::push_serialize(self, rval)
+    }
+
+    ///
+    /// Deserialize any `Deserialize` type directly from top of duktape stack
+    ///
+    /// # Returns
+    ///
+    /// Either the deserialized value or the deserialization `Error`; the value
+    /// is removed from the stack in both cases
+    ///
+    /// # Example
+    ///
+    ///     use rubtle_lib::Rubtle;
+    ///
+    ///     let rubtle = Rubtle::new();
+    ///
+    ///     rubtle.push_serialize(&vec![(1, "rubtle")]).unwrap();
+    ///
+    ///     let rval: Vec<(u8, String)> = rubtle.pop_deserialize().unwrap();
+    ///
 
-            for i in 0..nargs {
-                ffi::duk_dup(ctx, i as ffi::duk_idx_t);
+    #[cfg(feature = "serde")]
+    pub fn pop_deserialize<T: serde::de::DeserializeOwned>(&self) -> Result<T, Error> {
+        crate::de::pop_deserialize(self)
+    }
+
+    ///
+    /// Convert array or object on top of the stack
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - State of the conversion
+    ///
+    /// # Returns
+    ///
+    /// Either the converted value or the `Error`; the value is removed from
+    /// the stack on success only
+    ///
+
+    unsafe fn handle_objects(&self, state: &mut ConversionState) -> Result<Value, Error> {
+        if !state.enter(ffi::duk_get_heapptr(self.ctx, -1))? {
+            return Ok(Value::Ref(JsObject::pop(self.ctx)));
+        }
 
-                match rubtle.pop_value() {
-                    Some(val) => args.push(val),
-                    None => eprintln!("Unwrap of none value"),
+        ffi::duk_require_stack(self.ctx, 3);
+
+        /* Handle arrays */
+        let rval = if 1 == ffi::duk_is_array(self.ctx, -1) {
+            let mut vec: Vec<Value> = Vec::new();
+
+            self.push_enum_top(ffi::DUK_ENUM_ARRAY_INDICES_ONLY)?;
+
+            while self.next_property(state, true)? {
+                if self.skip_function(state)? {
+                    vec.push(Value::Null);
+                    ffi::duk_pop(self.ctx);
+                } else {
+                    match self.pop_converted(-1, state)? {
+                        Some(val) => vec.push(val),
+                        None => ffi::duk_pop(self.ctx),
+                    }
                 }
+
+                /* Remove iter from stack*/
+                ffi::duk_pop(self.ctx);
             }
 
-            /* Assemble invocation */
-            let invocation = Invocation::<i8> {
-                rubtle: &rubtle,
-                args: Some(args),
-                udata: None,
-            };
+            Value::Array(vec)
+        } else {
+            let mut hash = ObjectMap::new();
 
-            /* Fetch pointer from duktape */
-            ffi::duk_push_current_function(ctx);
-            ffi::duk_get_prop_string(ctx, -1, FUNC.as_ptr() as *const _);
-            let func_ptr = ffi::duk_get_pointer(ctx, -1) as *mut Callback<i8>;
-            ffi::duk_pop_n(ctx, 2);
+            self.push_enum_top(state.options.enum_flags())?;
 
-            /* Wrap function and finally call it */
-            let wrapped_func = || (*func_ptr)(invocation);
-            let result = match catch_unwind(AssertUnwindSafe(wrapped_func)) {
-                Ok(result) => result,
-                Err(_) => {
-                    ffi::duk_fatal_raw(ctx, cstr!("Fatal error on func call"));
-                    unreachable!();
+            while self.next_property(state, false)? {
+                if self.skip_function(state)? {
+                    ffi::duk_pop_2(self.ctx);
+
+                    continue;
                 }
-            };
 
-            match result {
-                Ok(value) => {
-                    rubtle.push_value(&value);
-                    1
+                /* Pop value and key in reverse */
+                if let Some(value) = self.pop_converted(-1, state)? {
+                    if let Some(key) = convert::get_key_at(self.ctx, -1) {
+                        hash.insert(key, value);
+                    }
+
+                    ffi::duk_pop(self.ctx);
+                } else {
+                    ffi::duk_pop_2(self.ctx);
                 }
-                Err(_) => -1,
             }
-        }
 
-        unsafe extern "C" fn finalizer<T>(ctx: *mut ffi::duk_context) -> ffi::duk_ret_t {
-            ffi::duk_require_stack(ctx, 1);
-            ffi::duk_get_prop_string(ctx, 0, FUNC.as_ptr() as *const _);
+            Value::Object(hash)
+        };
 
-            /* Get box and drop it */
-            let callback = Box::from_raw(ffi::duk_get_pointer(ctx, -1) as *mut Callback<i8>);
+        /* Remove enum and object */
+        ffi::duk_pop_2(self.ctx);
 
-            drop(callback);
+        state.leave();
 
-            ffi::duk_pop(ctx);
-            ffi::duk_push_undefined(ctx);
-            ffi::duk_put_prop_string(ctx, 0, FUNC.as_ptr() as *const _);
+        Ok(rval)
+    }
 
-            0
+    ///
+    /// Push next key and value of the enumerator on top of the stack
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - State of the conversion
+    /// * `in_array` - Whether the enumerator belongs to an array
+    ///
+    /// # Returns
+    ///
+    /// Either `true` if a property was pushed, `false` if the enumerator is
+    /// done or the thrown exception as `Error`. Accessor properties are
+    /// skipped unless getters may be invoked; array elements are kept as
+    /// `null` instead
+    ///
+
+    unsafe fn next_property(&self, state: &ConversionState, in_array: bool) -> Result<bool, Error> {
+        if state.options.accessors {
+            return self.next_enum_top(true);
         }
 
-        unsafe {
-            let cstr = CString::new(to_cesu8(name));
+        while self.next_enum_top(false)? {
+            /* Stack: object, enum, key */
+            if self.get_data_property(-3)? {
+                return Ok(true);
+            }
 
-            match cstr {
-                Ok(cval) => {
-                    ffi::duk_require_stack(self.ctx, 2);
-                    ffi::duk_push_c_function(self.ctx, Some(wrapper::<i8>), -1); //< (DUK_VARARGS)
+            /* Keep positions of array elements */
+            if in_array {
+                ffi::duk_push_null(self.ctx);
 
-                    /* Store wrapper */
-                    let boxed_func = Box::into_raw(Box::new(Box::new(func) as Callback<i8>));
+                return Ok(true);
+            }
 
-                    assert!(!boxed_func.is_null(), "Null function pointer");
+            ffi::duk_pop(self.ctx);
+        }
 
-                    ffi::duk_push_pointer(self.ctx, boxed_func as *mut _);
-                    ffi::duk_put_prop_string(self.ctx, -2, FUNC.as_ptr() as *const _);
+        Ok(false)
+    }
 
-                    /* Store finalizer */
-                    ffi::duk_push_c_function(self.ctx, Some(finalizer::<i8>), 1);
-                    ffi::duk_set_finalizer(self.ctx, -2);
+    ///
+    /// Push value of data property with key on top of the stack without
+    /// invoking getters
+    ///
+    /// # Arguments
+    ///
+    /// * `obj_idx` - Stack index of the object
+    ///
+    /// # Returns
+    ///
+    /// Either `true` if the value was pushed, `false` for accessor properties
+    /// or the thrown exception as `Error`
+    ///
 
-                    /* Finally store as global function */
-                    ffi::duk_put_global_lstring(
-                        self.ctx,
-                        cval.as_ptr(),
-                        cval.as_bytes().len() as u64,
-                    );
+    unsafe fn get_data_property(&self, obj_idx: ffi::duk_idx_t) -> Result<bool, Error> {
+        let obj_idx = ffi::duk_normalize_index(self.ctx, obj_idx);
+        let key_idx = ffi::duk_get_top(self.ctx) - 1;
+
+        ffi::duk_require_stack(self.ctx, 4);
+        ffi::duk_dup(self.ctx, obj_idx);
+
+        /* Walk prototype chain until the property is found */
+        while 1 == ffi::duk_is_object(self.ctx, -1) {
+            ffi::duk_dup_top(self.ctx);
+            ffi::duk_dup(self.ctx, key_idx);
+
+            /* Descriptors are filled with regular puts, which may throw */
+            self.safe_call(2, 1, |ctx| {
+                ffi::duk_get_prop_desc(ctx, -2, 0);
+
+                1
+            })?;
+
+            if 1 == ffi::duk_is_object(self.ctx, -1) {
+                /* Drop prototype to ignore inherited fields of the descriptor */
+                ffi::duk_push_undefined(self.ctx);
+                ffi::duk_set_prototype(self.ctx, -2);
+
+                let is_data = 0 == ffi::duk_has_prop_string(self.ctx, -1, b"get\0".as_ptr() as *const _);
+
+                if is_data {
+                    ffi::duk_get_prop_string(self.ctx, -1, b"value\0".as_ptr() as *const _);
+                    ffi::duk_remove(self.ctx, -2);
+                    ffi::duk_remove(self.ctx, -2);
+                } else {
+                    ffi::duk_pop_2(self.ctx);
                 }
-                Err(_) => unimplemented!(),
+
+                return Ok(is_data);
             }
+
+            ffi::duk_pop(self.ctx);
+            ffi::duk_get_prototype(self.ctx, -1);
+            ffi::duk_remove(self.ctx, -2);
         }
+
+        ffi::duk_pop(self.ctx);
+
+        Ok(false)
     }
 
     ///
-    /// Create a global object for JS
+    /// Check whether function on top of the stack is skipped
     ///
     /// # Arguments
     ///
-    /// * `name` - Name of the global
-    /// * `object`- Object from ObjectBuilder
+    /// * `state` - State of the conversion
     ///
-    /// # Example
+    /// # Returns
     ///
-    ///     use rubtle_lib::{Rubtle, Value, ObjectBuilder};
+    /// Either `true` if the value is a function that must be skipped or the
+    /// `Error` if functions are not allowed
     ///
-    ///     let rubtle = Rubtle::new();
+
+    unsafe fn skip_function(&self, state: &ConversionState) -> Result<bool, Error> {
+        if 1 != ffi::duk_is_function(self.ctx, -1) {
+            return Ok(false);
+        }
+
+        match state.options.functions {
+            FunctionHandling::Keep => Ok(false),
+            FunctionHandling::Skip => Ok(true),
+            FunctionHandling::Error => Err(Error::Conversion {
+                expected: "value",
+                actual: "function",
+            }),
+        }
+    }
+
     ///
-    ///     #[derive(Default)]
-    ///     struct UserData {
-    ///         value: i32,
-    ///     };
+    /// Copy data of plain buffer or buffer object on given index
+    ///
+    /// # Arguments
+    ///
+    /// * `idx` - Stack index
+    ///
+    /// # Returns
+    ///
+    /// Copy of the bytes the buffer or view covers
+    ///
+
+    unsafe fn get_bytes_at(&self, idx: ffi::duk_idx_t) -> Vec<u8> {
+        let mut len: ffi::duk_size_t = 0;
+        let data = ffi::duk_get_buffer_data(self.ctx, idx, &mut len);
+
+        if data.is_null() {
+            Vec::new()
+        } else {
+            slice::from_raw_parts(data as *const u8, len as usize).to_vec()
+        }
+    }
+
+    ///
+    /// Pop value on given index from duktape stack
+    ///
+    /// # Arguments
+    ///
+    /// * `idx` - Stack index; -1 for top
+    ///
+    /// # Returns
+    ///
+    /// Any value on top of the stack as `Option<Value>`; `None` if the
+    /// conversion fails. The value is removed from the stack in both cases,
+    /// use `pop_value_with` to get the conversion `Error`
+    ///
+    /// # Example
+    ///
+    ///     use rubtle_lib::{Rubtle, Value};
+    ///
+    ///     let rubtle = Rubtle::new();
+    ///     let rval = Value::from(4);
+    ///
+    ///     rubtle.push_value(&rval);
+    ///
+    ///     let rval2 = rubtle.pop_value_at(-1).unwrap();
+    //
+
+    pub fn pop_value_at(&self, idx: ffi::duk_idx_t) -> Option<Value> {
+        let options = ConversionOptions::default();
+
+        unsafe {
+            match self.pop_converted(idx, &mut ConversionState::new(&options)) {
+                Ok(Some(rval)) => Some(rval),
+                _ => {
+                    /* Failed conversions leave the value on the stack */
+                    ffi::duk_remove(self.ctx, idx);
+
+                    None
+                }
+            }
+        }
+    }
+
+    ///
+    /// Convert arguments of the current call
+    ///
+    /// # Returns
+    ///
+    /// Either all arguments or the first conversion `Error`; values without
+    /// representation are kept as `Value::None` to keep positions intact
+    ///
+
+    unsafe fn get_call_args(&self) -> Result<Vec<Value>, Error> {
+        let nargs = ffi::duk_get_top(self.ctx);
+        let options = ConversionOptions::default();
+        let mut args = Vec::with_capacity(nargs as usize);
+
+        ffi::duk_require_stack(self.ctx, 1);
+
+        for i in 0..nargs {
+            ffi::duk_dup(self.ctx, i);
+
+            match self.pop_converted(-1, &mut ConversionState::new(&options)) {
+                Ok(Some(val)) => args.push(val),
+                Ok(None) => {
+                    ffi::duk_pop(self.ctx);
+
+                    args.push(Value::None);
+                }
+                Err(err) => {
+                    ffi::duk_pop(self.ctx);
+
+                    return Err(err);
+                }
+            }
+        }
+
+        Ok(args)
+    }
+
+    ///
+    /// Pop value from top of duktape stack with given options
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - Options of the conversion
+    ///
+    /// # Returns
+    ///
+    /// Either the value or the conversion `Error`; the value is removed from
+    /// the stack in both cases
+    ///
+    /// # Example
+    ///
+    ///     use rubtle_lib::{ConversionOptions, Error, Limit, Rubtle, Value};
+    ///
+    ///     let rubtle = Rubtle::new();
+    ///     let options = ConversionOptions::new().with_max_depth(1);
+    ///
+    ///     rubtle.push_value(&Value::Array(vec![Value::Array(Vec::new())]));
+    ///
+    ///     assert_eq!(Err(Error::Limit(Limit::Depth)), rubtle.pop_value_with(&options));
+    ///
+
+    pub fn pop_value_with(&self, options: &ConversionOptions) -> Result<Value, Error> {
+        unsafe {
+            let result = match self.pop_converted(-1, &mut ConversionState::new(options)) {
+                Ok(Some(rval)) => return Ok(rval),
+                Ok(None) => Err(Error::Conversion {
+                    expected: "value",
+                    actual: convert::type_name_at(self.ctx, -1),
+                }),
+                Err(err) => Err(err),
+            };
+
+            ffi::duk_pop(self.ctx);
+
+            result
+        }
+    }
+
+    ///
+    /// Pop value on given index and convert it
+    ///
+    /// # Arguments
+    ///
+    /// * `idx` - Stack index; -1 for top
+    /// * `state` - State of the conversion
+    ///
+    /// # Returns
+    ///
+    /// Either `Some` value that was removed from the stack, `None` for
+    /// unsupported values or the `Error`; the stack is left untouched
+    /// unless a value is returned
+    ///
+
+    unsafe fn pop_converted(
+        &self,
+        idx: ffi::duk_idx_t,
+        state: &mut ConversionState,
+    ) -> Result<Option<Value>, Error> {
+        let rval = match ffi::duk_get_type(self.ctx, idx) as u32 {
+            ffi::DUK_TYPE_BOOLEAN => Value::Boolean(0 != ffi::duk_get_boolean(self.ctx, idx)),
+
+            ffi::DUK_TYPE_NUMBER => Value::Number(ffi::duk_get_number(self.ctx, idx)),
+
+            ffi::DUK_TYPE_STRING => match convert::get_str_at(self.ctx, idx) {
+                Some(string) => Value::Str(string),
+                None => return Ok(None),
+            },
+
+            ffi::DUK_TYPE_OBJECT => {
+                let idx = ffi::duk_normalize_index(self.ctx, idx);
+                let top = ffi::duk_get_top(self.ctx);
+
+                ffi::duk_require_stack(self.ctx, 1);
+                ffi::duk_dup(self.ctx, idx);
+
+                let mut timeval: ffi::duk_double_t = 0.0;
+
+                let rval = if 1 == ffi::rust_duk_get_date(self.ctx, -1, &mut timeval) {
+                    ffi::duk_pop(self.ctx);
+
+                    /* Invalid dates serialize to null in JSON as well */
+                    Ok(value::from_time_value(timeval).map_or(Value::Null, Value::Date))
+                } else if 1 == ffi::rust_duk_is_byte_buffer(self.ctx, -1) {
+                    let bytes = self.get_bytes_at(-1);
+
+                    ffi::duk_pop(self.ctx);
+
+                    Ok(Value::Bytes(bytes))
+                } else if 1 == ffi::duk_is_function(self.ctx, -1) {
+                    Ok(Value::Function(JsFunction::pop(self.ctx).into()))
+                } else {
+                    self.handle_objects(state)
+                };
+
+                match rval {
+                    Ok(rval) => {
+                        ffi::duk_remove(self.ctx, idx);
+
+                        return Ok(Some(rval));
+                    }
+                    Err(err) => {
+                        /* Drop everything left over by nested conversions */
+                        ffi::duk_set_top(self.ctx, top);
+
+                        return Err(err);
+                    }
+                }
+            },
+
+            ffi::DUK_TYPE_BUFFER => Value::Bytes(self.get_bytes_at(idx)),
+
+            ffi::DUK_TYPE_UNDEFINED => Value::None,
+
+            ffi::DUK_TYPE_NULL => Value::Null,
+
+            _ => return Ok(None),
+        };
+
+        ffi::duk_remove(self.ctx, idx);
+
+        Ok(Some(rval))
+    }
+
+    ///
+    /// Set value to context and assign a global reachable name
+    ///
+    /// # Arguments
+    ///
+    /// `name`- Name of the value
+    /// `rval` - The actual value; either a `Value` or any other `ToJs` type
+    ///
+    /// # Example
+    ///
+    ///     use std::collections::BTreeMap;
+    ///
+    ///     use rubtle_lib::{Rubtle, Value};
+    ///
+    ///     let rubtle = Rubtle::new();
+    ///     let rval = Value::from(4);
+    ///
+    ///     rubtle.set_global_value("rubtle", &rval);
+    ///
+    ///     let mut map = BTreeMap::new();
+    ///
+    ///     map.insert("rubtle", vec![(1, true), (2, false)]);
+    ///
+    ///     rubtle.set_global_value("rubtle", &map);
+    ///
+
+    pub fn set_global_value<T: ToJs + ?Sized>(&self, name: &str, rval: &T) {
+        unsafe {
+            let cstr = CString::new(to_cesu8(name));
+
+            match cstr {
+                Ok(cval) => {
+                    rval.to_js(self);
+
+                    ffi::duk_require_stack(self.ctx, 1);
+                    ffi::duk_put_global_lstring(
+                        self.ctx,
+                        cval.as_ptr(),
+                        cval.as_bytes().len() as u64,
+                    );
+                }
+                Err(_) => unimplemented!(),
+            }
+        }
+    }
+
+    ///
+    /// Get value from context for given global reachable name
+    ///
+    /// # Arguments
+    ///
+    /// `name`- Name of the value
+    ///
+    /// # Returns
+    ///
+    /// Any value on top of the stack as `Option<Value>`
+    ///
+    /// # Example
+    ///
+    ///     use rubtle_lib::{Rubtle, Value};
+    ///
+    ///     let rubtle = Rubtle::new();
+    ///
+    ///     rubtle.get_global_value("rubtle");
+    ///
+
+    pub fn get_global_value(&self, name: &str) -> Option<Value> {
+        unsafe {
+            self.push_global_prop(name).ok()?;
+
+            self.pop_value()
+        }
+    }
+
+    ///
+    /// Get value from context for given global reachable name with given options
+    ///
+    /// # Arguments
+    ///
+    /// `name`- Name of the value
+    /// `options` - Options of the conversion
+    ///
+    /// # Returns
+    ///
+    /// Either the value or the conversion `Error`
+    ///
+    /// # Example
+    ///
+    ///     use rubtle_lib::{ConversionOptions, Error, Rubtle};
+    ///
+    ///     let rubtle = Rubtle::new();
+    ///     let options = ConversionOptions::new();
+    ///
+    ///     rubtle.eval("var rubtle = []; rubtle.push(rubtle);").unwrap();
+    ///
+    ///     match rubtle.get_global_value_with("rubtle", &options) {
+    ///         Err(Error::Conversion { .. }) => (),
+    ///         _ => panic!("Expected conversion error"),
+    ///     }
+    ///
+
+    pub fn get_global_value_with(
+        &self,
+        name: &str,
+        options: &ConversionOptions,
+    ) -> Result<Value, Error> {
+        unsafe {
+            self.push_global_prop(name)?;
+        }
+
+        self.pop_value_with(options)
+    }
+
+    ///
+    /// Get value from context for given global reachable name as any `FromJs` type
+    ///
+    /// # Arguments
+    ///
+    /// `name`- Name of the value
+    ///
+    /// # Returns
+    ///
+    /// Either the converted value, a conversion `Error` or the exception thrown
+    /// by a getter as `Error::Exception`
+    ///
+    /// # Example
+    ///
+    ///     use std::collections::HashMap;
+    ///
+    ///     use rubtle_lib::Rubtle;
+    ///
+    ///     let rubtle = Rubtle::new();
+    ///
+    ///     rubtle.eval("var rubtle = { a: [1, 2], b: [] };").unwrap();
+    ///
+    ///     let map: HashMap<String, Vec<i32>> = rubtle.get_global("rubtle").unwrap();
+    ///
+    ///     assert_eq!(vec![1, 2], map["a"]);
+    ///
+
+    pub fn get_global<T: FromJs>(&self, name: &str) -> Result<T, Error> {
+        unsafe {
+            self.push_global_prop(name)?;
+        }
+
+        self.pop()
+    }
+
+    ///
+    /// Pop object from top of the stack as live handle
+    ///
+    /// # Returns
+    ///
+    /// `Option` with the handle if the top value is an object, array or function;
+    /// otherwise `None` and the value stays on the stack
+    ///
+    /// # Example
+    ///
+    ///     use rubtle_lib::{Rubtle, Value};
+    ///
+    ///     let rubtle = Rubtle::new();
+    ///
+    ///     rubtle.push_value(&Value::Array(vec![Value::from(1), Value::from(2)]));
+    ///
+    ///     let obj = rubtle.pop_js_object().unwrap();
+    ///
+    ///     assert_eq!(2, obj.len(&rubtle).unwrap());
+    ///
+
+    pub fn pop_js_object(&self) -> Option<JsObject> {
+        unsafe {
+            if 1 == ffi::duk_is_object(self.ctx, -1) {
+                Some(JsObject::pop(self.ctx))
+            } else {
+                None
+            }
+        }
+    }
+
+    ///
+    /// Push live object handle onto duktape stack
+    ///
+    /// # Arguments
+    ///
+    /// * `obj` - Handle of an object of this heap
+    ///
+    /// # Returns
+    ///
+    /// Either nothing or `Error::Heap` for handles of another or a destroyed heap
+    ///
+
+    pub fn push_js_object(&self, obj: &JsObject) -> Result<(), Error> {
+        unsafe { obj.push(self.ctx) }
+    }
+
+    ///
+    /// Get global object as live handle instead of a copy
+    ///
+    /// # Arguments
+    ///
+    /// `name`- Name of the global
+    ///
+    /// # Returns
+    ///
+    /// `Option` with the handle if the global is an object; otherwise `None`
+    ///
+    /// # Example
+    ///
+    ///     use rubtle_lib::{Rubtle, Value};
+    ///
+    ///     let rubtle = Rubtle::new();
+    ///
+    ///     rubtle.eval("var rubtle = {};").unwrap();
+    ///
+    ///     let obj = rubtle.get_global_js_object("rubtle").unwrap();
+    ///
+    ///     obj.set(&rubtle, "name", &Value::from("rubtle")).unwrap();
+    ///
+    ///     assert_eq!(Value::from("rubtle"), rubtle.eval("rubtle.name").unwrap());
+    ///
+
+    pub fn get_global_js_object(&self, name: &str) -> Option<JsObject> {
+        unsafe {
+            self.push_global_prop(name).ok()?;
+
+            let obj = self.pop_js_object();
+
+            if obj.is_none() {
+                ffi::duk_pop(self.ctx);
+            }
+
+            obj
+        }
+    }
+
+    ///
+    /// Set closure/function as a global function to call from JS
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the global
+    /// * `func`- Closure/function to call
+    ///
+    /// # Example
+    ///
+    ///     use rubtle_lib::{Rubtle, Value, Invocation, CallbackResult};
+    ///
+    ///     let rubtle = Rubtle::new();
+    ///
+    ///     let printer = |inv: Invocation<i8>| -> CallbackResult<Value> {
+    ///         let args = inv.args.unwrap();
+    ///         let s = args.first().unwrap();
+    ///
+    ///         println!("{:?}", s.as_string().unwrap());
+    ///
+    ///         Ok(Value::from(true))
+    ///     };
+    ///
+    ///     rubtle.set_global_function("print", printer);
+    ///
+
+    pub fn set_global_function<F, R>(&self, name: &str, func: F)
+    where
+        F: 'static + Fn(Invocation<i8>) -> CallbackResult<R>,
+        R: 'static + ToJs,
+    {
+        unsafe {
+            let cstr = CString::new(to_cesu8(name));
+
+            match cstr {
+                Ok(cval) => {
+                    self.push_callback(&Rc::new(function::box_callback(func)));
+
+                    /* Finally store as global function */
+                    ffi::duk_put_global_lstring(
+                        self.ctx,
+                        cval.as_ptr(),
+                        cval.as_bytes().len() as u64,
+                    );
+                }
+                Err(_) => unimplemented!(),
+            }
+        }
+    }
+
+    ///
+    /// Push closure/function as callable JS function
+    ///
+    /// # Arguments
+    ///
+    /// * `callback` - Shared callback; the JS function keeps a reference until
+    ///   it gets finalized
+    ///
+
+    pub(crate) fn push_callback(&self, callback: &Rc<Callback<i8>>) {
+        unsafe extern "C" fn wrapper<T>(ctx: *mut ffi::duk_context) -> ffi::duk_ret_t {
+            /* Get arguments from stack */
+            let rubtle = Rubtle {
+                ctx: ctx,
+                drop_ctx: false,
+            };
+            let args = match rubtle.get_call_args() {
+                Ok(args) => args,
+                Err(err) => {
+                    drop(rubtle);
+
+                    return throw_error(ctx, err);
+                }
+            };
+
+            /* Assemble invocation */
+            let invocation = Invocation::<i8> {
+                rubtle: &rubtle,
+                args: Some(args),
+                udata: None,
+            };
+
+            /* Fetch pointer from duktape */
+            ffi::duk_push_current_function(ctx);
+            ffi::duk_get_prop_string(ctx, -1, FUNC.as_ptr() as *const _);
+            let func_ptr = ffi::duk_get_pointer(ctx, -1) as *mut Rc<Callback<i8>>;
+            ffi::duk_pop_n(ctx, 2);
+
+            /* Keep callback alive even if the call drops the function */
+            let callback = (*func_ptr).clone();
+
+            /* Wrap function and finally call it */
+            let wrapped_func = || callback(invocation);
+            let result = match catch_unwind(AssertUnwindSafe(wrapped_func)) {
+                Ok(result) => result,
+                Err(payload) => Err(Error::Callback(panic_message(payload))),
+            };
+
+            drop(callback);
+
+            /* Handles of other heaps are thrown as error */
+            match result.and_then(|value| value.try_to_js(&rubtle)) {
+                Ok(()) => 1,
+                Err(err) => {
+                    drop(rubtle);
+                    throw_error(ctx, err)
+                }
+            }
+        }
+
+        unsafe extern "C" fn finalizer<T>(ctx: *mut ffi::duk_context) -> ffi::duk_ret_t {
+            ffi::duk_require_stack(ctx, 1);
+            ffi::duk_get_prop_string(ctx, 0, FUNC.as_ptr() as *const _);
+
+            /* Get box and drop it */
+            let func_ptr = ffi::duk_get_pointer(ctx, -1) as *mut Rc<Callback<i8>>;
+
+            if !func_ptr.is_null() {
+                drop(Box::from_raw(func_ptr));
+            }
+
+            ffi::duk_pop(ctx);
+            ffi::duk_push_undefined(ctx);
+            ffi::duk_put_prop_string(ctx, 0, FUNC.as_ptr() as *const _);
+
+            0
+        }
+
+        unsafe {
+            ffi::duk_require_stack(self.ctx, 2);
+            ffi::duk_push_c_function(self.ctx, Some(wrapper::<i8>), -1); //< (DUK_VARARGS)
+
+            /* Store wrapper */
+            let boxed_func = Box::into_raw(Box::new(callback.clone()));
+
+            assert!(!boxed_func.is_null(), "Null function pointer");
+
+            ffi::duk_push_pointer(self.ctx, boxed_func as *mut _);
+            ffi::duk_put_prop_string(self.ctx, -2, FUNC.as_ptr() as *const _);
+
+            /* Store finalizer */
+            ffi::duk_push_c_function(self.ctx, Some(finalizer::<i8>), 1);
+            ffi::duk_set_finalizer(self.ctx, -2);
+        }
+    }
+
+    ///
+    /// Create a global object for JS
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the global
+    /// * `object`- Object from ObjectBuilder
+    ///
+    /// # Example
+    ///
+    ///     use rubtle_lib::{Rubtle, Value, ObjectBuilder};
+    ///
+    ///     let rubtle = Rubtle::new();
+    ///
+    ///     #[derive(Default)]
+    ///     struct UserData {
+    ///         value: i32,
+    ///     };
+    ///
+    ///     let mut object = ObjectBuilder::<UserData>::new()
+    ///         .with_constructor(|inv| {
+    ///             let mut udata = inv.udata.as_mut().unwrap();
+    ///
+    ///             udata.value = 0;
+    ///          })
+    ///         .build();
+    ///
+    ///     let rubtle = Rubtle::new();
+    ///
+    ///     rubtle.set_global_object("Printer", &mut object);
+    ///
+
+    pub fn set_global_object<T>(&self, name: &str, object: &mut Object<T>)
+    where
+        T: Default + 'static,
+    {
+        unsafe extern "C" fn ctor_wrapper<T>(ctx: *mut ffi::duk_context) -> ffi::duk_ret_t
+        where
+            T: Default + 'static,
+        {
+            /* Verify this is a constrcutor call */
+            if 0 == ffi::duk_is_constructor_call(ctx) {
+                return -1;
+            }
+
+            /* Get arguments from stack */
+            let rubtle = Rubtle {
+                ctx: ctx,
+                drop_ctx: false,
+            };
+            let args = match rubtle.get_call_args() {
+                Ok(args) => args,
+                Err(err) => {
+                    drop(rubtle);
+
+                    return throw_error(ctx, err);
+                }
+            };
+
+            /* Create invocation data */
+            let mut inv = Invocation {
+                rubtle: &rubtle,
+                args: Some(args),
+                udata: Some(T::default()),
+            };
+
+            /* Fetch pointer from duktape */
+            ffi::duk_push_current_function(ctx);
+            ffi::duk_get_prop_string(ctx, -1, CTOR.as_ptr() as *const _);
+            let func_ptr = ffi::duk_get_pointer(ctx, -1) as *mut ObjectBuilderCtor<T>;
+            ffi::duk_pop_n(ctx, 2);
+
+            assert!(!func_ptr.is_null(), "Null function pointer");
+
+            /* Wrap function and finally call it */
+            let wrapped_func = || (*func_ptr)(&mut inv);
+
+            if let Err(payload) = catch_unwind(AssertUnwindSafe(wrapped_func)) {
+                let err = Error::Callback(panic_message(payload));
+
+                drop(inv);
+                drop(rubtle);
+
+                return throw_error(ctx, err);
+            }
+
+            let boxed_udata = Box::into_raw(Box::new(inv));
+
+            assert!(!boxed_udata.is_null(), "Null user data pointer");
+
+            /* Store user data */
+            ffi::duk_push_this(ctx);
+            ffi::duk_push_pointer(ctx, boxed_udata as *mut _);
+            ffi::duk_put_prop_string(ctx, -2, UDATA.as_ptr() as *const _);
+
+            ffi::duk_pop(ctx);
+
+            0
+        }
+
+        unsafe extern "C" fn meth_wrapper<T>(ctx: *mut ffi::duk_context) -> ffi::duk_ret_t
+        where
+            T: Default + 'static,
+        {
+            /* Get arguments from stack */
+            let rubtle = Rubtle {
+                ctx: ctx,
+                drop_ctx: false,
+            };
+            let args = match rubtle.get_call_args() {
+                Ok(args) => args,
+                Err(err) => {
+                    drop(rubtle);
+
+                    return throw_error(ctx, err);
+                }
+            };
+
+            /* Fetch pointer from duktape */
+            ffi::duk_push_current_function(ctx);
+            ffi::duk_get_prop_string(ctx, -1, METH.as_ptr() as *const _);
+            let func_ptr = ffi::duk_get_pointer(ctx, -1) as *mut ObjectBuilderCallback<T>;
+            ffi::duk_pop_n(ctx, 2);
+
+            assert!(!func_ptr.is_null(), "Null function pointer");
+
+            /* Fetch user data from duktape */
+            ffi::duk_push_this(ctx);
+            ffi::duk_get_prop_string(ctx, -1, UDATA.as_ptr() as *const _);
+            let inv_ptr = ffi::duk_get_pointer(ctx, -1) as *mut Invocation<T>;
+            ffi::duk_pop_n(ctx, 2);
+
+            assert!(!inv_ptr.is_null(), "Null user data pointer");
+
+            (*inv_ptr).args = Some(args);
+
+            /* Wrap function and finally call it */
+            let wrapped_func = || (*func_ptr)(&mut *inv_ptr);
+            let result = match catch_unwind(AssertUnwindSafe(wrapped_func)) {
+                Ok(res) => res,
+                Err(payload) => Err(Error::Callback(panic_message(payload))),
+            };
+
+            /* Handles of other heaps are thrown as error */
+            match result.and_then(|val| rubtle.try_push_value(&val)) {
+                Ok(()) => 1,
+                Err(err) => {
+                    drop(rubtle);
+                    throw_error(ctx, err)
+                }
+            }
+        }
+
+        unsafe {
+            let cstr = CString::new(to_cesu8(name));
+
+            match cstr {
+                Ok(cval) => {
+                    ffi::duk_push_c_function(self.ctx, Some(ctor_wrapper::<T>), -1);
+
+                    /* Store ctor wrapper */
+                    match object.take_constructor() {
+                        Some(ctor) => {
+                            let boxed_func = Box::into_raw(Box::new(ctor));
+
+                            ffi::duk_push_pointer(self.ctx, boxed_func as *mut _);
+                            ffi::duk_put_prop_string(self.ctx, -2, CTOR.as_ptr() as *const _);
+                        }
+                        None => {
+                            ffi::duk_fatal_raw(self.ctx, cstr!("No constructor"));
+                            unreachable!();
+                        }
+                    }
+
+                    ffi::duk_push_object(self.ctx);
+
+                    /* Store method wrapper */
+                    for (name, meth) in object {
+                        let cstr = CString::new(to_cesu8(name));
+
+                        match cstr {
+                            Ok(cval) => {
+                                let boxed_func = Box::into_raw(Box::new(meth));
+
+                                ffi::duk_push_c_function(self.ctx, Some(meth_wrapper::<T>), -1); //< (DUK_VARARGS)
+
+                                ffi::duk_push_pointer(self.ctx, boxed_func as *mut _);
+                                ffi::duk_put_prop_string(self.ctx, -2, METH.as_ptr() as *const _);
+
+                                ffi::duk_put_prop_lstring(
+                                    self.ctx,
+                                    -2,
+                                    cval.as_ptr(),
+                                    cval.as_bytes().len() as u64,
+                                );
+                            }
+                            Err(_) => unimplemented!(),
+                        }
+                    }
+
+                    ffi::duk_put_prop_string(self.ctx, -2, cstr!("prototype"));
+                    ffi::duk_put_global_lstring(
+                        self.ctx,
+                        cval.as_ptr(),
+                        cval.as_bytes().len() as u64,
+                    );
+                }
+                Err(_) => unimplemented!(),
+            }
+        }
+    }
+
+    ///
+    /// Encode value as JSON like `JSON.stringify` does
+    ///
+    /// # Arguments
+    ///
+    /// * `rval` - Value to encode
+    ///
+    /// # Returns
+    ///
+    /// Either the JSON `String` or the `Error`; values without JSON
+    /// representation like functions fail with a conversion error
+    ///
+    /// # Example
+    ///
+    ///     use rubtle_lib::{Rubtle, Value};
+    ///
+    ///     let rubtle = Rubtle::new();
+    ///
+    ///     assert_eq!(r#""rubtle""#, rubtle.json_encode(&Value::from("rubtle")).unwrap());
+    ///
+
+    pub fn json_encode(&self, rval: &Value) -> Result<String, Error> {
+        self.json_encode_with(rval, &JsonOptions::default())
+    }
+
+    ///
+    /// Encode value as JSON with given options
+    ///
+    /// # Arguments
+    ///
+    /// * `rval` - Value to encode
+    /// * `options` - Format and indentation of the output
+    ///
+    /// # Returns
+    ///
+    /// Either the encoded `String` or the `Error`
+    ///
+    /// # Example
+    ///
+    ///     use rubtle_lib::{JsonFormat, JsonOptions, Rubtle, Value};
+    ///
+    ///     let rubtle = Rubtle::new();
+    ///     let options = JsonOptions::new().with_format(JsonFormat::Jx);
+    ///
+    ///     assert_eq!("undefined", rubtle.json_encode_with(&Value::None, &options).unwrap());
+    ///
+
+    pub fn json_encode_with(&self, rval: &Value, options: &JsonOptions) -> Result<String, Error> {
+        let format = options.format.codec_format();
+        let indent = options.indent.min(10) as ffi::duk_int_t;
+
+        self.try_push_value(rval)?;
+
+        unsafe {
+            self.safe_call(1, 1, |ctx| {
+                ffi::rust_duk_json_encode(ctx, -1, format, indent);
+
+                1
+            })?;
+
+            match self.pop_value_with(&ConversionOptions::default())? {
+                Value::Str(string) => Ok(string),
+                rval => Err(rval.conversion_error("string")),
+            }
+        }
+    }
+
+    ///
+    /// Decode JSON into value like `JSON.parse` does
+    ///
+    /// # Arguments
+    ///
+    /// * `json` - JSON to decode
+    ///
+    /// # Returns
+    ///
+    /// Either the decoded `Value` or the thrown `SyntaxError` as `Error`
+    ///
+    /// # Example
+    ///
+    ///     use rubtle_lib::{Rubtle, Value};
+    ///
+    ///     let rubtle = Rubtle::new();
+    ///
+    ///     let rval = rubtle.json_decode(r#"{ "rubtle": [1, 2] }"#).unwrap();
+    ///
+    ///     match rval {
+    ///         Value::Object(hash) => assert_eq!(Value::from(&vec![1, 2]), hash["rubtle"]),
+    ///         _ => panic!("Expected object"),
+    ///     }
+    ///
+
+    pub fn json_decode(&self, json: &str) -> Result<Value, Error> {
+        self.json_decode_with(json, &JsonOptions::default())
+    }
+
+    ///
+    /// Decode JSON into value with given options
+    ///
+    /// # Arguments
+    ///
+    /// * `json` - JSON to decode
+    /// * `options` - Format of the input; indentation is ignored
+    ///
+    /// # Returns
+    ///
+    /// Either the decoded `Value` or the thrown `SyntaxError` as `Error`
+    ///
+    /// # Example
+    ///
+    ///     use rubtle_lib::{JsonFormat, JsonOptions, Rubtle, Value};
+    ///
+    ///     let rubtle = Rubtle::new();
+    ///     let options = JsonOptions::new().with_format(JsonFormat::Jx);
+    ///
+    ///     assert_eq!(Value::from(vec![1u8, 2]), rubtle.json_decode_with("|0102|", &options).unwrap());
+    ///
+
+    pub fn json_decode_with(&self, json: &str, options: &JsonOptions) -> Result<Value, Error> {
+        let format = options.format.codec_format();
+
+        unsafe {
+            ffi::duk_require_stack(self.ctx, 1);
+
+            convert::push_str(self.ctx, json);
+
+            self.safe_call(1, 1, |ctx| {
+                ffi::rust_duk_json_decode(ctx, -1, format);
+
+                1
+            })?;
+
+            self.pop_value_with(&ConversionOptions::default())
+        }
+    }
+
+    ///
+    /// Encode value as CBOR like `CBOR.encode` does
+    ///
+    /// # Arguments
+    ///
+    /// * `rval` - Value to encode
+    ///
+    /// # Returns
+    ///
+    /// Either the encoded bytes or the thrown exception as `Error`
+    ///
+    /// # Example
+    ///
+    ///     use rubtle_lib::{Rubtle, Value};
+    ///
+    ///     let rubtle = Rubtle::new();
+    ///
+    ///     assert_eq!(vec![0x63, b'f', b'o', b'o'], rubtle.cbor_encode(&Value::from("foo")).unwrap());
+    ///
+
+    pub fn cbor_encode(&self, rval: &Value) -> Result<Vec<u8>, Error> {
+        self.try_push_value(rval)?;
+
+        unsafe {
+            self.safe_call(1, 1, |ctx| {
+                ffi::duk_cbor_encode(ctx, -1, 0);
+
+                1
+            })?;
+
+            let bytes = self.get_bytes_at(-1);
+
+            ffi::duk_pop(self.ctx);
+
+            Ok(bytes)
+        }
+    }
+
+    ///
+    /// Decode CBOR into value like `CBOR.decode` does
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - CBOR to decode
+    ///
+    /// # Returns
+    ///
+    /// Either the decoded `Value` or the thrown exception as `Error`
+    ///
+    /// # Example
+    ///
+    ///     use rubtle_lib::{Rubtle, Value};
+    ///
+    ///     let rubtle = Rubtle::new();
+    ///     let rval = Value::Array(vec![Value::from(1), Value::from("rubtle"), Value::Null]);
+    ///
+    ///     let bytes = rubtle.cbor_encode(&rval).unwrap();
+    ///
+    ///     assert_eq!(rval, rubtle.cbor_decode(&bytes).unwrap());
+    ///
+
+    pub fn cbor_decode(&self, bytes: &[u8]) -> Result<Value, Error> {
+        unsafe {
+            ffi::duk_require_stack(self.ctx, 1);
+
+            let buf = ffi::duk_push_buffer_raw(self.ctx, bytes.len() as u64, 0);
+
+            if !bytes.is_empty() {
+                ptr::copy_nonoverlapping(bytes.as_ptr(), buf as *mut u8, bytes.len());
+            }
+
+            self.safe_call(1, 1, |ctx| {
+                ffi::duk_cbor_decode(ctx, -1, 0);
+
+                1
+            })?;
+        }
+
+        self.pop_value_with(&ConversionOptions::default())
+    }
+
+    ///
+    /// Eval given string
+    ///
+    /// # Arugments
+    ///
+    /// * `str_val` - String to eval
+    ///
+    /// # Returns
+    ///
+    /// Completion value of the script as `Value` or the thrown exception as `Error`
+    ///
+    /// # Example
+    ///
+    ///     use rubtle_lib::Rubtle;
+    ///
+    ///     let rubtle = Rubtle::new();
+    ///
+    ///     let rval = rubtle.eval(r#"
+    ///         var rubtle = 4;
+    ///         rubtle * 2;
+    ///     "#).unwrap();
+    ///
+    ///     assert_eq!(8.0, rval.as_number().unwrap());
+    ///
+
+    pub fn eval(&self, str_val: &str) -> Result<Value, Error> {
+        unsafe {
+            let top = ffi::duk_get_top(self.ctx);
+
+            let result = self.compile_top(str_val, None).and_then(|_| self.run_top());
+
+            /* Drop anything the conversion left behind */
+            ffi::duk_set_top(self.ctx, top);
+
+            result
+        }
+    }
+
+    ///
+    /// Compile given string once for later runs
+    ///
+    /// # Arugments
+    ///
+    /// * `str_val` - String to compile
+    /// * `filename` - Filename to use in error stacks
+    ///
+    /// # Returns
+    ///
+    /// Compiled `Script` or the syntax error as `Error`
+    ///
+    /// # Example
+    ///
+    ///     use rubtle_lib::Rubtle;
+    ///
+    ///     let rubtle = Rubtle::new();
+    ///
+    ///     let script = rubtle.compile(r#"
+    ///         var rubtle = (rubtle || 0) + 1;
+    ///     "#, "rubtle.js").unwrap();
+    ///
+    ///     script.run(&rubtle).unwrap();
+    ///
+
+    pub fn compile(&self, str_val: &str, filename: &str) -> Result<Script, Error> {
+        unsafe {
+            let top = ffi::duk_get_top(self.ctx);
+
+            let result = self
+                .compile_top(str_val, Some(filename))
+                .map(|_| Script::new(StashRef::pop(self.ctx), filename));
+
+            ffi::duk_set_top(self.ctx, top);
+
+            result
+        }
+    }
+
+    ///
+    /// Load script from bytecode created by `Script::dump`
+    ///
+    /// # Arugments
+    ///
+    /// * `bytecode` - Bytecode with rubtle header
+    ///
+    /// # Returns
+    ///
+    /// Loaded `Script` or the reason of the rejection as `Error`
+    ///
+    /// # Safety
+    ///
+    /// Duktape doesn't validate bytecode, so crafted bytecode can corrupt
+    /// memory. The header only rejects bytecode of other duktape versions
+    /// and builds and catches truncated or corrupted data, so the caller must
+    /// make sure the bytecode was created by `Script::dump` of a trusted
+    /// source and hasn't been tampered with.
+    ///
+    /// # Example
+    ///
+    ///     use rubtle_lib::{Rubtle, Value};
+    ///
+    ///     let rubtle = Rubtle::new();
+    ///     let script = rubtle.compile("1 + 1", "rubtle.js").unwrap();
+    ///     let bytecode = script.dump(&rubtle).unwrap();
+    ///
+    ///     let rubtle2 = Rubtle::new();
+    ///     let script2 = unsafe { rubtle2.load(&bytecode).unwrap() };
+    ///
+    ///     assert_eq!(Value::from(2), script2.run(&rubtle2).unwrap());
+    ///
+
+    pub unsafe fn load(&self, bytecode: &[u8]) -> Result<Script, Error> {
+        unsafe extern "C" fn load_wrapper(
+            ctx: *mut ffi::duk_context,
+            _udata: *mut c_void,
+        ) -> ffi::duk_ret_t {
+            ffi::duk_load_function(ctx);
+
+            1
+        }
+
+        let (filename, data) = script::parse_header(bytecode)?;
+
+        let top = ffi::duk_get_top(self.ctx);
+
+        ffi::duk_require_stack(self.ctx, 1);
+
+        let buf = ffi::duk_push_buffer_raw(self.ctx, data.len() as u64, 0);
+
+        ptr::copy_nonoverlapping(data.as_ptr(), buf as *mut u8, data.len());
+
+        let result = if ffi::DUK_EXEC_SUCCESS as i32
+            == ffi::duk_safe_call(self.ctx, Some(load_wrapper), ptr::null_mut(), 1, 1)
+        {
+            Ok(Script::new(StashRef::pop(self.ctx), &filename))
+        } else {
+            Err(Error::Bytecode(self.pop_js_error().message))
+        };
+
+        ffi::duk_set_top(self.ctx, top);
+
+        result
+    }
+
+    ///
+    /// Compile given string and leave the function on top of the stack
+    ///
+    /// # Arugments
+    ///
+    /// * `str_val` - String to compile
+    /// * `filename` - Optional filename to use in error stacks
+    ///
+    /// # Returns
+    ///
+    /// Either nothing or the syntax error as `Error`
+    ///
+
+    pub(crate) unsafe fn compile_top(&self, str_val: &str, filename: Option<&str>) -> Result<(), Error> {
+        let mut flags = ffi::DUK_COMPILE_EVAL | ffi::DUK_COMPILE_NOSOURCE | ffi::DUK_COMPILE_SAFE;
+
+        ffi::duk_require_stack(self.ctx, 2);
+
+        match filename {
+            Some(filename) => {
+                self.push_value(&Value::from(filename));
+
+                flags |= 1; //< Number of args
+            }
+            None => flags |= ffi::DUK_COMPILE_NOFILENAME,
+        }
+
+        if 0 != ffi::duk_compile_raw(
+            self.ctx,
+            str_val.as_ptr() as *const c_char,
+            str_val.len() as u64,
+            flags,
+        ) {
+            Err(Error::Syntax(self.pop_js_error()))
+        } else {
+            Ok(())
+        }
+    }
+
+    ///
+    /// Run compiled function on top of the stack
+    ///
+    /// # Returns
+    ///
+    /// Completion value of the function as `Value` or the thrown exception as `Error`
+    ///
+
+    pub(crate) unsafe fn run_top(&self) -> Result<Value, Error> {
+        ffi::duk_require_stack(self.ctx, 1);
+        ffi::duk_push_global_object(self.ctx);
+
+        self.call_top(0)
+    }
+
+    ///
+    /// Call function below `this` and the arguments on top of the stack
+    ///
+    /// # Arguments
+    ///
+    /// * `nargs` - Number of arguments on the stack
+    ///
+    /// # Returns
+    ///
+    /// Return value of the function as `Value` or the thrown exception as `Error`
+    ///
+
+    pub(crate) unsafe fn call_top(&self, nargs: ffi::duk_idx_t) -> Result<Value, Error> {
+        let heap = HeapData::from_ctx(self.ctx);
+
+        heap.enter();
+
+        let result = if ffi::DUK_EXEC_SUCCESS as i32 == ffi::duk_pcall_method(self.ctx, nargs) {
+            let options = ConversionOptions::default();
+
+            match self.pop_converted(-1, &mut ConversionState::new(&options)) {
+                Ok(Some(rval)) => Ok(rval),
+                Ok(None) => {
+                    ffi::duk_pop(self.ctx);
+
+                    Ok(Value::None)
+                }
+                Err(err) => {
+                    ffi::duk_pop(self.ctx);
+
+                    Err(err)
+                }
+            }
+        } else {
+            Err(self.pop_run_error(heap))
+        };
+
+        heap.leave();
+
+        result
+    }
+
+    ///
+    /// Get property of the object on top of the stack in protected mode
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - Name of the property
+    ///
+    /// # Returns
+    ///
+    /// Either nothing or the thrown exception as `Error`; the object is
+    /// replaced by the value on success and removed on errors
+    ///
+
+    pub(crate) unsafe fn get_prop_top(&self, key: &str) -> Result<(), Error> {
+        ffi::duk_require_stack(self.ctx, 1);
+
+        convert::push_str(self.ctx, key);
+
+        self.safe_call(2, 1, |ctx| {
+            ffi::duk_get_prop(ctx, -2);
+
+            1
+        })
+    }
+
+    ///
+    /// Get indexed property of the object on top of the stack in protected
+    /// mode
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - Index of the property
+    ///
+    /// # Returns
+    ///
+    /// Either nothing or the thrown exception as `Error`; the object is
+    /// replaced by the value on success and removed on errors
+    ///
+
+    pub(crate) unsafe fn get_index_top(&self, index: ffi::duk_uarridx_t) -> Result<(), Error> {
+        ffi::duk_require_stack(self.ctx, 1);
+        ffi::duk_push_uint(self.ctx, index);
+
+        self.safe_call(2, 1, |ctx| {
+            ffi::duk_get_prop(ctx, -2);
+
+            1
+        })
+    }
+
+    ///
+    /// Push enumerator for the object on top of the stack in protected mode
+    ///
+    /// # Arguments
+    ///
+    /// * `flags` - Flags for `duk_enum`
+    ///
+    /// # Returns
+    ///
+    /// Either nothing or the thrown exception as `Error`, e.g. from the
+    /// `ownKeys` trap of a `Proxy`; the object is kept below the enumerator
+    ///
+
+    pub(crate) unsafe fn push_enum_top(&self, flags: ffi::duk_uint_t) -> Result<(), Error> {
+        ffi::duk_require_stack(self.ctx, 1);
+        ffi::duk_dup_top(self.ctx);
+
+        self.safe_call(1, 1, |ctx| {
+            ffi::duk_enum(ctx, -1, flags);
+
+            1
+        })
+    }
+
+    ///
+    /// Push next key and optionally value of the enumerator on top of the
+    /// stack in protected mode
+    ///
+    /// # Arguments
+    ///
+    /// * `get_value` - Whether to push the value as well; this invokes getters
+    ///
+    /// # Returns
+    ///
+    /// Either `true` if the key was pushed, `false` if the enumerator is
+    /// done or the thrown exception as `Error`
+    ///
+
+    pub(crate) unsafe fn next_enum_top(&self, get_value: bool) -> Result<bool, Error> {
+        let nrets = if get_value { 2 } else { 1 };
+
+        ffi::duk_require_stack(self.ctx, 3);
+        ffi::duk_dup_top(self.ctx);
+
+        self.safe_call(1, nrets, |ctx| {
+            if 0 == ffi::duk_next(ctx, -1, get_value as ffi::duk_bool_t) {
+                0
+            } else {
+                nrets
+            }
+        })?;
+
+        /* Missing return values are filled up with undefined */
+        if 1 == ffi::duk_is_undefined(self.ctx, -nrets) {
+            ffi::duk_pop_n(self.ctx, nrets);
+
+            return Ok(false);
+        }
+
+        Ok(true)
+    }
+
+    ///
+    /// Push global property in protected mode
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the property
+    ///
+    /// # Returns
+    ///
+    /// Either nothing or the thrown exception as `Error`
+    ///
+
+    unsafe fn push_global_prop(&self, name: &str) -> Result<(), Error> {
+        ffi::duk_require_stack(self.ctx, 1);
+        ffi::duk_push_global_object(self.ctx);
+
+        self.get_prop_top(name)
+    }
+
+    ///
+    /// Run closure in protected mode with the arguments on top of the stack
     ///
-    ///     let mut object = ObjectBuilder::<UserData>::new()
-    ///         .with_constructor(|inv| {
-    ///             let mut udata = inv.udata.as_mut().unwrap();
+    /// # Arguments
     ///
-    ///             udata.value = 0;
-    ///          })
-    ///         .build();
+    /// * `nargs` - Number of arguments on the stack
+    /// * `nrets` - Number of values the closure leaves on the stack; at least one
+    ///   to keep the error
+    /// * `func` - Closure to run; must not hold anything that needs dropping,
+    ///   because errors unwind the stack by longjmp
     ///
-    ///     let rubtle = Rubtle::new();
+    /// # Returns
     ///
-    ///     rubtle.set_global_object("Printer", &mut object);
+    /// Either nothing or the thrown exception as `Error`
     ///
 
-    pub fn set_global_object<T>(&self, name: &str, object: &mut Object<T>)
+    pub(crate) unsafe fn safe_call<F>(
+        &self,
+        nargs: ffi::duk_idx_t,
+        nrets: ffi::duk_idx_t,
+        mut func: F,
+    ) -> Result<(), Error>
     where
-        T: Default + 'static,
+        F: FnMut(*mut ffi::duk_context) -> ffi::duk_ret_t,
     {
-        unsafe extern "C" fn ctor_wrapper<T>(ctx: *mut ffi::duk_context) -> ffi::duk_ret_t
+        unsafe extern "C" fn trampoline<F>(
+            ctx: *mut ffi::duk_context,
+            udata: *mut c_void,
+        ) -> ffi::duk_ret_t
         where
-            T: Default + 'static,
+            F: FnMut(*mut ffi::duk_context) -> ffi::duk_ret_t,
         {
-            /* Verify this is a constrcutor call */
-            if 0 == ffi::duk_is_constructor_call(ctx) {
-                return -1;
-            }
+            (*(udata as *mut F))(ctx)
+        }
 
-            /* Get arguments from stack */
-            let rubtle = Rubtle {
-                ctx: ctx,
-                drop_ctx: false,
-            };
-            let nargs = ffi::duk_get_top(ctx) as usize;
-            let mut args = Vec::with_capacity(nargs);
+        assert!(0 < nrets, "Safe call without return value");
 
-            for i in 0..nargs {
-                ffi::duk_dup(ctx, i as ffi::duk_idx_t);
+        let heap = HeapData::from_ctx(self.ctx);
 
-                match rubtle.pop_value() {
-                    Some(val) => args.push(val),
-                    None => eprintln!("Unwrap of None value"),
-                }
-            }
+        heap.enter();
 
-            /* Create invocation data */
-            let mut inv = Invocation {
-                rubtle: &rubtle,
-                args: Some(args),
-                udata: Some(T::default()),
-            };
+        let result = if ffi::DUK_EXEC_SUCCESS as i32
+            == ffi::duk_safe_call(
+                self.ctx,
+                Some(trampoline::<F>),
+                &mut func as *mut F as *mut c_void,
+                nargs,
+                nrets,
+            )
+        {
+            Ok(())
+        } else {
+            /* Keep only the error of the return values */
+            ffi::duk_pop_n(self.ctx, nrets - 1);
 
-            /* Fetch pointer from duktape */
-            ffi::duk_push_current_function(ctx);
-            ffi::duk_get_prop_string(ctx, -1, CTOR.as_ptr() as *const _);
-            let func_ptr = ffi::duk_get_pointer(ctx, -1) as *mut ObjectBuilderCtor<T>;
-            ffi::duk_pop_n(ctx, 2);
+            Err(self.pop_run_error(heap))
+        };
 
-            assert!(!func_ptr.is_null(), "Null function pointer");
+        heap.leave();
 
-            /* Wrap function and finally call it */
-            let wrapped_func = || (*func_ptr)(&mut inv);
+        result
+    }
 
-            let _result = match catch_unwind(AssertUnwindSafe(wrapped_func)) {
-                Ok(res) => res,
-                Err(_) => {
-                    ffi::duk_fatal_raw(ctx, cstr!("Fatal error on func call"));
-                    unreachable!();
-                }
-            };
+    ///
+    /// Pop error of a failed run and take the limits of the heap into account
+    ///
+    /// # Arguments
+    ///
+    /// * `heap` - Heap data of the run
+    ///
+    /// # Returns
+    ///
+    /// Hit limit or the thrown exception as `Error`
+    ///
 
-            let boxed_udata = Box::into_raw(Box::new(inv));
+    unsafe fn pop_run_error(&self, heap: &HeapData) -> Error {
+        if heap.is_timed_out() {
+            ffi::duk_pop(self.ctx);
 
-            assert!(!boxed_udata.is_null(), "Null user data pointer");
+            Error::Limit(Limit::Time)
+        } else {
+            let err = self.pop_js_error();
 
-            /* Store user data */
-            ffi::duk_push_this(ctx);
-            ffi::duk_push_pointer(ctx, boxed_udata as *mut _);
-            ffi::duk_put_prop_string(ctx, -2, UDATA.as_ptr() as *const _);
+            /* Scripts may catch the alloc error and throw something else */
+            if heap.is_memory_exceeded() && is_alloc_error(&err) {
+                Error::Limit(Limit::Memory)
+            } else {
+                Error::Exception(err)
+            }
+        }
+    }
 
-            ffi::duk_pop(ctx);
+    ///
+    /// Push error onto duktape stack as JS error object
+    ///
+    /// # Arguments
+    ///
+    /// * `err` - Error to push; the constructor is picked by the name of JS errors
+    ///
 
-            0
-        }
+    pub(crate) fn push_error(&self, err: &Error) {
+        let (name, message) = match err {
+            Error::Syntax(js) | Error::Exception(js) => (&js.name[..], js.message.clone()),
+            Error::Callback(details) | Error::Bytecode(details) => ("Error", details.clone()),
+            Error::Conversion { .. } | Error::Serde(_) | Error::Heap(_) => {
+                ("TypeError", err.to_string())
+            }
+            Error::Limit(_) => ("RangeError", err.to_string()),
+        };
 
-        unsafe extern "C" fn meth_wrapper<T>(ctx: *mut ffi::duk_context) -> ffi::duk_ret_t
-        where
-            T: Default + 'static,
-        {
-            /* Get arguments from stack */
-            let rubtle = Rubtle {
-                ctx: ctx,
-                drop_ctx: false,
+        let cmesg = CString::new(to_cesu8(&message.replace('\0', "")).into_owned()).unwrap();
+
+        unsafe {
+            ffi::duk_require_stack(self.ctx, 2);
+
+            let err_code = match name {
+                "Error" => Some(ffi::DUK_ERR_ERROR),
+                "EvalError" => Some(ffi::DUK_ERR_EVAL_ERROR),
+                "RangeError" => Some(ffi::DUK_ERR_RANGE_ERROR),
+                "ReferenceError" => Some(ffi::DUK_ERR_REFERENCE_ERROR),
+                "SyntaxError" => Some(ffi::DUK_ERR_SYNTAX_ERROR),
+                "TypeError" => Some(ffi::DUK_ERR_TYPE_ERROR),
+                "URIError" => Some(ffi::DUK_ERR_URI_ERROR),
+                _ => None,
             };
-            let nargs = ffi::duk_get_top(ctx) as usize;
-            let mut args = Vec::with_capacity(nargs);
 
-            for i in 0..nargs {
-                ffi::duk_dup(ctx, i as ffi::duk_idx_t);
+            /* Try to create custom errors via their global constructor */
+            if err_code.is_none() && self.push_custom_error(name, &cmesg) {
+                return;
+            }
 
-                match rubtle.pop_value() {
-                    Some(val) => args.push(val),
-                    None => eprintln!("Unwrap of None value"),
-                }
+            ffi::duk_push_error_object_raw(
+                self.ctx,
+                err_code.unwrap_or(ffi::DUK_ERR_ERROR) as ffi::duk_errcode_t,
+                ptr::null(),
+                0,
+                cstr!("%s"),
+                cmesg.as_ptr(),
+            );
+
+            /* Keep name of unknown constructors */
+            if err_code.is_none() {
+                self.push_value(&Value::from(name));
+                ffi::duk_put_prop_string(self.ctx, -2, cstr!("name"));
             }
+        }
+    }
 
-            /* Fetch pointer from duktape */
-            ffi::duk_push_current_function(ctx);
-            ffi::duk_get_prop_string(ctx, -1, METH.as_ptr() as *const _);
-            let func_ptr = ffi::duk_get_pointer(ctx, -1) as *mut ObjectBuilderCallback<T>;
-            ffi::duk_pop_n(ctx, 2);
+    ///
+    /// Push error created by a global JS constructor onto duktape stack
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the global constructor
+    /// * `cmesg` - Message to pass to the constructor
+    ///
+    /// # Returns
+    ///
+    /// `true` if the error has been pushed; otherwise `false`
+    ///
 
-            assert!(!func_ptr.is_null(), "Null function pointer");
+    unsafe fn push_custom_error(&self, name: &str, cmesg: &CString) -> bool {
+        let cname = match CString::new(to_cesu8(name)) {
+            Ok(cname) => cname,
+            Err(_) => return false,
+        };
 
-            /* Fetch user data from duktape */
-            ffi::duk_push_this(ctx);
-            ffi::duk_get_prop_string(ctx, -1, UDATA.as_ptr() as *const _);
-            let inv_ptr = ffi::duk_get_pointer(ctx, -1) as *mut Invocation<T>;
-            ffi::duk_pop_n(ctx, 2);
+        ffi::duk_get_global_lstring(self.ctx, cname.as_ptr(), cname.as_bytes().len() as u64);
 
-            assert!(!inv_ptr.is_null(), "Null user data pointer");
+        if 1 == ffi::duk_is_constructable(self.ctx, -1) {
+            ffi::duk_push_lstring(self.ctx, cmesg.as_ptr(), cmesg.as_bytes().len() as u64);
 
-            (*inv_ptr).args = Some(args);
+            if ffi::DUK_EXEC_SUCCESS as i32 == ffi::duk_pnew(self.ctx, 1) {
+                return true;
+            }
+        }
 
-            /* Wrap function and finally call it */
-            let wrapped_func = || (*func_ptr)(&mut *inv_ptr);
-            let result = match catch_unwind(AssertUnwindSafe(wrapped_func)) {
-                Ok(res) => res,
-                Err(_) => {
-                    ffi::duk_fatal_raw(ctx, cstr!("Fatal error on func call"));
-                    unreachable!();
+        /* Remove constructor or thrown error */
+        ffi::duk_pop(self.ctx);
+
+        false
+    }
+
+    ///
+    /// Pop thrown error from duktape stack
+    ///
+    /// # Returns
+    ///
+    /// The error on top of the stack as `JsError`
+    ///
+
+    pub(crate) fn pop_js_error(&self) -> JsError {
+        unsafe {
+            let err = if 0 != ffi::duk_get_error_code(self.ctx, -1) {
+                JsError {
+                    name: self
+                        .get_prop_string(-1, cstr!("name"))
+                        .unwrap_or_else(|| String::from("Error")),
+                    message: self.get_prop_string(-1, cstr!("message")).unwrap_or_default(),
+                    stack: self.get_prop_string(-1, cstr!("stack")),
+                    file_name: self.get_prop_string(-1, cstr!("fileName")),
+                    line_number: self.get_prop_number(-1, cstr!("lineNumber")).map(|n| n as u32),
+                }
+            } else {
+                /* Anything can be thrown, so just coerce it */
+                JsError {
+                    name: String::from("Error"),
+                    message: self.to_string_at(-1),
+                    stack: None,
+                    file_name: None,
+                    line_number: None,
                 }
             };
 
-            match result {
-                Ok(val) => {
-                    rubtle.push_value(&val);
+            ffi::duk_pop(self.ctx);
 
-                    1
-                }
-                Err(_) => 0,
-            }
+            err
         }
+    }
 
-        unsafe {
-            let cstr = CString::new(to_cesu8(name));
-
-            match cstr {
-                Ok(cval) => {
-                    ffi::duk_push_c_function(self.ctx, Some(ctor_wrapper::<T>), -1);
+    ///
+    /// Get number property of value at given index
+    ///
+    /// # Arguments
+    ///
+    /// * `idx` - Stack index of the object
+    /// * `key` - Name of the property
+    ///
+    /// # Returns
+    ///
+    /// `Option` with the property value when it is a number
+    ///
 
-                    /* Store ctor wrapper */
-                    match object.take_constructor() {
-                        Some(ctor) => {
-                            let boxed_func = Box::into_raw(Box::new(ctor));
+    unsafe fn get_prop_number(&self, idx: ffi::duk_idx_t, key: *const c_char) -> Option<f64> {
+        ffi::duk_require_stack(self.ctx, 1);
+        ffi::duk_get_prop_string(self.ctx, idx, key);
 
-                            ffi::duk_push_pointer(self.ctx, boxed_func as *mut _);
-                            ffi::duk_put_prop_string(self.ctx, -2, CTOR.as_ptr() as *const _);
-                        }
-                        None => {
-                            ffi::duk_fatal_raw(self.ctx, cstr!("No constructor"));
-                            unreachable!();
-                        }
-                    }
+        let result = if 1 == ffi::duk_is_number(self.ctx, -1) {
+            Some(ffi::duk_get_number(self.ctx, -1))
+        } else {
+            None
+        };
 
-                    ffi::duk_push_object(self.ctx);
+        ffi::duk_pop(self.ctx);
 
-                    /* Store method wrapper */
-                    for (name, meth) in object {
-                        let cstr = CString::new(to_cesu8(name));
+        result
+    }
 
-                        match cstr {
-                            Ok(cval) => {
-                                let boxed_func = Box::into_raw(Box::new(meth));
+    ///
+    /// Get string property of value at given index
+    ///
+    /// # Arguments
+    ///
+    /// * `idx` - Stack index of the object
+    /// * `key` - Name of the property
+    ///
+    /// # Returns
+    ///
+    /// `Option` with the property value when it is a string
+    ///
 
-                                ffi::duk_push_c_function(self.ctx, Some(meth_wrapper::<T>), -1); //< (DUK_VARARGS)
+    unsafe fn get_prop_string(&self, idx: ffi::duk_idx_t, key: *const c_char) -> Option<String> {
+        ffi::duk_require_stack(self.ctx, 1);
+        ffi::duk_get_prop_string(self.ctx, idx, key);
 
-                                ffi::duk_push_pointer(self.ctx, boxed_func as *mut _);
-                                ffi::duk_put_prop_string(self.ctx, -2, METH.as_ptr() as *const _);
+        let result = if 1 == ffi::duk_is_string(self.ctx, -1) {
+            Some(self.to_string_at(-1))
+        } else {
+            None
+        };
 
-                                ffi::duk_put_prop_lstring(
-                                    self.ctx,
-                                    -2,
-                                    cval.as_ptr(),
-                                    cval.as_bytes().len() as u64,
-                                );
-                            }
-                            Err(_) => unimplemented!(),
-                        }
-                    }
+        ffi::duk_pop(self.ctx);
 
-                    ffi::duk_put_prop_string(self.ctx, -2, cstr!("prototype"));
-                    ffi::duk_put_global_lstring(
-                        self.ctx,
-                        cval.as_ptr(),
-                        cval.as_bytes().len() as u64,
-                    );
-                }
-                Err(_) => unimplemented!(),
-            }
-        }
+        result
     }
 
     ///
-    /// Eval given string
-    ///
-    /// # Arugments
+    /// Safely coerce value at given index to string without popping it
     ///
-    /// * `str_val` - String to eval
+    /// # Arguments
     ///
-    /// # Example
+    /// * `idx` - Stack index of the value
     ///
-    ///     use rubtle_lib::Rubtle;
+    /// # Returns
     ///
-    ///     let rubtle = Rubtle::new();
+    /// Coerced value as `String`
     ///
-    ///     rubtle.eval(r#"
-    ///         var rubtle = 4;
-    ///     "#)
-    ///
-
-    pub fn eval(&self, str_val: &str) {
-        let cstr = CString::new(str_val);
-
-        match cstr {
-            Ok(val) => unsafe {
-                ffi::duk_eval_raw(
-                    self.ctx,
-                    val.as_ptr(),
-                    val.into_bytes().len() as u64,
-                    ffi::DUK_COMPILE_EVAL
-                        | ffi::DUK_COMPILE_NOSOURCE
-                        | ffi::DUK_COMPILE_NORESULT
-                        | ffi::DUK_COMPILE_NOFILENAME,
-                );
-            },
-            Err(e) => eprintln!("Cannot eval str '{}': {}", str_val, e),
-        }
+
+    unsafe fn to_string_at(&self, idx: ffi::duk_idx_t) -> String {
+        let mut len: ffi::duk_size_t = 0;
+
+        ffi::duk_require_stack(self.ctx, 1);
+        ffi::duk_dup(self.ctx, idx);
+
+        let data = ffi::duk_safe_to_lstring(self.ctx, -1, &mut len);
+        let bytes = slice::from_raw_parts(data as *const u8, len as usize);
+
+        let string = match from_cesu8(bytes) {
+            Ok(string) => string.into_owned(),
+            Err(_) => String::from_utf8_lossy(bytes).into_owned(),
+        };
+
+        ffi::duk_pop(self.ctx);
+
+        string
     }
 
     ///
     /// Create and init duktape context
     ///
+    /// # Arguments
+    ///
+    /// * `heap_data` - Heap data; installs the Rust allocator when it tracks memory
+    ///
     /// # Returns
     ///
-    /// A new duktape heap context
+    /// Either a new duktape heap context or `Error::Limit` when the heap
+    /// cannot be allocated, e.g. because of a too small memory limit
     ///
 
-    unsafe fn create_heap() -> *mut ffi::duk_context {
-        let ctx = ffi::duk_create_heap(None, None, None, ptr::null_mut(), Some(fatal_handler));
+    pub(crate) unsafe fn create_heap(heap_data: HeapData) -> Result<*mut ffi::duk_context, Error> {
+        let with_allocator = heap_data.memory.is_some();
+        let heap_data = Box::into_raw(Box::new(heap_data));
+
+        let ctx = if with_allocator {
+            ffi::duk_create_heap(
+                Some(allocator::alloc_func),
+                Some(allocator::realloc_func),
+                Some(allocator::free_func),
+                heap_data as *mut c_void,
+                Some(fatal_handler),
+            )
+        } else {
+            ffi::duk_create_heap(None, None, None, heap_data as *mut c_void, Some(fatal_handler))
+        };
 
-        ctx
+        if ctx.is_null() {
+            drop(Box::from_raw(heap_data));
+
+            return Err(Error::Limit(Limit::Memory));
+        }
+
+        Ok(ctx)
     }
 }
 
@@ -784,12 +2750,86 @@ unsafe extern "C" fn fatal_handler(_udata: *mut c_void, msg: *const c_char) {
     process::abort();
 }
 
+///
+/// Extract message from panic payload
+///
+/// # Arguments
+///
+/// * `payload` - Payload caught by `catch_unwind`
+///
+/// # Returns
+///
+/// Panic message as `String`
+///
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    if let Some(mesg) = payload.downcast_ref::<&str>() {
+        mesg.to_string()
+    } else if let Some(mesg) = payload.downcast_ref::<String>() {
+        mesg.clone()
+    } else {
+        String::from("Panic in host function")
+    }
+}
+
+///
+/// Check whether error has been thrown by duktape on failed allocations
+///
+/// # Arguments
+///
+/// * `err` - Popped JS error
+///
+/// # Returns
+///
+/// `true` for the alloc error or the double error duktape throws when it
+/// cannot even allocate the error; otherwise `false`
+///
+
+fn is_alloc_error(err: &JsError) -> bool {
+    ("Error" == err.name && "alloc failed" == err.message) || "DoubleError" == err.name
+}
+
+///
+/// Throw error as JS exception from a C function
+///
+/// # Arguments
+///
+/// * `ctx` - Duktape context
+/// * `err` - Error to throw
+///
+/// This unwinds via longjmp, so callers must not hold values with
+/// pending drops when calling this.
+///
+
+unsafe fn throw_error(ctx: *mut ffi::duk_context, err: Error) -> ffi::duk_ret_t {
+    let rubtle = Rubtle {
+        ctx: ctx,
+        drop_ctx: false,
+    };
+
+    rubtle.push_error(&err);
+
+    drop(err);
+    drop(rubtle);
+
+    ffi::duk_throw_raw(ctx);
+
+    unreachable!();
+}
+
 impl Drop for Rubtle {
     fn drop(&mut self) {
         /* Check wether heap needs to be kept alive */
         if self.drop_ctx {
             unsafe {
+                let heap_data = HeapData::ptr_from_ctx(self.ctx);
+
+                /* Stop handles from touching the heap while it goes down */
+                HeapData::from_ctx(self.ctx).kill();
+
                 ffi::duk_destroy_heap(self.ctx);
+
+                drop(Box::from_raw(heap_data));
             }
         }
     }
@@ -0,0 +1,109 @@
+///
+/// @package Rubtle-Lib
+///
+/// @file RubtleBuilder functions
+/// @copyright 2020-present Christoph Kappel <christoph@unexist.dev>
+/// @version $Id$
+///
+/// This program can be distributed under the terms of the GNU GPLv2.
+/// See the file LICENSE for details.
+//
+use crate::allocator::MemoryTracker;
+use crate::heap::HeapData;
+use crate::{Error, Rubtle};
+
+#[derive(Default)]
+pub struct RubtleBuilder {
+    track_memory: bool,
+    memory_limit: Option<usize>,
+}
+
+impl RubtleBuilder {
+    ///
+    /// Create a new RubtleBuilder
+    ///
+    /// # Example
+    ///
+    ///     use rubtle_lib::RubtleBuilder;
+    ///
+    ///     let rubtle = RubtleBuilder::new()
+    ///         .with_memory_limit(4 * 1024 * 1024)
+    ///         .build();
+    ///
+
+    pub fn new() -> RubtleBuilder {
+        RubtleBuilder::default()
+    }
+
+    ///
+    /// Install Rust allocator that tracks memory usage
+    ///
+
+    pub fn with_memory_tracking(&mut self) -> &mut RubtleBuilder {
+        self.track_memory = true;
+
+        self
+    }
+
+    ///
+    /// Install Rust allocator that fails allocations above given limit
+    ///
+    /// # Arguments
+    ///
+    /// * `limit` - Hard limit of allocated bytes
+    ///
+
+    pub fn with_memory_limit(&mut self, limit: usize) -> &mut RubtleBuilder {
+        self.track_memory = true;
+        self.memory_limit = Some(limit);
+
+        self
+    }
+
+    ///
+    /// Create Rubtle instance with the configured options
+    ///
+    /// # Returns
+    ///
+    /// New `Rubtle` instance
+    ///
+    /// # Panics
+    ///
+    /// Panics if the heap cannot be created within the memory limit; use
+    /// `try_build` to handle this
+    ///
+
+    pub fn build(&mut self) -> Rubtle {
+        self.try_build().expect("Failed to create heap")
+    }
+
+    ///
+    /// Create Rubtle instance with the configured options
+    ///
+    /// # Returns
+    ///
+    /// Either the new `Rubtle` instance or `Error::Limit` if the heap cannot
+    /// be created within the memory limit
+    ///
+    /// # Example
+    ///
+    ///     use rubtle_lib::{Error, Limit, RubtleBuilder};
+    ///
+    ///     let rval = RubtleBuilder::new().with_memory_limit(1024).try_build();
+    ///
+    ///     assert!(matches!(rval, Err(Error::Limit(Limit::Memory))));
+    ///
+
+    pub fn try_build(&mut self) -> Result<Rubtle, Error> {
+        let memory = if self.track_memory {
+            Some(MemoryTracker::new(self.memory_limit))
+        } else {
+            None
+        };
+
+        Ok(Rubtle {
+            ctx: unsafe { Rubtle::create_heap(HeapData::new(memory))? },
+            drop_ctx: true,
+        })
+    }
+}
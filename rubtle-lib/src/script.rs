@@ -0,0 +1,289 @@
+///
+/// @package Rubtle-Lib
+///
+/// @file Script functions
+/// @copyright 2020-present Christoph Kappel <christoph@unexist.dev>
+/// @version $Id$
+///
+/// This program can be distributed under the terms of the GNU GPLv2.
+/// See the file LICENSE for details.
+//
+use std::{fmt, ptr, slice};
+
+use std::convert::TryInto;
+use std::os::raw::c_void;
+
+use crate::stash::StashRef;
+use crate::{Error, Rubtle, Value};
+
+/// Magic bytes of the bytecode header
+const MAGIC: &[u8; 4] = b"RBTL";
+
+/// Version of the bytecode header format
+const FORMAT: u8 = 2;
+
+/// Size of the fixed part of the bytecode header
+const HEADER_LEN: usize = 25;
+
+pub struct Script {
+    /// Compiled function pinned in the heap stash
+    func: StashRef,
+
+    /// Filename used in error stacks
+    filename: String,
+}
+
+impl Script {
+    pub(crate) fn new(func: StashRef, filename: &str) -> Script {
+        Script {
+            func,
+            filename: filename.to_string(),
+        }
+    }
+
+    ///
+    /// Run compiled script
+    ///
+    /// # Arguments
+    ///
+    /// * `rubtle` - Rubtle instance the script was compiled with
+    ///
+    /// # Returns
+    ///
+    /// Completion value of the script as `Value` or the thrown exception as `Error`
+    ///
+    /// # Example
+    ///
+    ///     use rubtle_lib::{Rubtle, Value};
+    ///
+    ///     let rubtle = Rubtle::new();
+    ///
+    ///     let script = rubtle.compile("1 + 1", "rubtle.js").unwrap();
+    ///
+    ///     assert_eq!(Value::from(2), script.run(&rubtle).unwrap());
+    ///
+
+    pub fn run(&self, rubtle: &Rubtle) -> Result<Value, Error> {
+        unsafe {
+            let top = ffi::duk_get_top(rubtle.ctx);
+
+            self.func.push(rubtle.ctx)?;
+
+            let result = rubtle.run_top();
+
+            ffi::duk_set_top(rubtle.ctx, top);
+
+            result
+        }
+    }
+
+    ///
+    /// Dump compiled script as bytecode
+    ///
+    /// # Arguments
+    ///
+    /// * `rubtle` - Rubtle instance the script was compiled with
+    ///
+    /// # Returns
+    ///
+    /// Bytecode with a header that identifies the engine build
+    ///
+    /// # Example
+    ///
+    ///     use rubtle_lib::Rubtle;
+    ///
+    ///     let rubtle = Rubtle::new();
+    ///     let script = rubtle.compile("1 + 1", "rubtle.js").unwrap();
+    ///
+    ///     let bytecode = script.dump(&rubtle).unwrap();
+    ///
+
+    pub fn dump(&self, rubtle: &Rubtle) -> Result<Vec<u8>, Error> {
+        unsafe extern "C" fn dump_wrapper(
+            ctx: *mut ffi::duk_context,
+            _udata: *mut c_void,
+        ) -> ffi::duk_ret_t {
+            ffi::duk_dump_function(ctx);
+
+            1
+        }
+
+        unsafe {
+            let top = ffi::duk_get_top(rubtle.ctx);
+
+            self.func.push(rubtle.ctx)?;
+
+            let result = if ffi::DUK_EXEC_SUCCESS as i32
+                == ffi::duk_safe_call(rubtle.ctx, Some(dump_wrapper), ptr::null_mut(), 1, 1)
+            {
+                let mut len: ffi::duk_size_t = 0;
+                let data = ffi::duk_get_buffer(rubtle.ctx, -1, &mut len);
+
+                let bytes = slice::from_raw_parts(data as *const u8, len as usize);
+
+                Ok(write_header(&self.filename, bytes))
+            } else {
+                Err(Error::Bytecode(rubtle.pop_js_error().message))
+            };
+
+            ffi::duk_set_top(rubtle.ctx, top);
+
+            result
+        }
+    }
+
+    ///
+    /// Return filename of the script
+    ///
+    /// Returns
+    ///
+    /// Filename used in error stacks
+    ///
+
+    pub fn filename(&self) -> &str {
+        &self.filename
+    }
+}
+
+impl fmt::Debug for Script {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "script({})", self.filename)
+    }
+}
+
+///
+/// Prepend rubtle header to duktape bytecode
+///
+/// # Arguments
+///
+/// * `filename` - Filename of the script
+/// * `data` - Duktape bytecode
+///
+/// # Returns
+///
+/// Bytecode with header
+///
+
+fn write_header(filename: &str, data: &[u8]) -> Vec<u8> {
+    let mut bytecode = Vec::with_capacity(HEADER_LEN + filename.len() + data.len());
+
+    bytecode.extend_from_slice(MAGIC);
+    bytecode.push(FORMAT);
+    bytecode.extend_from_slice(&ffi::DUK_VERSION.to_le_bytes());
+    bytecode.extend_from_slice(&build_flags().to_le_bytes());
+    bytecode.extend_from_slice(&(filename.len() as u32).to_le_bytes());
+    bytecode.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    bytecode.extend_from_slice(&checksum(data).to_le_bytes());
+    bytecode.extend_from_slice(filename.as_bytes());
+    bytecode.extend_from_slice(data);
+
+    bytecode
+}
+
+///
+/// Verify and strip rubtle header from bytecode
+///
+/// # Arguments
+///
+/// * `bytecode` - Bytecode with header
+///
+/// # Returns
+///
+/// Filename and duktape bytecode or the reason of the rejection as `Error`
+///
+
+pub(crate) fn parse_header(bytecode: &[u8]) -> Result<(String, &[u8]), Error> {
+    let read_u32 = |pos: usize| -> Result<u32, Error> {
+        bytecode
+            .get(pos..pos + 4)
+            .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+            .ok_or_else(|| Error::Bytecode(String::from("Truncated bytecode header")))
+    };
+
+    if bytecode.len() < MAGIC.len() + 1 || &bytecode[..MAGIC.len()] != MAGIC {
+        return Err(Error::Bytecode(String::from("Invalid bytecode header")));
+    }
+
+    if FORMAT != bytecode[4] {
+        return Err(Error::Bytecode(format!(
+            "Unsupported bytecode format {}, expected {}",
+            bytecode[4], FORMAT
+        )));
+    }
+
+    let version = read_u32(5)?;
+
+    if ffi::DUK_VERSION != version {
+        return Err(Error::Bytecode(format!(
+            "Bytecode from duktape {}, expected {}",
+            version,
+            ffi::DUK_VERSION
+        )));
+    }
+
+    let flags = read_u32(9)?;
+
+    if build_flags() != flags {
+        return Err(Error::Bytecode(format!(
+            "Bytecode from build {:#x}, expected {:#x}",
+            flags,
+            build_flags()
+        )));
+    }
+
+    let name_len = read_u32(13)? as usize;
+    let data_len = read_u32(17)? as usize;
+    let sum = read_u32(21)?;
+
+    let filename = bytecode
+        .get(HEADER_LEN..HEADER_LEN + name_len)
+        .and_then(|bytes| String::from_utf8(bytes.to_vec()).ok())
+        .ok_or_else(|| Error::Bytecode(String::from("Invalid bytecode filename")))?;
+
+    let data = &bytecode[HEADER_LEN + name_len..];
+
+    if data_len != data.len() {
+        return Err(Error::Bytecode(format!(
+            "Bytecode has {} bytes, expected {}",
+            data.len(),
+            data_len
+        )));
+    }
+
+    if checksum(data) != sum {
+        return Err(Error::Bytecode(String::from("Bytecode checksum mismatch")));
+    }
+
+    Ok((filename, data))
+}
+
+///
+/// Get fingerprint of the duktape build
+///
+/// # Returns
+///
+/// Flags of the build configuration that change the bytecode format,
+/// like packed values, fastints, byte order and pointer size
+///
+
+fn build_flags() -> u32 {
+    unsafe { ffi::rust_duk_get_build_flags() }
+}
+
+///
+/// Calculate FNV-1a checksum of the bytecode
+///
+/// # Arguments
+///
+/// * `data` - Duktape bytecode
+///
+/// # Returns
+///
+/// Checksum to catch truncated or corrupted bytecode
+///
+
+fn checksum(data: &[u8]) -> u32 {
+    data.iter().fold(0x811c_9dc5, |hash: u32, byte| {
+        (hash ^ *byte as u32).wrapping_mul(0x0100_0193)
+    })
+}
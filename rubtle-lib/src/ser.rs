@@ -0,0 +1,872 @@
+///
+/// @package Rubtle-Lib
+///
+/// @file Serde serializer functions
+/// @copyright 2020-present Christoph Kappel <christoph@unexist.dev>
+/// @version $Id$
+///
+/// This program can be distributed under the terms of the GNU GPLv2.
+/// See the file LICENSE for details.
+//
+use std::fmt;
+
+use serde::ser::{self, Serialize};
+
+use crate::convert::{self, ToJs};
+use crate::value;
+use crate::{Error, ObjectMap, Rubtle, Value};
+
+impl ser::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Error {
+        Error::Serde(msg.to_string())
+    }
+}
+
+///
+/// Serialize any `Serialize` type into a `Value`
+///
+/// # Arguments
+///
+/// * `rval` - Value to serialize
+///
+/// # Returns
+///
+/// Either the new `Value` or the serialization `Error`
+///
+/// # Example
+///
+///     use std::collections::BTreeMap;
+///
+///     use rubtle_lib::{to_value, Value};
+///
+///     let mut map = BTreeMap::new();
+///
+///     map.insert("rubtle", (1, true));
+///
+///     let rval = to_value(&map).unwrap();
+///
+///     assert!(rval.is_object());
+///
+
+pub fn to_value<T: Serialize + ?Sized>(rval: &T) -> Result<Value, Error> {
+    rval.serialize(ValueSerializer)
+}
+
+///
+/// Serialize any `Serialize` type directly onto the stack
+///
+/// # Arguments
+///
+/// * `rubtle` - Rubtle instance to push onto
+/// * `rval` - Value to serialize
+///
+/// # Returns
+///
+/// Either nothing or the serialization `Error`; the stack is left
+/// untouched on errors
+///
+
+pub(crate) fn push_serialize<T: Serialize + ?Sized>(rubtle: &Rubtle, rval: &T) -> Result<(), Error> {
+    unsafe {
+        let top = ffi::duk_get_top(rubtle.ctx);
+
+        let result = rval.serialize(StackSerializer { rubtle });
+
+        if result.is_err() {
+            ffi::duk_set_top(rubtle.ctx, top);
+        }
+
+        result
+    }
+}
+
+///
+/// Serialize map key into a string like JS property keys
+///
+/// # Arguments
+///
+/// * `key` - Key to serialize
+///
+/// # Returns
+///
+/// Either the key as `String` or the serialization `Error`
+///
+
+fn key_to_string<T: Serialize + ?Sized>(key: &T) -> Result<String, Error> {
+    match key.serialize(ValueSerializer)? {
+        Value::Str(key) => Ok(key),
+        Value::Boolean(key) => Ok(key.to_string()),
+        Value::Number(key) => Ok(key.to_string()),
+        rval => Err(Error::Serde(format!("Expected string key but got {}", rval.type_name()))),
+    }
+}
+
+impl Serialize for Value {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Value::None | Value::Null => serializer.serialize_unit(),
+            Value::Boolean(val) => serializer.serialize_bool(*val),
+            Value::Number(val) => serializer.serialize_f64(*val),
+            Value::Str(val) => serializer.serialize_str(val),
+            Value::Array(val) => serializer.collect_seq(val),
+            Value::Object(val) => serializer.collect_map(val),
+            Value::Bytes(val) => serializer.serialize_bytes(val),
+            Value::Date(val) => serializer.serialize_f64(value::to_time_value(val)),
+            Value::Function(_) | Value::External(_) | Value::Ref(_) => Err(ser::Error::custom(format!(
+                "Cannot serialize {}",
+                self.type_name()
+            ))),
+        }
+    }
+}
+
+/// Serializer into a `Value`
+struct ValueSerializer;
+
+/// Serializer of sequences and tuples into a `Value::Array`
+struct ValueArray {
+    vec: Vec<Value>,
+
+    /// Name of the variant for tuple variants
+    variant: Option<&'static str>,
+}
+
+/// Serializer of maps and structs into a `Value::Object`
+struct ValueObject {
+    hash: ObjectMap,
+
+    /// Key of the pending map entry
+    key: Option<String>,
+
+    /// Name of the variant for struct variants
+    variant: Option<&'static str>,
+}
+
+///
+/// Wrap value into an object with the variant as key
+///
+/// # Arguments
+///
+/// * `variant` - Name of the variant
+/// * `rval` - Content of the variant
+///
+/// # Returns
+///
+/// A new `Value::Object`
+///
+
+fn wrap_variant(variant: &'static str, rval: Value) -> Value {
+    let mut hash = ObjectMap::new();
+
+    hash.insert(variant, rval);
+
+    Value::Object(hash)
+}
+
+impl ser::Serializer for ValueSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    type SerializeSeq = ValueArray;
+    type SerializeTuple = ValueArray;
+    type SerializeTupleStruct = ValueArray;
+    type SerializeTupleVariant = ValueArray;
+    type SerializeMap = ValueObject;
+    type SerializeStruct = ValueObject;
+    type SerializeStructVariant = ValueObject;
+
+    fn serialize_bool(self, v: bool) -> Result<Value, Error> {
+        Ok(Value::Boolean(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Value, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Value, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Value, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Value, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Value, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Value, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Value, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Value, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Value, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Value, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Value, Error> {
+        Ok(Value::Str(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Value, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Value, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_none(self) -> Result<Value, Error> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Value, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Value, Error> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Value, Error> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Value, Error> {
+        Ok(Value::from(variant))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Value, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Value, Error> {
+        Ok(wrap_variant(variant, value.serialize(self)?))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<ValueArray, Error> {
+        Ok(ValueArray {
+            vec: Vec::with_capacity(len.unwrap_or(0)),
+            variant: None,
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<ValueArray, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<ValueArray, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<ValueArray, Error> {
+        Ok(ValueArray {
+            vec: Vec::with_capacity(len),
+            variant: Some(variant),
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<ValueObject, Error> {
+        Ok(ValueObject {
+            hash: ObjectMap::new(),
+            key: None,
+            variant: None,
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<ValueObject, Error> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<ValueObject, Error> {
+        Ok(ValueObject {
+            hash: ObjectMap::new(),
+            key: None,
+            variant: Some(variant),
+        })
+    }
+}
+
+impl ValueArray {
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.vec.push(value.serialize(ValueSerializer)?);
+
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Value, Error> {
+        let rval = Value::Array(self.vec);
+
+        Ok(match self.variant {
+            Some(variant) => wrap_variant(variant, rval),
+            None => rval,
+        })
+    }
+}
+
+impl ser::SerializeSeq for ValueArray {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTuple for ValueArray {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleStruct for ValueArray {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleVariant for ValueArray {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        self.finish()
+    }
+}
+
+impl ValueObject {
+    fn insert<T: Serialize + ?Sized>(&mut self, key: String, value: &T) -> Result<(), Error> {
+        self.hash.insert(key, value.serialize(ValueSerializer)?);
+
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Value, Error> {
+        let rval = Value::Object(self.hash);
+
+        Ok(match self.variant {
+            Some(variant) => wrap_variant(variant, rval),
+            None => rval,
+        })
+    }
+}
+
+impl ser::SerializeMap for ValueObject {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+        self.key = Some(key_to_string(key)?);
+
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        let key = self.key.take().unwrap_or_default();
+
+        self.insert(key, value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStruct for ValueObject {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.insert(String::from(key), value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStructVariant for ValueObject {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.insert(String::from(key), value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        self.finish()
+    }
+}
+
+/// Serializer directly onto the stack
+struct StackSerializer<'a> {
+    rubtle: &'a Rubtle,
+}
+
+/// Serializer of sequences and tuples into an array on the stack
+struct StackArray<'a> {
+    rubtle: &'a Rubtle,
+
+    /// Index of the next element
+    idx: ffi::duk_uarridx_t,
+
+    /// Whether the array is wrapped into a variant object
+    variant: bool,
+}
+
+/// Serializer of maps and structs into an object on the stack
+struct StackObject<'a> {
+    rubtle: &'a Rubtle,
+
+    /// Whether the object is wrapped into a variant object
+    variant: bool,
+}
+
+impl<'a> StackSerializer<'a> {
+    ///
+    /// Push object with the variant as pending key
+    ///
+    /// # Arguments
+    ///
+    /// * `variant` - Name of the variant
+    ///
+
+    fn push_variant(&self, variant: &str) {
+        unsafe {
+            ffi::duk_require_stack(self.rubtle.ctx, 3);
+            ffi::duk_push_object(self.rubtle.ctx);
+            convert::push_str(self.rubtle.ctx, variant);
+        }
+    }
+
+    fn push<T: ToJs + ?Sized>(self, value: &T) -> Result<(), Error> {
+        value.to_js(self.rubtle);
+
+        Ok(())
+    }
+}
+
+impl<'a> ser::Serializer for StackSerializer<'a> {
+    type Ok = ();
+    type Error = Error;
+
+    type SerializeSeq = StackArray<'a>;
+    type SerializeTuple = StackArray<'a>;
+    type SerializeTupleStruct = StackArray<'a>;
+    type SerializeTupleVariant = StackArray<'a>;
+    type SerializeMap = StackObject<'a>;
+    type SerializeStruct = StackObject<'a>;
+    type SerializeStructVariant = StackObject<'a>;
+
+    fn serialize_bool(self, v: bool) -> Result<(), Error> {
+        self.push(&v)
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<(), Error> {
+        self.push(&v)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<(), Error> {
+        self.push(&v)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<(), Error> {
+        self.push(&v)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<(), Error> {
+        self.push(&v)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<(), Error> {
+        self.push(&v)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<(), Error> {
+        self.push(&v)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<(), Error> {
+        self.push(&v)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<(), Error> {
+        self.push(&v)
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<(), Error> {
+        self.push(&v)
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<(), Error> {
+        self.push(&v)
+    }
+
+    fn serialize_char(self, v: char) -> Result<(), Error> {
+        self.push(&v.to_string())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<(), Error> {
+        self.push(v)
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<(), Error> {
+        self.push(&Value::from(v))
+    }
+
+    fn serialize_none(self) -> Result<(), Error> {
+        self.push(&Value::Null)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<(), Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), Error> {
+        self.push(&Value::Null)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), Error> {
+        self.push(&Value::Null)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<(), Error> {
+        self.push(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        let rubtle = self.rubtle;
+
+        self.push_variant(variant);
+        value.serialize(self)?;
+
+        unsafe {
+            ffi::duk_put_prop(rubtle.ctx, -3);
+        }
+
+        Ok(())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<StackArray<'a>, Error> {
+        unsafe {
+            ffi::duk_require_stack(self.rubtle.ctx, 2);
+            ffi::duk_push_array(self.rubtle.ctx);
+        }
+
+        Ok(StackArray {
+            rubtle: self.rubtle,
+            idx: 0,
+            variant: false,
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<StackArray<'a>, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<StackArray<'a>, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<StackArray<'a>, Error> {
+        self.push_variant(variant);
+
+        self.serialize_seq(Some(len)).map(|array| StackArray {
+            variant: true,
+            ..array
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<StackObject<'a>, Error> {
+        unsafe {
+            ffi::duk_require_stack(self.rubtle.ctx, 3);
+            ffi::duk_push_object(self.rubtle.ctx);
+        }
+
+        Ok(StackObject {
+            rubtle: self.rubtle,
+            variant: false,
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<StackObject<'a>, Error> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<StackObject<'a>, Error> {
+        self.push_variant(variant);
+
+        self.serialize_map(Some(len)).map(|object| StackObject {
+            variant: true,
+            ..object
+        })
+    }
+}
+
+impl<'a> StackArray<'a> {
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        value.serialize(StackSerializer { rubtle: self.rubtle })?;
+
+        unsafe {
+            ffi::duk_put_prop_index(self.rubtle.ctx, -2, self.idx);
+        }
+
+        self.idx += 1;
+
+        Ok(())
+    }
+
+    fn finish(self) -> Result<(), Error> {
+        if self.variant {
+            unsafe {
+                ffi::duk_put_prop(self.rubtle.ctx, -3);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> ser::SerializeSeq for StackArray<'a> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.finish()
+    }
+}
+
+impl<'a> ser::SerializeTuple for StackArray<'a> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.finish()
+    }
+}
+
+impl<'a> ser::SerializeTupleStruct for StackArray<'a> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.finish()
+    }
+}
+
+impl<'a> ser::SerializeTupleVariant for StackArray<'a> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.finish()
+    }
+}
+
+impl<'a> StackObject<'a> {
+    fn insert<T: Serialize + ?Sized>(&mut self, key: &str, value: &T) -> Result<(), Error> {
+        unsafe {
+            convert::push_str(self.rubtle.ctx, key);
+        }
+
+        value.serialize(StackSerializer { rubtle: self.rubtle })?;
+
+        unsafe {
+            ffi::duk_put_prop(self.rubtle.ctx, -3);
+        }
+
+        Ok(())
+    }
+
+    fn finish(self) -> Result<(), Error> {
+        if self.variant {
+            unsafe {
+                ffi::duk_put_prop(self.rubtle.ctx, -3);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> ser::SerializeMap for StackObject<'a> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+        let key = key_to_string(key)?;
+
+        unsafe {
+            convert::push_str(self.rubtle.ctx, &key);
+        }
+
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        value.serialize(StackSerializer { rubtle: self.rubtle })?;
+
+        unsafe {
+            ffi::duk_put_prop(self.rubtle.ctx, -3);
+        }
+
+        Ok(())
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.finish()
+    }
+}
+
+impl<'a> ser::SerializeStruct for StackObject<'a> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.insert(key, value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.finish()
+    }
+}
+
+impl<'a> ser::SerializeStructVariant for StackObject<'a> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.insert(key, value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.finish()
+    }
+}
@@ -0,0 +1,135 @@
+///
+/// @package Rubtle-Lib
+///
+/// @file Stash functions
+/// @copyright 2020-present Christoph Kappel <christoph@unexist.dev>
+/// @version $Id$
+///
+/// This program can be distributed under the terms of the GNU GPLv2.
+/// See the file LICENSE for details.
+//
+use std::rc::Weak;
+
+use crate::heap::HeapData;
+use crate::Error;
+
+/// Reference to a JS value pinned in the heap stash
+pub(crate) struct StashRef {
+    ctx: *mut ffi::duk_context,
+    slot: u64,
+    alive: Weak<()>,
+}
+
+impl StashRef {
+    ///
+    /// Pop value from top of the stack and pin it in the heap stash
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - Duktape context
+    ///
+    /// # Returns
+    ///
+    /// A new `StashRef` that releases the value on drop
+    ///
+
+    pub(crate) unsafe fn pop(ctx: *mut ffi::duk_context) -> StashRef {
+        let heap = HeapData::from_ctx(ctx);
+        let slot = heap.next_ref();
+
+        ffi::duk_require_stack(ctx, 2);
+        ffi::duk_push_heap_stash(ctx);
+        ffi::duk_swap_top(ctx, -2);
+        ffi::duk_push_number(ctx, slot as f64);
+        ffi::duk_swap_top(ctx, -2);
+        ffi::duk_put_prop(ctx, -3);
+        ffi::duk_pop(ctx);
+
+        StashRef {
+            ctx,
+            slot,
+            alive: heap.watch(),
+        }
+    }
+
+    ///
+    /// Push pinned value onto the stack
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - Duktape context to push onto; must share the heap
+    ///
+
+    pub(crate) unsafe fn push(&self, ctx: *mut ffi::duk_context) -> Result<(), Error> {
+        self.check(ctx)?;
+
+        ffi::duk_require_stack(ctx, 2);
+        ffi::duk_push_heap_stash(ctx);
+        ffi::duk_push_number(ctx, self.slot as f64);
+        ffi::duk_get_prop(ctx, -2);
+        ffi::duk_remove(ctx, -2);
+
+        Ok(())
+    }
+
+    ///
+    /// Check whether reference can be used with given context
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - Duktape context to use the reference with
+    ///
+    /// # Returns
+    ///
+    /// Either nothing or `Error::Heap` for dead or foreign heaps
+    ///
+
+    pub(crate) fn check(&self, ctx: *mut ffi::duk_context) -> Result<(), Error> {
+        if !self.is_alive() {
+            Err(Error::Heap("Heap of handle is gone"))
+        } else if self.ctx != ctx {
+            Err(Error::Heap("Handle belongs to another heap"))
+        } else {
+            Ok(())
+        }
+    }
+
+    ///
+    /// Return context the reference belongs to
+    ///
+    /// # Returns
+    ///
+    /// Duktape context
+    ///
+
+    pub(crate) fn ctx(&self) -> *mut ffi::duk_context {
+        self.ctx
+    }
+
+    ///
+    /// Check whether the heap of the reference is still alive
+    ///
+    /// # Returns
+    ///
+    /// `true` if the heap is alive; otherwise `false`
+    ///
+
+    pub(crate) fn is_alive(&self) -> bool {
+        self.alive.upgrade().is_some()
+    }
+}
+
+impl Drop for StashRef {
+    fn drop(&mut self) {
+        /* Heap takes care of everything when it is gone */
+        if self.is_alive() {
+            unsafe {
+                ffi::duk_require_stack(self.ctx, 2);
+                ffi::duk_push_heap_stash(self.ctx);
+                ffi::duk_push_number(self.ctx, self.slot as f64);
+                ffi::duk_del_prop(self.ctx, -2);
+                ffi::duk_pop(self.ctx);
+            }
+        }
+    }
+}
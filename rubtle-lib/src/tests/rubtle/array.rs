@@ -65,7 +65,7 @@ fn get_global_array_value_bool() {
         r#"
         var rubtle = [true, false];
     "#,
-    );
+    ).unwrap();
 
     let rval = rubtle.get_global_value("rubtle").unwrap();
 
@@ -83,7 +83,7 @@ fn get_global_array_value_i32() {
         r#"
         var rubtle = [1, 2];
     "#,
-    );
+    ).unwrap();
 
     let rval = rubtle.get_global_value("rubtle").unwrap();
 
@@ -101,7 +101,7 @@ fn get_global_array_value_f64() {
         r#"
         var rubtle = [1.0, 2.0];
     "#,
-    );
+    ).unwrap();
 
     let rval = rubtle.get_global_value("rubtle").unwrap();
 
@@ -119,7 +119,7 @@ fn get_global_array_value_str() {
         r#"
         var rubtle = ["rubtle", "rubtle"];
     "#,
-    );
+    ).unwrap();
 
     let rval = rubtle.get_global_value("rubtle").unwrap();
 
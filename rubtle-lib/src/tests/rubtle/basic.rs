@@ -62,6 +62,19 @@ fn push_and_pop_none_value() {
     assert_eq!(rval, rval2);
 }
 
+#[test]
+fn push_and_pop_null_value() {
+    let rubtle = Rubtle::new();
+
+    rubtle.push_value(&Value::Null);
+
+    assert_eq!(Value::from(true), rubtle.eval("true").unwrap());
+    assert_eq!(Value::Null, rubtle.pop_value().unwrap());
+
+    assert_eq!(Value::Null, rubtle.eval("null").unwrap());
+    assert_eq!(Value::None, rubtle.eval("undefined").unwrap());
+}
+
 ///
 /// Global
 ///
@@ -98,7 +111,7 @@ fn get_global_bool_value() {
         r#"
         var rubtle = true;
     "#,
-    );
+    ).unwrap();
 
     let rval = rubtle.get_global_value("rubtle").unwrap();
     let rval2 = Value::from(true);
@@ -114,7 +127,7 @@ fn get_global_number_value_i32() {
         r#"
         var rubtle = 4;
     "#,
-    );
+    ).unwrap();
 
     let rval = rubtle.get_global_value("rubtle").unwrap();
     let rval2 = Value::from(4);
@@ -130,7 +143,7 @@ fn get_global_number_value_f64() {
         r#"
         var rubtle = 4.0;
     "#,
-    );
+    ).unwrap();
 
     let rval = rubtle.get_global_value("rubtle").unwrap();
     let rval2 = Value::from(4.0);
@@ -146,7 +159,7 @@ fn get_global_string_value() {
         r#"
         var rubtle = 'test';
     "#,
-    );
+    ).unwrap();
 
     let rval = rubtle.get_global_value("rubtle").unwrap();
     let rval2 = Value::from("test");
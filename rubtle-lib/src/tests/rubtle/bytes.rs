@@ -0,0 +1,147 @@
+///
+/// @package Rubtle-Lib
+///
+/// @file Rubtle tests - bytes
+/// @copyright 2020-present Christoph Kappel <christoph@unexist.dev>
+/// @version $Id$
+///
+/// This program can be distributed under the terms of the GNU GPLv2.
+/// See the file LICENSE for details.
+///
+use crate::{BufferType, Rubtle, Value};
+
+///
+/// Binary data
+///
+
+#[test]
+fn push_and_pop_bytes_value() {
+    let rubtle = Rubtle::new();
+
+    let rval = Value::from(vec![0u8, 1, 254, 255]);
+
+    rubtle.push_value(&rval);
+    let rval2 = rubtle.pop_value().unwrap();
+
+    assert_eq!(rval, rval2);
+}
+
+#[test]
+fn push_and_pop_empty_bytes_value() {
+    let rubtle = Rubtle::new();
+
+    let rval = Value::from(Vec::<u8>::new());
+
+    rubtle.push_value(&rval);
+
+    assert_eq!(rval, rubtle.pop_value().unwrap());
+}
+
+#[test]
+fn set_global_bytes_value_with_buffer_types() {
+    let rubtle = Rubtle::new();
+
+    let types = [
+        (BufferType::Uint8Array, "rubtle instanceof Uint8Array"),
+        (BufferType::ArrayBuffer, "rubtle instanceof ArrayBuffer"),
+        (BufferType::DataView, "rubtle instanceof DataView"),
+        (BufferType::NodeBuffer, "Buffer.isBuffer(rubtle)"),
+        (BufferType::Plain, "rubtle === Uint8Array.plainOf(rubtle)"),
+    ];
+
+    for (buffer_type, check) in types.iter() {
+        rubtle.set_buffer_type(*buffer_type);
+        rubtle.set_global_value("rubtle", &Value::from(vec![1u8, 2, 3]));
+
+        assert_eq!(Value::from(true), rubtle.eval(check).unwrap(), "{:?}", buffer_type);
+        assert_eq!(
+            Value::from(vec![1u8, 2, 3]),
+            rubtle.get_global_value("rubtle").unwrap()
+        );
+    }
+}
+
+#[test]
+fn push_bytes_with_buffer_type() {
+    let rubtle = Rubtle::new();
+
+    rubtle.push_bytes_as(&[1, 2, 3], BufferType::DataView);
+
+    let obj = rubtle.pop_js_object().unwrap();
+
+    rubtle.set_global_value("rubtle", &obj);
+
+    assert_eq!(Value::from(true), rubtle.eval("rubtle instanceof DataView").unwrap());
+
+    /* Heap default must be untouched */
+    rubtle.set_global_value("rubtle", &Value::from(vec![1u8, 2, 3]));
+
+    assert_eq!(Value::from(true), rubtle.eval("rubtle instanceof Uint8Array").unwrap());
+}
+
+#[test]
+fn get_global_bytes_value_from_views() {
+    let rubtle = Rubtle::new();
+
+    rubtle.eval(
+        r#"
+        var buf = new ArrayBuffer(6);
+        var ary = new Uint8Array(buf);
+
+        for (var i = 0; i < ary.length; i++) {
+            ary[i] = i;
+        }
+
+        var view = new DataView(buf, 2, 3);
+        var sub = ary.subarray(1, 3);
+        var node = new Buffer('rubtle');
+        var plain = Uint8Array.plainOf(ary);
+        var floats = new Float32Array(2);
+    "#,
+    ).unwrap();
+
+    assert_eq!(
+        Value::from(vec![0u8, 1, 2, 3, 4, 5]),
+        rubtle.get_global_value("buf").unwrap()
+    );
+    assert_eq!(
+        Value::from(vec![2u8, 3, 4]),
+        rubtle.get_global_value("view").unwrap()
+    );
+    assert_eq!(
+        Value::from(vec![1u8, 2]),
+        rubtle.get_global_value("sub").unwrap()
+    );
+    assert_eq!(
+        Value::from("rubtle".as_bytes()),
+        rubtle.get_global_value("node").unwrap()
+    );
+    assert_eq!(
+        Value::from(vec![0u8, 1, 2, 3, 4, 5]),
+        rubtle.get_global_value("plain").unwrap()
+    );
+
+    /* Typed arrays with wider elements stay objects */
+    assert!(rubtle.get_global_value("floats").unwrap().is_object());
+}
+
+#[test]
+fn modify_bytes_in_script() {
+    let rubtle = Rubtle::new();
+
+    rubtle.set_global_value("frame", &Value::from(vec![1u8, 2, 3]));
+
+    let rval = rubtle.eval(
+        r#"
+        var out = new Uint8Array(frame.length);
+
+        for (var i = 0; i < frame.length; i++) {
+            out[i] = frame[i] * 2;
+        }
+
+        out;
+    "#,
+    ).unwrap();
+
+    assert_eq!(Value::from(vec![2u8, 4, 6]), rval);
+}
@@ -0,0 +1,114 @@
+///
+/// @package Rubtle-Lib
+///
+/// @file Rubtle tests - cbor
+/// @copyright 2020-present Christoph Kappel <christoph@unexist.dev>
+/// @version $Id$
+///
+/// This program can be distributed under the terms of the GNU GPLv2.
+/// See the file LICENSE for details.
+///
+use crate::{Error, ObjectMap, Rubtle, Value};
+
+///
+/// CBOR
+///
+
+#[test]
+fn cbor_encode_and_decode_object() {
+    let rubtle = Rubtle::new();
+
+    let mut hash = ObjectMap::new();
+
+    hash.insert("name", Value::from("rubtle"));
+    hash.insert("list", Value::from(&vec![1, 2]));
+    hash.insert("flag", Value::from(true));
+    hash.insert("half", Value::from(0.5));
+
+    let rval = Value::Object(hash);
+
+    let bytes = rubtle.cbor_encode(&rval).unwrap();
+
+    assert_eq!(rval, rubtle.cbor_decode(&bytes).unwrap());
+
+    unsafe {
+        assert_eq!(0, ffi::duk_get_top(rubtle.ctx));
+    }
+}
+
+#[test]
+fn cbor_encode_known_bytes() {
+    let rubtle = Rubtle::new();
+
+    assert_eq!(vec![0xf6], rubtle.cbor_encode(&Value::Null).unwrap());
+    assert_eq!(vec![0xf7], rubtle.cbor_encode(&Value::None).unwrap());
+    assert_eq!(
+        vec![0x82, 0x01, 0xf5],
+        rubtle
+            .cbor_encode(&Value::Array(vec![Value::from(1), Value::from(true)]))
+            .unwrap()
+    );
+}
+
+#[test]
+fn cbor_decode_bytes() {
+    let rubtle = Rubtle::new();
+
+    /* Byte string of two bytes */
+    assert_eq!(
+        Value::from(vec![1u8, 2]),
+        rubtle.cbor_decode(&[0x42, 0x01, 0x02]).unwrap()
+    );
+}
+
+#[test]
+fn cbor_interop_with_js() {
+    let rubtle = Rubtle::new();
+
+    let bytes = rubtle.cbor_encode(&Value::from("rubtle")).unwrap();
+
+    rubtle.set_global_value("rubtle", &Value::from(bytes));
+
+    assert_eq!(Value::from("rubtle"), rubtle.eval("CBOR.decode(rubtle)").unwrap());
+
+    let rval = rubtle.eval("new Uint8Array(CBOR.encode({ a: [1, 'b'] }))").unwrap();
+
+    match rubtle.cbor_decode(rval.as_bytes().unwrap()).unwrap() {
+        Value::Object(hash) => assert_eq!(
+            Value::Array(vec![Value::from(1), Value::from("b")]),
+            hash["a"]
+        ),
+        _ => panic!("Expected object"),
+    }
+}
+
+#[test]
+fn cbor_decode_invalid() {
+    let rubtle = Rubtle::new();
+
+    match rubtle.cbor_decode(&[0x82, 0x01]) {
+        Err(Error::Exception(_)) => (),
+        rval => panic!("Expected exception, got {:?}", rval),
+    }
+
+    unsafe {
+        assert_eq!(0, ffi::duk_get_top(rubtle.ctx));
+    }
+}
+
+#[test]
+fn cbor_encode_handle_of_other_rubtle_fails() {
+    let rubtle = Rubtle::new();
+    let other = Rubtle::new();
+
+    let rval = rubtle.eval("[function() {}]").unwrap();
+
+    assert_eq!(
+        Err(Error::Heap("Handle belongs to another heap")),
+        other.cbor_encode(&rval)
+    );
+
+    unsafe {
+        assert_eq!(0, ffi::duk_get_top(other.ctx));
+    }
+}
@@ -0,0 +1,251 @@
+///
+/// @package Rubtle-Lib
+///
+/// @file Rubtle tests - conversion
+/// @copyright 2020-present Christoph Kappel <christoph@unexist.dev>
+/// @version $Id$
+///
+/// This program can be distributed under the terms of the GNU GPLv2.
+/// See the file LICENSE for details.
+///
+use crate::{ConversionOptions, Error, FunctionHandling, Rubtle, Value};
+
+fn keys_of(rubtle: &Rubtle, name: &str, options: &ConversionOptions) -> Vec<String> {
+    match rubtle.get_global_value_with(name, options).unwrap() {
+        Value::Object(hash) => hash.keys().map(String::from).collect(),
+        _ => panic!("Expected object"),
+    }
+}
+
+///
+/// Keys
+///
+
+#[test]
+fn convert_own_properties_only() {
+    let rubtle = Rubtle::new();
+
+    rubtle
+        .eval("var rubtle = Object.create({ a: 1 }); rubtle.b = 2;")
+        .unwrap();
+
+    assert_eq!(vec!["b", "a"], keys_of(&rubtle, "rubtle", &ConversionOptions::new()));
+    assert_eq!(
+        vec!["b"],
+        keys_of(&rubtle, "rubtle", &ConversionOptions::new().with_own_only(true))
+    );
+}
+
+#[test]
+fn convert_symbol_keys() {
+    let rubtle = Rubtle::new();
+
+    rubtle
+        .eval("var rubtle = { a: 1 }; rubtle[Symbol('b')] = 2; rubtle[Symbol.for('c')] = 3;")
+        .unwrap();
+
+    assert_eq!(vec!["a"], keys_of(&rubtle, "rubtle", &ConversionOptions::new()));
+
+    let rval = rubtle
+        .get_global_value_with("rubtle", &ConversionOptions::new().with_symbols(true))
+        .unwrap();
+
+    match rval {
+        Value::Object(hash) => {
+            assert_eq!(Value::from(2), hash["Symbol(b)"]);
+            assert_eq!(Value::from(3), hash["Symbol(c)"]);
+        }
+        _ => panic!("Expected object"),
+    }
+}
+
+#[test]
+fn convert_non_enumerable_properties() {
+    let rubtle = Rubtle::new();
+
+    rubtle
+        .eval("var rubtle = { a: 1 }; Object.defineProperty(rubtle, 'b', { value: 2 });")
+        .unwrap();
+
+    let options = ConversionOptions::new()
+        .with_own_only(true)
+        .with_non_enumerable(true);
+
+    assert_eq!(vec!["a"], keys_of(&rubtle, "rubtle", &ConversionOptions::new()));
+    assert_eq!(vec!["a", "b"], keys_of(&rubtle, "rubtle", &options));
+}
+
+///
+/// Accessors
+///
+
+#[test]
+fn skip_accessor_properties() {
+    let rubtle = Rubtle::new();
+
+    rubtle
+        .eval(
+            r#"
+        var calls = 0;
+        var proto = { get b() { calls++; return 2; }, c: 3 };
+        var rubtle = Object.create(proto);
+
+        rubtle.a = 1;
+        Object.defineProperty(rubtle, 'd', {
+            get: function() { calls++; return 4; },
+            enumerable: true
+        });
+        calls = 0;
+    "#,
+        )
+        .unwrap();
+
+    let options = ConversionOptions::new().with_accessors(false);
+
+    assert_eq!(vec!["a", "c"], keys_of(&rubtle, "rubtle", &options));
+    assert_eq!(Value::from(0), rubtle.get_global_value("calls").unwrap());
+
+    assert_eq!(
+        vec!["a", "d", "b", "c"],
+        keys_of(&rubtle, "rubtle", &ConversionOptions::new())
+    );
+    assert_eq!(Value::from(2), rubtle.get_global_value("calls").unwrap());
+
+    unsafe {
+        assert_eq!(0, ffi::duk_get_top(rubtle.ctx));
+    }
+}
+
+#[test]
+fn keep_accessor_elements_as_null() {
+    let rubtle = Rubtle::new();
+
+    rubtle
+        .eval(
+            r#"
+        var rubtle = [1, 2];
+
+        Object.defineProperty(rubtle, 1, { get: function() { return 3; } });
+    "#,
+        )
+        .unwrap();
+
+    let options = ConversionOptions::new().with_accessors(false);
+
+    assert_eq!(
+        Ok(Value::Array(vec![Value::from(1), Value::Null])),
+        rubtle.get_global_value_with("rubtle", &options)
+    );
+}
+
+#[test]
+fn skip_accessor_properties_with_inherited_get() {
+    let rubtle = Rubtle::new();
+
+    rubtle
+        .eval(
+            r#"
+        Object.defineProperty(Object.prototype, 'get', { value: 1, writable: true });
+
+        var rubtle = { a: 1 };
+
+        Object.defineProperty(rubtle, 'b', {
+            get: function() { return 2; },
+            enumerable: true
+        });
+    "#,
+        )
+        .unwrap();
+
+    let options = ConversionOptions::new().with_accessors(false);
+
+    assert_eq!(vec!["a"], keys_of(&rubtle, "rubtle", &options));
+}
+
+#[test]
+fn get_object_with_throwing_getter() {
+    let rubtle = Rubtle::new();
+
+    rubtle
+        .eval("var rubtle = { get a() { throw new Error('rubtle'); } }; void 0;")
+        .unwrap();
+
+    match rubtle.get_global_value_with("rubtle", &ConversionOptions::new()) {
+        Err(Error::Exception(err)) => assert_eq!("rubtle", err.message),
+        _ => panic!("Expected exception"),
+    }
+
+    unsafe {
+        assert_eq!(0, ffi::duk_get_top(rubtle.ctx));
+    }
+}
+
+#[test]
+fn get_proxy_with_throwing_trap() {
+    let rubtle = Rubtle::new();
+
+    rubtle
+        .eval(
+            r#"
+        var rubtle = new Proxy({ a: 1 }, {
+            ownKeys: function() { throw new Error('rubtle'); }
+        });
+        void 0;
+    "#,
+        )
+        .unwrap();
+
+    match rubtle.get_global_value_with("rubtle", &ConversionOptions::new()) {
+        Err(Error::Exception(err)) => assert_eq!("rubtle", err.message),
+        _ => panic!("Expected exception"),
+    }
+
+    unsafe {
+        assert_eq!(0, ffi::duk_get_top(rubtle.ctx));
+    }
+}
+
+///
+/// Functions
+///
+
+#[test]
+fn handle_nested_functions() {
+    let rubtle = Rubtle::new();
+
+    rubtle
+        .eval("var rubtle = { a: 1, f: function() {}, b: [function() {}, 2] };")
+        .unwrap();
+
+    let rval = rubtle
+        .get_global_value_with(
+            "rubtle",
+            &ConversionOptions::new().with_functions(FunctionHandling::Skip),
+        )
+        .unwrap();
+
+    match rval {
+        Value::Object(ref hash) => {
+            assert!(!hash.contains_key("f"));
+            assert_eq!(Value::Array(vec![Value::Null, Value::from(2)]), hash["b"]);
+        }
+        _ => panic!("Expected object"),
+    }
+
+    match rubtle.get_global_value_with(
+        "rubtle",
+        &ConversionOptions::new().with_functions(FunctionHandling::Error),
+    ) {
+        Err(Error::Conversion { actual: "function", .. }) => (),
+        rval => panic!("Expected conversion error, got {:?}", rval),
+    }
+
+    unsafe {
+        assert_eq!(0, ffi::duk_get_top(rubtle.ctx));
+    }
+
+    match rubtle.get_global_value_with("rubtle", &ConversionOptions::new()).unwrap() {
+        Value::Object(ref hash) => assert!(hash["f"].is_function()),
+        _ => panic!("Expected object"),
+    }
+}
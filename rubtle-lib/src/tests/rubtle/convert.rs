@@ -0,0 +1,234 @@
+///
+/// @package Rubtle-Lib
+///
+/// @file Rubtle tests - convert
+/// @copyright 2020-present Christoph Kappel <christoph@unexist.dev>
+/// @version $Id$
+///
+/// This program can be distributed under the terms of the GNU GPLv2.
+/// See the file LICENSE for details.
+///
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+
+use crate::{CallbackResult, Error, FromJs, Invocation, Rubtle, ToJs, Value};
+
+#[derive(Debug, PartialEq)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+impl ToJs for Point {
+    fn to_js(&self, rubtle: &Rubtle) {
+        rubtle.push_object_fields(&[("x", &self.x), ("y", &self.y)]);
+    }
+}
+
+impl FromJs for Point {
+    fn from_js(rubtle: &Rubtle, idx: i32) -> Result<Point, Error> {
+        Ok(Point {
+            x: rubtle.get_object_field(idx, "x")?,
+            y: rubtle.get_object_field(idx, "y")?,
+        })
+    }
+}
+
+///
+/// Convert
+///
+
+#[test]
+fn push_and_pop_nested_vec() {
+    let rubtle = Rubtle::new();
+
+    let rval = vec![vec![1, 2], vec![], vec![3]];
+
+    rubtle.push(&rval);
+    let rval2: Vec<Vec<i32>> = rubtle.pop().unwrap();
+
+    assert_eq!(rval, rval2);
+}
+
+#[test]
+fn push_and_pop_option() {
+    let rubtle = Rubtle::new();
+
+    rubtle.push(&vec![Some("rubtle"), None]);
+
+    assert_eq!(
+        vec![Some(String::from("rubtle")), None],
+        rubtle.pop::<Vec<Option<String>>>().unwrap()
+    );
+}
+
+#[test]
+fn push_and_pop_tuple() {
+    let rubtle = Rubtle::new();
+
+    rubtle.push(&(1, "rubtle", true));
+
+    assert_eq!(
+        (1u8, String::from("rubtle"), true),
+        rubtle.pop::<(u8, String, bool)>().unwrap()
+    );
+}
+
+#[test]
+fn push_and_pop_maps() {
+    let rubtle = Rubtle::new();
+
+    let mut map = BTreeMap::new();
+
+    map.insert(String::from("a"), vec![1.5]);
+    map.insert(String::from("b"), vec![]);
+
+    rubtle.push(&map);
+    let map2: BTreeMap<String, Vec<f64>> = rubtle.pop().unwrap();
+
+    assert_eq!(map, map2);
+
+    let mut map = HashMap::new();
+
+    map.insert("a", Value::from(true));
+    map.insert("b", Value::from("rubtle"));
+
+    rubtle.push(&map);
+    let map2: HashMap<String, Value> = rubtle.pop().unwrap();
+
+    assert_eq!(Some(&Value::from("rubtle")), map2.get("b"));
+    assert_eq!(2, map2.len());
+}
+
+#[test]
+fn push_and_pop_user_type() {
+    let rubtle = Rubtle::new();
+
+    rubtle.set_global_value("rubtle", &vec![Point { x: 1, y: 2 }]);
+
+    assert_eq!(Value::from(2), rubtle.eval("rubtle[0].y").unwrap());
+
+    rubtle.eval("rubtle.push({ x: 3, y: 4 });").unwrap();
+
+    assert_eq!(
+        vec![Point { x: 1, y: 2 }, Point { x: 3, y: 4 }],
+        rubtle.get_global::<Vec<Point>>("rubtle").unwrap()
+    );
+}
+
+#[test]
+fn pop_with_wrong_type() {
+    let rubtle = Rubtle::new();
+
+    rubtle.push(&vec![1, 2]);
+
+    assert_eq!(
+        Err(Error::Conversion {
+            expected: "string",
+            actual: "number",
+        }),
+        rubtle.pop::<Vec<String>>()
+    );
+
+    rubtle.push(&(1, 2));
+
+    assert!(rubtle.pop::<(i32, i32, i32)>().is_err());
+
+    rubtle.push(&300);
+
+    assert!(rubtle.pop::<u8>().is_err());
+
+    /* Stack is left balanced after errors */
+    unsafe {
+        assert_eq!(0, ffi::duk_get_top(rubtle.ctx));
+    }
+}
+
+#[test]
+fn get_with_throwing_getter() {
+    let rubtle = Rubtle::new();
+
+    rubtle.eval(
+        r#"
+        void Object.defineProperty(this, 'broken', {
+            get: function() { throw new TypeError('rubtle'); }
+        });
+
+        var point = { x: 1, get y() { throw new RangeError('rubtle'); } };
+    "#,
+    ).unwrap();
+
+    match rubtle.get_global::<i32>("broken") {
+        Err(Error::Exception(js)) => assert_eq!("TypeError", js.name),
+        rval => panic!("Expected exception, got {:?}", rval),
+    }
+
+    match rubtle.get_global::<Point>("point") {
+        Err(Error::Exception(js)) => assert_eq!("RangeError", js.name),
+        rval => panic!("Expected exception, got {:?}", rval),
+    }
+
+    assert_eq!(None, rubtle.get_global_value("broken"));
+
+    unsafe {
+        assert_eq!(0, ffi::duk_get_top(rubtle.ctx));
+    }
+}
+
+#[test]
+fn get_collections_with_throwing_getter() {
+    let rubtle = Rubtle::new();
+
+    rubtle.eval(
+        r#"
+        var hash = { a: 1, get b() { throw new TypeError('rubtle'); } };
+        var list = [1];
+
+        Object.defineProperty(list, 1, {
+            get: function() { throw new RangeError('rubtle'); }
+        });
+
+        void 0;
+    "#,
+    ).unwrap();
+
+    match rubtle.get_global::<HashMap<String, i32>>("hash") {
+        Err(Error::Exception(js)) => assert_eq!("TypeError", js.name),
+        rval => panic!("Expected exception, got {:?}", rval),
+    }
+
+    match rubtle.get_global::<BTreeMap<String, i32>>("hash") {
+        Err(Error::Exception(js)) => assert_eq!("TypeError", js.name),
+        rval => panic!("Expected exception, got {:?}", rval),
+    }
+
+    match rubtle.get_global::<Vec<i32>>("list") {
+        Err(Error::Exception(js)) => assert_eq!("RangeError", js.name),
+        rval => panic!("Expected exception, got {:?}", rval),
+    }
+
+    match rubtle.get_global::<(i32, i32)>("list") {
+        Err(Error::Exception(js)) => assert_eq!("RangeError", js.name),
+        rval => panic!("Expected exception, got {:?}", rval),
+    }
+
+    unsafe {
+        assert_eq!(0, ffi::duk_get_top(rubtle.ctx));
+    }
+}
+
+#[test]
+fn return_to_js_type_from_callback() {
+    let rubtle = Rubtle::new();
+
+    rubtle.set_global_function("point", |inv: Invocation<i8>| -> CallbackResult<Point> {
+        let args = inv.args.unwrap();
+
+        Ok(Point {
+            x: i32::try_from(args[0].clone())?,
+            y: 0,
+        })
+    });
+
+    assert_eq!(Value::from(4), rubtle.eval("point(4).x").unwrap());
+}
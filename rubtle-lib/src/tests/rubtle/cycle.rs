@@ -0,0 +1,220 @@
+///
+/// @package Rubtle-Lib
+///
+/// @file Rubtle tests - cycle
+/// @copyright 2020-present Christoph Kappel <christoph@unexist.dev>
+/// @version $Id$
+///
+/// This program can be distributed under the terms of the GNU GPLv2.
+/// See the file LICENSE for details.
+///
+use crate::{CallbackResult, ConversionOptions, CycleHandling, Error, Limit, Rubtle, Value};
+
+///
+/// Cycles
+///
+
+#[test]
+fn eval_cyclic_object_fails() {
+    let rubtle = Rubtle::new();
+
+    match rubtle.eval("var a = {}; a.self = a; a") {
+        Err(Error::Conversion { .. }) => (),
+        rval => panic!("Expected conversion error, got {:?}", rval),
+    }
+
+    /* Stack is left balanced after errors */
+    unsafe {
+        assert_eq!(0, ffi::duk_get_top(rubtle.ctx));
+    }
+}
+
+#[test]
+fn pop_cyclic_array_fails() {
+    let rubtle = Rubtle::new();
+
+    rubtle.eval("var rubtle = [1]; rubtle.push([rubtle]);").unwrap();
+
+    assert_eq!(None, rubtle.get_global_value("rubtle"));
+    assert!(rubtle.get_global_value_with("rubtle", &ConversionOptions::new()).is_err());
+
+    unsafe {
+        assert_eq!(0, ffi::duk_get_top(rubtle.ctx));
+    }
+}
+
+#[test]
+fn call_function_with_cyclic_argument_fails() {
+    let rubtle = Rubtle::new();
+
+    rubtle.set_global_function("rubtle", |inv| -> CallbackResult<Value> {
+        Ok(inv.args.unwrap().pop().unwrap_or(Value::None))
+    });
+
+    assert_eq!(
+        Value::from(2),
+        rubtle.eval("var a = {}; a.self = 1; rubtle(a, 2);").unwrap()
+    );
+
+    match rubtle.eval("a.self = a; rubtle(a, 2);") {
+        Err(Error::Exception(_)) => (),
+        rval => panic!("Expected exception, got {:?}", rval),
+    }
+
+    unsafe {
+        assert_eq!(0, ffi::duk_get_top(rubtle.ctx));
+    }
+}
+
+#[test]
+fn copy_shared_object() {
+    let rubtle = Rubtle::new();
+
+    rubtle
+        .eval("var shared = { a: 1 }; var rubtle = { left: shared, right: shared };")
+        .unwrap();
+
+    let rval = rubtle
+        .get_global_value_with("rubtle", &ConversionOptions::new())
+        .unwrap();
+
+    match rval {
+        Value::Object(ref hash) => assert_eq!(hash["left"], hash["right"]),
+        _ => panic!("Expected object"),
+    }
+}
+
+#[test]
+fn keep_cyclic_object_as_reference() {
+    let rubtle = Rubtle::new();
+    let options = ConversionOptions::new().with_cycles(CycleHandling::Reference);
+
+    rubtle
+        .eval("var rubtle = { a: 1 }; void (rubtle.self = rubtle);")
+        .unwrap();
+
+    let rval = rubtle.get_global_value_with("rubtle", &options).unwrap();
+
+    let hash = match rval {
+        Value::Object(hash) => hash,
+        _ => panic!("Expected object"),
+    };
+
+    assert_eq!(Value::from(1), hash["a"]);
+    assert!(hash["self"].is_ref());
+
+    /* Reference pushes back the same object */
+    rubtle.set_global_value("rubtle2", &hash["self"]);
+
+    assert_eq!(Value::from(true), rubtle.eval("rubtle === rubtle2").unwrap());
+}
+
+#[test]
+fn keep_shared_object_as_reference() {
+    let rubtle = Rubtle::new();
+    let options = ConversionOptions::new().with_cycles(CycleHandling::Reference);
+
+    rubtle
+        .eval("var shared = {}; var rubtle = [shared, shared];")
+        .unwrap();
+
+    match rubtle.get_global_value_with("rubtle", &options).unwrap() {
+        Value::Array(vec) => {
+            assert_eq!(Value::Object(Default::default()), vec[0]);
+            assert!(vec[1].is_ref());
+        }
+        _ => panic!("Expected array"),
+    }
+}
+
+///
+/// Depth
+///
+
+#[test]
+fn exceed_max_depth() {
+    let rubtle = Rubtle::new();
+    let options = ConversionOptions::new().with_max_depth(2);
+
+    rubtle.eval("var rubtle = [[[]]];").unwrap();
+
+    assert_eq!(
+        Err(Error::Limit(Limit::Depth)),
+        rubtle.get_global_value_with("rubtle", &options)
+    );
+
+    unsafe {
+        assert_eq!(0, ffi::duk_get_top(rubtle.ctx));
+    }
+
+    assert!(rubtle
+        .get_global_value_with("rubtle", &options.with_max_depth(3))
+        .is_ok());
+}
+
+#[test]
+fn pop_deeply_nested_array() {
+    let rubtle = Rubtle::new();
+
+    rubtle
+        .eval("var rubtle = []; for (var i = 0; i < 250; i++) void (rubtle = [rubtle]);")
+        .unwrap();
+
+    assert!(rubtle.get_global_value("rubtle").is_some());
+
+    unsafe {
+        assert_eq!(0, ffi::duk_get_top(rubtle.ctx));
+    }
+
+    rubtle
+        .eval("for (var i = 0; i < 10; i++) void (rubtle = [rubtle]);")
+        .unwrap();
+
+    assert_eq!(
+        Err(Error::Limit(Limit::Depth)),
+        rubtle.get_global_value_with("rubtle", &ConversionOptions::new())
+    );
+}
+
+///
+/// Nodes
+///
+
+#[test]
+fn exceed_max_nodes_with_shared_objects() {
+    let rubtle = Rubtle::new();
+
+    rubtle
+        .eval("var rubtle = []; for (var i = 0; i < 40; i++) void (rubtle = [rubtle, rubtle]);")
+        .unwrap();
+
+    assert_eq!(
+        Err(Error::Limit(Limit::Nodes)),
+        rubtle.get_global_value_with("rubtle", &ConversionOptions::new())
+    );
+
+    unsafe {
+        assert_eq!(0, ffi::duk_get_top(rubtle.ctx));
+    }
+
+    /* Shared objects are converted once when kept as reference */
+    let options = ConversionOptions::new().with_cycles(CycleHandling::Reference);
+
+    assert!(rubtle.get_global_value_with("rubtle", &options).is_ok());
+}
+
+#[test]
+fn stay_within_max_nodes() {
+    let rubtle = Rubtle::new();
+    let options = ConversionOptions::new().with_max_nodes(7);
+
+    rubtle
+        .eval("var rubtle = []; for (var i = 0; i < 2; i++) void (rubtle = [rubtle, rubtle]);")
+        .unwrap();
+
+    assert!(rubtle.get_global_value_with("rubtle", &options).is_ok());
+    assert_eq!(
+        Err(Error::Limit(Limit::Nodes)),
+        rubtle.get_global_value_with("rubtle", &options.with_max_nodes(6))
+    );
+}
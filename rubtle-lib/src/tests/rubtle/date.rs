@@ -0,0 +1,102 @@
+///
+/// @package Rubtle-Lib
+///
+/// @file Rubtle tests - date
+/// @copyright 2020-present Christoph Kappel <christoph@unexist.dev>
+/// @version $Id$
+///
+/// This program can be distributed under the terms of the GNU GPLv2.
+/// See the file LICENSE for details.
+///
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::{Rubtle, Value};
+
+///
+/// Dates
+///
+
+#[test]
+fn push_and_pop_date_value() {
+    let rubtle = Rubtle::new();
+
+    let rval = Value::from(UNIX_EPOCH + Duration::from_millis(1_600_000_000_123));
+
+    rubtle.push_value(&rval);
+    let rval2 = rubtle.pop_value().unwrap();
+
+    assert_eq!(rval, rval2);
+}
+
+#[test]
+fn push_date_value_before_epoch() {
+    let rubtle = Rubtle::new();
+
+    rubtle.set_global_value("rubtle", &Value::from(UNIX_EPOCH - Duration::from_millis(1500)));
+
+    assert_eq!(Value::from(-1500), rubtle.eval("rubtle.getTime()").unwrap());
+    assert_eq!(
+        Value::from(UNIX_EPOCH - Duration::from_millis(1500)),
+        rubtle.get_global_value("rubtle").unwrap()
+    );
+}
+
+#[test]
+fn push_date_value_with_sub_millis() {
+    let rubtle = Rubtle::new();
+
+    rubtle.set_global_value("rubtle", &Value::from(UNIX_EPOCH + Duration::from_micros(1999)));
+
+    assert_eq!(Value::from(1), rubtle.eval("rubtle.getTime()").unwrap());
+}
+
+#[test]
+fn push_date_value_with_replaced_date() {
+    let rubtle = Rubtle::new();
+
+    rubtle.eval("var RealDate = Date; Date = 1;").unwrap();
+    rubtle.set_global_value("rubtle", &Value::from(UNIX_EPOCH + Duration::from_millis(1500)));
+
+    assert_eq!(Value::from(true), rubtle.eval("rubtle instanceof RealDate").unwrap());
+    assert_eq!(Value::from(1500), rubtle.eval("rubtle.getTime()").unwrap());
+}
+
+#[test]
+fn get_global_date_value() {
+    let rubtle = Rubtle::new();
+
+    let rval = rubtle.eval("new Date(Date.UTC(2020, 0, 2, 3, 4, 5, 6))").unwrap();
+
+    assert!(rval.is_date());
+    assert_eq!(
+        Some(UNIX_EPOCH + Duration::from_millis(1_577_934_245_006)),
+        rval.as_date()
+    );
+
+    let now = SystemTime::now();
+    let rval = rubtle.eval("new Date()").unwrap();
+
+    assert!(now.duration_since(rval.as_date().unwrap()).map_or(true, |d| d.as_secs() < 5));
+}
+
+#[test]
+fn get_global_invalid_date_value() {
+    let rubtle = Rubtle::new();
+
+    assert_eq!(Value::Null, rubtle.eval("new Date(NaN)").unwrap());
+}
+
+#[test]
+fn get_global_object_with_date_value() {
+    let rubtle = Rubtle::new();
+
+    let rval = rubtle.eval("({ at: new Date(1000) })").unwrap();
+
+    match rval {
+        Value::Object(hash) => assert_eq!(
+            Some(&Value::from(UNIX_EPOCH + Duration::from_secs(1))),
+            hash.get("at")
+        ),
+        _ => panic!("Unexpected value {:?}", rval),
+    }
+}
@@ -8,7 +8,9 @@
 /// This program can be distributed under the terms of the GNU GPLv2.
 /// See the file LICENSE for details.
 ///
-use crate::Rubtle;
+use std::time::UNIX_EPOCH;
+
+use crate::{BufferType, Error, Rubtle, Value};
 
 ///
 /// Eval
@@ -22,5 +24,171 @@ fn evil_eval_test() {
         r#"
         var rubtle = 'rubtle';
     "#,
+    ).unwrap();
+}
+
+#[test]
+fn eval_returns_completion_value() {
+    let rubtle = Rubtle::new();
+
+    let rval = rubtle.eval(
+        r#"
+        var rubtle = 4;
+        rubtle * 2;
+    "#,
+    ).unwrap();
+
+    assert_eq!(Value::from(8), rval);
+}
+
+#[test]
+fn eval_returns_syntax_error() {
+    let rubtle = Rubtle::new();
+
+    let err = rubtle.eval(
+        r#"
+        var rubtle = ;
+    "#,
+    ).unwrap_err();
+
+    match err {
+        Error::Syntax(js) => {
+            assert_eq!("SyntaxError", js.name);
+            assert_eq!(Some(2), js.line_number);
+        }
+        _ => panic!("Expected syntax error"),
+    }
+}
+
+#[test]
+fn eval_returns_thrown_error() {
+    let rubtle = Rubtle::new();
+
+    let err = rubtle.eval(
+        r#"
+        var rubtle = 4;
+
+        throw new RangeError('rubtle');
+    "#,
+    ).unwrap_err();
+
+    match err {
+        Error::Exception(js) => {
+            assert_eq!("RangeError", js.name);
+            assert_eq!("rubtle", js.message);
+            assert_eq!(Some(4), js.line_number);
+            assert!(js.stack.is_some());
+        }
+        _ => panic!("Expected exception"),
+    }
+
+    /* Heap must still be usable */
+    assert_eq!(Value::from(2), rubtle.eval("1 + 1").unwrap());
+}
+
+#[test]
+fn eval_returns_runtime_syntax_error_as_exception() {
+    let rubtle = Rubtle::new();
+
+    let err = rubtle.eval(
+        r#"
+        JSON.parse('{');
+    "#,
+    ).unwrap_err();
+
+    match err {
+        Error::Exception(js) => assert_eq!("SyntaxError", js.name),
+        _ => panic!("Expected exception"),
+    }
+}
+
+#[test]
+fn eval_returns_thrown_value() {
+    let rubtle = Rubtle::new();
+
+    let err = rubtle.eval(
+        r#"
+        throw 'rubtle';
+    "#,
+    ).unwrap_err();
+
+    assert_eq!("rubtle", err.as_js_error().unwrap().message);
+}
+
+#[test]
+fn coerce_results_like_engine() {
+    let rubtle = Rubtle::new();
+
+    let scripts = [
+        "[1, [2, null], 'a', {}]",
+        "1e21",
+        "-1e-7",
+        "123.456",
+        "2 / 3",
+        "Math.pow(2, 70)",
+        "undefined",
+    ];
+
+    for script in scripts.iter() {
+        let rval = rubtle.eval(script).unwrap();
+
+        assert_eq!(
+            rubtle.eval(&format!("String({})", script)).unwrap(),
+            Value::from(rval.coerce_string().unwrap())
+        );
+        assert_eq!(
+            rubtle.eval(&format!("Boolean({})", script)).unwrap(),
+            Value::from(rval.coerce_boolean())
+        );
+    }
+
+    for strval in ["' 42 '", "'0x10'", "'1e3'", "'.5'", "'12px'", "''"].iter() {
+        let rval = rubtle.eval(strval).unwrap();
+
+        /* Compare as strings, since NaN never equals itself */
+        assert_eq!(
+            rubtle.eval(&format!("Number({})", strval)).unwrap().coerce_string(),
+            Value::from(rval.coerce_number().unwrap()).coerce_string()
+        );
+    }
+}
+
+#[test]
+fn coerce_string_in_heap() {
+    let rubtle = Rubtle::new();
+
+    let rval = Value::Array(vec![Value::Date(UNIX_EPOCH), Value::from(1)]);
+
+    assert_eq!(None, rval.coerce_string());
+    assert_eq!(
+        rubtle.eval("String([new Date(0), 1])").unwrap(),
+        Value::from(rubtle.coerce_string(&rval).unwrap())
     );
-}
\ No newline at end of file
+
+    let func = rubtle.eval("(function rubtle() {})").unwrap();
+
+    assert_eq!(
+        rubtle.eval("String(function rubtle() {})").unwrap(),
+        Value::from(rubtle.coerce_string(&func).unwrap())
+    );
+
+    /* Buffers depend on the buffer type of the heap */
+    let bytes = Value::from(vec![1u8]);
+
+    rubtle.set_buffer_type(BufferType::ArrayBuffer);
+
+    assert_eq!("[object ArrayBuffer]", rubtle.coerce_string(&bytes).unwrap());
+
+    let rval = rubtle
+        .eval("({ toString: function() { throw new Error('rubtle'); } })")
+        .unwrap();
+
+    match rubtle.coerce_string(&rval) {
+        Err(Error::Exception(err)) => assert_eq!("rubtle", err.message),
+        _ => panic!("Expected exception"),
+    }
+
+    unsafe {
+        assert_eq!(0, ffi::duk_get_top(rubtle.ctx));
+    }
+}
@@ -0,0 +1,128 @@
+///
+/// @package Rubtle-Lib
+///
+/// @file Rubtle tests - external
+/// @copyright 2020-present Christoph Kappel <christoph@unexist.dev>
+/// @version $Id$
+///
+/// This program can be distributed under the terms of the GNU GPLv2.
+/// See the file LICENSE for details.
+///
+use crate::{BufferType, Rubtle, Value};
+
+use std::panic::{self, AssertUnwindSafe};
+
+///
+/// External buffers
+///
+
+#[test]
+fn read_external_buffer_in_place() {
+    let rubtle = Rubtle::new();
+    let mut data: Vec<u8> = (0..100).collect();
+
+    let rval = rubtle.with_external_buffer(&mut data, |buf| {
+        rubtle.set_global_value("rubtle", &buf.to_value());
+
+        rubtle.eval(
+            r#"
+            var sum = 0;
+
+            for (var i = 0; i < rubtle.length; i++) {
+                sum += rubtle[i];
+            }
+
+            sum;
+        "#,
+        ).unwrap()
+    });
+
+    assert_eq!(Value::from(4950), rval);
+}
+
+#[test]
+fn write_external_buffer_in_place() {
+    let rubtle = Rubtle::new();
+    let mut data = vec![0u8; 4];
+
+    rubtle.with_external_buffer(&mut data, |buf| {
+        rubtle.set_global_value("rubtle", &buf.to_value());
+        rubtle.eval("for (var i = 0; i < rubtle.length; i++) { rubtle[i] = i + 1; }").unwrap();
+    });
+
+    assert_eq!(vec![1u8, 2, 3, 4], data);
+}
+
+#[test]
+fn detach_external_buffer_on_return() {
+    let rubtle = Rubtle::new();
+    let mut data = vec![1u8, 2, 3];
+
+    for buffer_type in [BufferType::Plain, BufferType::Uint8Array, BufferType::DataView].iter() {
+        rubtle.set_buffer_type(*buffer_type);
+
+        let rval = rubtle.with_external_buffer(&mut data, |buf| {
+            let rval = buf.to_value();
+
+            rubtle.set_global_value("rubtle", &rval);
+            rubtle.eval("var view = new Uint8Array(Uint8Array.plainOf(rubtle).buffer);").unwrap();
+
+            rval
+        });
+
+        /* Neither the buffer nor views on it reach the memory anymore */
+        assert_eq!(Value::from(Vec::<u8>::new()), rubtle.get_global_value("view").unwrap());
+        assert_eq!(Value::from(0), rubtle.eval("Uint8Array.plainOf(rubtle).length").unwrap());
+        assert_eq!(Value::from(0), rubtle.eval("view[0] = 9; view[0]").unwrap());
+
+        /* Handles outliving the guard push empty buffers */
+        rubtle.set_global_value("rubtle", &rval);
+
+        assert_eq!(Value::from(0), rubtle.eval("Uint8Array.plainOf(rubtle).length").unwrap());
+    }
+
+    assert_eq!(vec![1u8, 2, 3], data);
+}
+
+#[test]
+fn detach_external_buffer_on_panic() {
+    let rubtle = Rubtle::new();
+    let mut data = vec![1u8, 2, 3];
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        rubtle.with_external_buffer(&mut data, |buf| {
+            rubtle.set_global_value("rubtle", &buf.to_value());
+
+            panic!("rubtle");
+        })
+    }));
+
+    assert!(result.is_err());
+    assert_eq!(Value::from(0), rubtle.eval("Uint8Array.plainOf(rubtle).length").unwrap());
+}
+
+#[test]
+fn pop_external_buffer_as_copy() {
+    let rubtle = Rubtle::new();
+    let mut data = vec![1u8, 2, 3];
+
+    rubtle.with_external_buffer(&mut data, |buf| {
+        rubtle.push_value(&buf.to_value());
+
+        assert_eq!(Value::from(vec![1u8, 2, 3]), rubtle.pop_value().unwrap());
+    });
+}
+
+#[test]
+fn detach_external_buffer_after_rubtle() {
+    let rubtle = Rubtle::new();
+    let mut data = vec![1u8, 2, 3];
+
+    rubtle.with_external_buffer(&mut data, |buf| {
+        let other = Rubtle::new();
+
+        other.set_global_value("rubtle", &buf.to_value());
+
+        drop(other);
+    });
+}
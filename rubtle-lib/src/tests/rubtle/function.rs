@@ -0,0 +1,271 @@
+///
+/// @package Rubtle-Lib
+///
+/// @file Rubtle tests - function
+/// @copyright 2020-present Christoph Kappel <christoph@unexist.dev>
+/// @version $Id$
+///
+/// This program can be distributed under the terms of the GNU GPLv2.
+/// See the file LICENSE for details.
+///
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::{CallbackResult, Error, Function, Invocation, JsFunction, Rubtle, Value};
+
+///
+/// JS functions
+///
+
+#[test]
+fn pop_js_function() {
+    let rubtle = Rubtle::new();
+
+    let rval = rubtle.eval("(function() { return 'rubtle'; })").unwrap();
+
+    assert!(rval.is_function());
+    assert!(rval.as_function().unwrap().is_js_function());
+}
+
+#[test]
+fn call_js_function_with_args() {
+    let rubtle = Rubtle::new();
+
+    rubtle.eval("function add(a, b) { return a + b; }").unwrap();
+
+    let rval = rubtle.get_global_value("add").unwrap();
+    let add = rval.as_function().unwrap().as_js_function().unwrap();
+
+    assert_eq!(
+        Value::from(5),
+        add.call(&rubtle, &Value::None, &[Value::from(2), Value::from(3)]).unwrap()
+    );
+    assert_eq!(
+        Value::from("rubtle4"),
+        add.call(&rubtle, &Value::None, &[Value::from("rubtle"), Value::from(4)]).unwrap()
+    );
+}
+
+#[test]
+fn call_js_function_with_this() {
+    let rubtle = Rubtle::new();
+
+    let rval = rubtle.eval("(function() { return this.name; })").unwrap();
+    let func = rval.as_function().unwrap().as_js_function().unwrap();
+
+    let mut this = HashMap::new();
+
+    this.insert(String::from("name"), Value::from("rubtle"));
+
+    assert_eq!(
+        Value::from("rubtle"),
+        func.call(&rubtle, &Value::Object(this.into()), &[]).unwrap()
+    );
+}
+
+#[test]
+fn call_js_function_throwing_error() {
+    let rubtle = Rubtle::new();
+
+    let rval = rubtle.eval("(function() { throw new TypeError('rubtle'); })").unwrap();
+    let func = rval.as_function().unwrap().as_js_function().unwrap();
+
+    let err = func.call(&rubtle, &Value::None, &[]).unwrap_err();
+
+    match err {
+        Error::Exception(js) => {
+            assert_eq!("TypeError", js.name);
+            assert_eq!("rubtle", js.message);
+        }
+        _ => panic!("Unexpected error {:?}", err),
+    }
+
+    /* Heap is still usable */
+    assert_eq!(Value::from(2), rubtle.eval("1 + 1").unwrap());
+}
+
+#[test]
+fn call_js_function_registered_as_handler() {
+    let rubtle = Rubtle::new();
+    let handlers: Rc<RefCell<Vec<JsFunction>>> = Rc::new(RefCell::new(Vec::new()));
+    let handlers2 = handlers.clone();
+
+    rubtle.set_global_function("on", move |inv| -> CallbackResult<Value> {
+        let args = inv.args.unwrap();
+
+        match args.first().and_then(|val| val.as_function()) {
+            Some(func) => handlers2.borrow_mut().push(func.as_js_function().unwrap().clone()),
+            None => return Err(Error::type_error("Expected function")),
+        }
+
+        Ok(Value::None)
+    });
+
+    rubtle.eval(
+        r#"
+        var counter = 0;
+
+        on(function(step) { counter += step; return counter; });
+    "#,
+    ).unwrap();
+
+    /* Handler must survive garbage collection */
+    rubtle.gc();
+
+    let handler = handlers.borrow()[0].clone();
+
+    assert_eq!(Value::from(2), handler.call(&rubtle, &Value::None, &[Value::from(2)]).unwrap());
+    assert_eq!(Value::from(5), handler.call(&rubtle, &Value::None, &[Value::from(3)]).unwrap());
+    assert_eq!(Value::from(5), rubtle.get_global_value("counter").unwrap());
+}
+
+#[test]
+fn push_js_function_back() {
+    let rubtle = Rubtle::new();
+
+    let rval = rubtle.eval("(function(a) { return a * 2; })").unwrap();
+
+    rubtle.set_global_value("double", &rval);
+
+    assert_eq!(Value::from(8), rubtle.eval("double(4)").unwrap());
+}
+
+#[test]
+fn compare_js_functions() {
+    let rubtle = Rubtle::new();
+
+    rubtle.eval("function a() {}; function b() {}").unwrap();
+
+    let a = rubtle.get_global_value("a").unwrap();
+
+    assert_eq!(a, a.clone());
+    assert_eq!(a, rubtle.get_global_value("a").unwrap());
+    assert_ne!(a, rubtle.get_global_value("b").unwrap());
+}
+
+#[test]
+fn drop_js_function_after_rubtle() {
+    let rubtle = Rubtle::new();
+
+    let rval = rubtle.eval("(function() {})").unwrap();
+
+    drop(rubtle);
+    drop(rval);
+}
+
+///
+/// Rust closures
+///
+
+#[test]
+fn push_object_with_closure() {
+    let rubtle = Rubtle::new();
+    let mut options = HashMap::new();
+
+    options.insert(String::from("name"), Value::from("rubtle"));
+    options.insert(
+        String::from("onData"),
+        Value::from(Function::from(|inv: Invocation<i8>| -> CallbackResult<Value> {
+            let args = inv.args.unwrap();
+
+            Ok(Value::from(args.first().unwrap().as_number().unwrap() * 2.0))
+        })),
+    );
+
+    rubtle.set_global_value("options", &Value::Object(options.into()));
+
+    assert_eq!(
+        Value::from("function"),
+        rubtle.eval("typeof options.onData").unwrap()
+    );
+    assert_eq!(
+        Value::from("rubtle8"),
+        rubtle.eval("options.name + options.onData(4)").unwrap()
+    );
+}
+
+#[test]
+fn push_array_with_closures() {
+    let rubtle = Rubtle::new();
+
+    let double = Function::from(|inv: Invocation<i8>| -> CallbackResult<Value> {
+        let args = inv.args.unwrap();
+
+        Ok(Value::from(args.first().unwrap().as_number().unwrap() * 2.0))
+    });
+
+    /* Both entries share the same closure */
+    let rval = Value::Array(vec![Value::from(double.clone()), Value::from(double)]);
+
+    rubtle.set_global_value("funcs", &rval);
+
+    assert_eq!(
+        Value::from(10),
+        rubtle.eval("funcs[0](2) + funcs[1](3)").unwrap()
+    );
+}
+
+#[test]
+fn call_rust_function() {
+    let rubtle = Rubtle::new();
+
+    let func = Function::from(|inv: Invocation<i8>| -> CallbackResult<Value> {
+        match inv.args.unwrap().first() {
+            Some(Value::Str(s)) => Ok(Value::from(format!("{}!", s))),
+            _ => Err(Error::type_error("Expected string")),
+        }
+    });
+
+    assert_eq!(
+        Value::from("rubtle!"),
+        func.call(&rubtle, &Value::None, &[Value::from("rubtle")]).unwrap()
+    );
+
+    match func.call(&rubtle, &Value::None, &[]) {
+        Err(Error::Exception(js)) => assert_eq!("TypeError", js.name),
+        rval => panic!("Unexpected result {:?}", rval),
+    }
+}
+
+#[test]
+fn call_rust_function_with_handle_of_other_rubtle() {
+    let rubtle = Rubtle::new();
+    let other = Rubtle::new();
+
+    let func = Function::from(|_inv: Invocation<i8>| -> CallbackResult<Value> {
+        Ok(Value::from(true))
+    });
+
+    let rval = rubtle.eval("[1]").unwrap();
+    let obj = rubtle.eval("(function() {})").unwrap();
+
+    assert_eq!(
+        Err(Error::Heap("Handle belongs to another heap")),
+        func.call(&other, &Value::None, &[rval, obj])
+    );
+
+    unsafe {
+        assert_eq!(0, ffi::duk_get_top(other.ctx));
+    }
+}
+
+#[test]
+fn release_closure_on_gc() {
+    let rubtle = Rubtle::new();
+    let token = Rc::new(());
+    let token2 = token.clone();
+
+    let func = Function::from(move |_inv: Invocation<i8>| -> CallbackResult<Value> {
+        Ok(Value::from(Rc::strong_count(&token2) as i32))
+    });
+
+    rubtle.set_global_value("rubtle", &Value::Array(vec![Value::from(func)]));
+
+    assert_eq!(Value::from(2), rubtle.eval("rubtle[0]()").unwrap());
+
+    rubtle.eval("rubtle = null;").unwrap();
+    rubtle.gc();
+
+    assert_eq!(1, Rc::strong_count(&token));
+}
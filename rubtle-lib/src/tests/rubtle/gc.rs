@@ -0,0 +1,155 @@
+///
+/// @package Rubtle-Lib
+///
+/// @file Rubtle tests - gc
+/// @copyright 2020-present Christoph Kappel <christoph@unexist.dev>
+/// @version $Id$
+///
+/// This program can be distributed under the terms of the GNU GPLv2.
+/// See the file LICENSE for details.
+///
+use crate::{Rubtle, RubtleBuilder, Value};
+
+///
+/// Garbage collection
+///
+
+#[test]
+fn gc_frees_garbage() {
+    let rubtle = Rubtle::new();
+
+    rubtle.eval(
+        r#"
+        var rubtle = [];
+
+        for (var i = 0; i < 1000; i++) {
+            rubtle.push({ idx: i, name: 'rubtle' + i });
+        }
+    "#,
+    ).unwrap();
+
+    let before = rubtle.heap_stats();
+
+    rubtle.eval("rubtle = null;").unwrap();
+    rubtle.gc();
+
+    let after = rubtle.heap_stats();
+
+    assert!(before.objects >= after.objects + 1000);
+    assert!(before.strings > after.strings);
+}
+
+#[test]
+fn gc_compact_keeps_live_objects() {
+    let rubtle = RubtleBuilder::new().with_memory_tracking().build();
+
+    rubtle.eval(
+        r#"
+        var rubtle = { keep: 'rubtle' };
+
+        for (var i = 0; i < 100; i++) {
+            rubtle['tmp' + i] = i;
+        }
+
+        for (var i = 0; i < 100; i++) {
+            delete rubtle['tmp' + i];
+        }
+    "#,
+    ).unwrap();
+
+    let before = rubtle.heap_stats().allocated_bytes.unwrap();
+
+    rubtle.gc_compact();
+
+    let after = rubtle.heap_stats().allocated_bytes.unwrap();
+
+    assert!(before > after);
+    assert_eq!(
+        Value::from("rubtle"),
+        rubtle.eval("rubtle.keep").unwrap()
+    );
+}
+
+#[test]
+fn gc_runs_finalizers() {
+    let rubtle = Rubtle::new();
+
+    rubtle.eval(
+        r#"
+        var finalized = 0;
+        var rubtle = {};
+
+        Duktape.fin(rubtle, function() { finalized++; });
+
+        rubtle = null;
+    "#,
+    ).unwrap();
+
+    rubtle.gc();
+
+    let stats = rubtle.heap_stats();
+
+    assert_eq!(Value::from(1.0), rubtle.eval("finalized").unwrap());
+    assert_eq!(0, stats.pending_finalizers);
+    assert_eq!(1, stats.finalized);
+}
+
+#[test]
+fn gc_counts_finalizers_once() {
+    let rubtle = Rubtle::new();
+
+    rubtle.eval(
+        r#"
+        var rubtle = [];
+
+        for (var i = 0; i < 3; i++) {
+            rubtle.push({});
+
+            Duktape.fin(rubtle[i], function() {});
+        }
+
+        rubtle = null;
+    "#,
+    ).unwrap();
+
+    rubtle.gc();
+
+    assert_eq!(3, rubtle.heap_stats().finalized);
+
+    rubtle.gc();
+
+    /* Freed objects must not be counted twice */
+    assert_eq!(3, rubtle.heap_stats().finalized);
+}
+
+///
+/// Heap stats
+///
+
+#[test]
+fn heap_stats_count_buffers() {
+    let rubtle = Rubtle::new();
+
+    let before = rubtle.heap_stats();
+
+    rubtle.eval("var rubtle = [new Uint8Array(16), new ArrayBuffer(8)];").unwrap();
+
+    let after = rubtle.heap_stats();
+
+    assert_eq!(before.buffers + 2, after.buffers);
+    assert!(before.objects + 3 <= after.objects);
+}
+
+#[test]
+fn heap_stats_with_memory_tracking() {
+    let rubtle = RubtleBuilder::new().with_memory_tracking().build();
+
+    let stats = rubtle.heap_stats();
+
+    assert_eq!(
+        rubtle.memory_usage().map(|usage| usage.allocated),
+        stats.allocated_bytes
+    );
+    assert!(0 < stats.objects);
+    assert!(0 < stats.strings);
+}
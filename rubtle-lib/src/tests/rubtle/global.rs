@@ -8,7 +8,7 @@
 /// This program can be distributed under the terms of the GNU GPLv2.
 /// See the file LICENSE for details.
 ///
-use crate::{Rubtle, Value, CallbackResult};
+use crate::{Rubtle, Value, CallbackResult, Error};
 
 use crate::tests::rubtle::helper::js_printer;
 
@@ -39,5 +39,128 @@ fn set_and_run_global_printer() {
         r#"
         print('Test');
     "#,
-    );
-}
\ No newline at end of file
+    ).unwrap();
+}
+
+#[test]
+fn set_global_function_returning_error() {
+    let rubtle = Rubtle::new();
+
+    rubtle.set_global_function("fail", |_inv| -> CallbackResult<Value> {
+        Err(Error::Callback(String::from("rubtle")))
+    });
+
+    let rval = rubtle.eval(
+        r#"
+        try {
+            fail();
+            false;
+        } catch (e) {
+            e instanceof Error && 'rubtle' == e.message;
+        }
+    "#,
+    ).unwrap();
+
+    assert_eq!(Value::from(true), rval);
+}
+
+#[test]
+fn set_global_function_returning_type_error() {
+    let rubtle = Rubtle::new();
+
+    rubtle.set_global_function("fail", |_inv| -> CallbackResult<Value> {
+        Err(Error::type_error("rubtle"))
+    });
+
+    let rval = rubtle.eval(
+        r#"
+        try {
+            fail();
+            false;
+        } catch (e) {
+            e instanceof TypeError && 'rubtle' == e.message;
+        }
+    "#,
+    ).unwrap();
+
+    assert_eq!(Value::from(true), rval);
+}
+
+#[test]
+fn set_global_function_returning_custom_error() {
+    let rubtle = Rubtle::new();
+
+    rubtle.set_global_function("fail", |_inv| -> CallbackResult<Value> {
+        Err(Error::js("RubtleError", "rubtle"))
+    });
+
+    let rval = rubtle.eval(
+        r#"
+        function RubtleError(message) {
+            this.message = message;
+        }
+
+        RubtleError.prototype = Object.create(Error.prototype);
+        RubtleError.prototype.name = 'RubtleError';
+
+        try {
+            fail();
+            false;
+        } catch (e) {
+            e instanceof RubtleError && 'rubtle' == e.message;
+        }
+    "#,
+    ).unwrap();
+
+    assert_eq!(Value::from(true), rval);
+}
+
+#[test]
+fn set_global_function_returning_unknown_error() {
+    let rubtle = Rubtle::new();
+
+    rubtle.set_global_function("fail", |_inv| -> CallbackResult<Value> {
+        Err(Error::js("RubtleError", "rubtle"))
+    });
+
+    let err = rubtle.eval(
+        r#"
+        fail();
+    "#,
+    ).unwrap_err();
+
+    match err {
+        Error::Exception(js) => {
+            assert_eq!("RubtleError", js.name);
+            assert_eq!("rubtle", js.message);
+        }
+        _ => panic!("Expected exception"),
+    }
+}
+
+#[test]
+fn set_global_function_panicking() {
+    let rubtle = Rubtle::new();
+
+    rubtle.set_global_function("fail", |_inv| -> CallbackResult<Value> {
+        panic!("rubtle");
+    });
+
+    let rval = rubtle.eval(
+        r#"
+        try {
+            fail();
+            false;
+        } catch (e) {
+            e instanceof Error && 'rubtle' == e.message;
+        }
+    "#,
+    ).unwrap();
+
+    assert_eq!(Value::from(true), rval);
+
+    /* Heap must still be usable */
+    let err = rubtle.eval("fail();").unwrap_err();
+
+    assert_eq!("rubtle", err.as_js_error().unwrap().message);
+}
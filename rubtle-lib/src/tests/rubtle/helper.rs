@@ -18,9 +18,9 @@ pub fn js_printer(inv: Invocation<i8>) -> CallbackResult<Value> {
     let args = inv.args.unwrap();
 
     for val in args.iter() {
-        match val.coerce_string() {
-            Some(s) => println!("{:?}", s),
-            None => eprintln!("Error unwrap value"),
+        match inv.rubtle.coerce_string(val) {
+            Ok(s) => println!("{:?}", s),
+            Err(err) => eprintln!("Error unwrap value: {}", err),
         }
     }
 
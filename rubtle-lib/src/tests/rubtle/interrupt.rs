@@ -0,0 +1,139 @@
+///
+/// @package Rubtle-Lib
+///
+/// @file Rubtle tests - interrupt
+/// @copyright 2020-present Christoph Kappel <christoph@unexist.dev>
+/// @version $Id$
+///
+/// This program can be distributed under the terms of the GNU GPLv2.
+/// See the file LICENSE for details.
+///
+use crate::{Error, Limit, Rubtle, Value, CallbackResult};
+
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+///
+/// Time limits
+///
+
+#[test]
+fn eval_with_time_limit() {
+    let rubtle = Rubtle::new();
+
+    rubtle.set_time_limit(Duration::from_millis(50));
+
+    let err = rubtle.eval(
+        r#"
+        while (true) {
+            try {
+                for (;;) {}
+            } catch (e) {
+                /* Nope */
+            }
+        }
+    "#,
+    ).unwrap_err();
+
+    assert_eq!(Error::Limit(Limit::Time), err);
+
+    rubtle.clear_time_limit();
+
+    /* Heap must still be usable */
+    assert_eq!(Value::from(2), rubtle.eval("1 + 1").unwrap());
+}
+
+#[test]
+fn run_script_with_time_limit() {
+    let rubtle = Rubtle::new();
+    let script = rubtle.compile("while (true) {}", "rubtle.js").unwrap();
+
+    rubtle.set_time_limit(Duration::from_millis(10));
+
+    for _ in 0..2 {
+        assert_eq!(Err(Error::Limit(Limit::Time)), script.run(&rubtle));
+    }
+
+    rubtle.clear_time_limit();
+
+    assert_eq!(Value::from(2), rubtle.eval("1 + 1").unwrap());
+}
+
+#[test]
+fn eval_with_time_limit_in_nested_eval() {
+    let rubtle = Rubtle::new();
+
+    rubtle.set_global_function("nested", |inv| -> CallbackResult<Value> {
+        inv.rubtle.eval("while (true) {}")
+    });
+
+    rubtle.set_time_limit(Duration::from_millis(10));
+
+    assert_eq!(
+        Err(Error::Limit(Limit::Time)),
+        rubtle.eval(
+            r#"
+            try {
+                nested();
+            } catch (e) {
+                /* Nope */
+            }
+
+            while (true) {}
+        "#,
+        )
+    );
+}
+
+///
+/// Interrupt handle
+///
+
+#[test]
+fn interrupt_eval_from_thread() {
+    let rubtle = Rubtle::new();
+    let handle = rubtle.interrupt_handle();
+
+    let (tx, rx) = mpsc::channel();
+
+    rubtle.set_global_function("started", move |_inv| -> CallbackResult<Value> {
+        tx.send(()).unwrap();
+
+        Ok(Value::from(true))
+    });
+
+    /* Wait until the script is running */
+    let thread = thread::spawn(move || {
+        rx.recv().unwrap();
+
+        handle.interrupt();
+    });
+
+    assert_eq!(
+        Err(Error::Limit(Limit::Time)),
+        rubtle.eval("started(); while (true) {}")
+    );
+
+    thread.join().unwrap();
+
+    /* Interrupt must not affect the next run */
+    assert_eq!(Value::from(2), rubtle.eval("1 + 1").unwrap());
+}
+
+#[test]
+fn interrupt_while_idle() {
+    let rubtle = Rubtle::new();
+    let handle = rubtle.interrupt_handle();
+
+    thread::spawn(move || handle.interrupt()).join().unwrap();
+
+    /* Pending interrupt must abort the next run */
+    assert_eq!(
+        Err(Error::Limit(Limit::Time)),
+        rubtle.eval("while (true) {}")
+    );
+
+    /* Interrupt is consumed by the aborted run */
+    assert_eq!(Value::from(2), rubtle.eval("1 + 1").unwrap());
+}
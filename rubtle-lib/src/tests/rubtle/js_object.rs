@@ -0,0 +1,203 @@
+///
+/// @package Rubtle-Lib
+///
+/// @file Rubtle tests - js object
+/// @copyright 2020-present Christoph Kappel <christoph@unexist.dev>
+/// @version $Id$
+///
+/// This program can be distributed under the terms of the GNU GPLv2.
+/// See the file LICENSE for details.
+///
+use crate::{CallbackResult, Error, Rubtle, Value};
+
+///
+/// Object handles
+///
+
+#[test]
+fn get_and_set_via_handle() {
+    let rubtle = Rubtle::new();
+
+    rubtle.eval("var rubtle = { name: 'rubtle', count: 1 };").unwrap();
+
+    let obj = rubtle.get_global_js_object("rubtle").unwrap();
+
+    assert_eq!(Value::from("rubtle"), obj.get(&rubtle, "name").unwrap());
+    assert_eq!(Value::None, obj.get(&rubtle, "missing").unwrap());
+
+    obj.set(&rubtle, "count", &Value::from(2)).unwrap();
+
+    /* Changes on either side reach the other one */
+    assert_eq!(Value::from(2), rubtle.eval("rubtle.count").unwrap());
+
+    rubtle.eval("rubtle.count++;").unwrap();
+
+    assert_eq!(Value::from(3), obj.get(&rubtle, "count").unwrap());
+}
+
+#[test]
+fn has_and_delete_via_handle() {
+    let rubtle = Rubtle::new();
+
+    rubtle.eval("var rubtle = { name: 'rubtle' };").unwrap();
+
+    let obj = rubtle.get_global_js_object("rubtle").unwrap();
+
+    assert!(obj.has(&rubtle, "name").unwrap());
+    assert!(obj.has(&rubtle, "toString").unwrap());
+
+    obj.delete(&rubtle, "name").unwrap();
+
+    assert!(!obj.has(&rubtle, "name").unwrap());
+    assert_eq!(Value::from(false), rubtle.eval("'name' in rubtle").unwrap());
+}
+
+#[test]
+fn keys_and_len_via_handle() {
+    let rubtle = Rubtle::new();
+
+    rubtle.eval(
+        r#"
+        var rubtle = { a: 1, b: 2 };
+        var ary = [1, 2, 3];
+
+        Object.defineProperty(rubtle, 'hidden', { value: 3, enumerable: false });
+    "#,
+    ).unwrap();
+
+    let obj = rubtle.get_global_js_object("rubtle").unwrap();
+
+    assert_eq!(vec!["a", "b"], obj.keys(&rubtle).unwrap());
+    assert_eq!(2, obj.len(&rubtle).unwrap());
+    assert!(!obj.is_array(&rubtle));
+
+    let ary = rubtle.get_global_js_object("ary").unwrap();
+
+    assert_eq!(3, ary.len(&rubtle).unwrap());
+    assert!(ary.is_array(&rubtle));
+}
+
+#[test]
+fn get_nested_object_via_handle() {
+    let rubtle = Rubtle::new();
+
+    rubtle.eval("var rubtle = { inner: { value: 4 }, plain: 1 };").unwrap();
+
+    let obj = rubtle.get_global_js_object("rubtle").unwrap();
+    let inner = obj.get_object(&rubtle, "inner").unwrap().unwrap();
+
+    assert!(obj.get_object(&rubtle, "plain").unwrap().is_none());
+
+    inner.set(&rubtle, "value", &Value::from(5)).unwrap();
+
+    assert_eq!(Value::from(5), rubtle.eval("rubtle.inner.value").unwrap());
+}
+
+#[test]
+fn throwing_accessors_via_handle() {
+    let rubtle = Rubtle::new();
+
+    rubtle.eval(
+        r#"
+        var rubtle = Object.freeze({
+            get broken() { throw new RangeError('rubtle'); }
+        });
+    "#,
+    ).unwrap();
+
+    let obj = rubtle.get_global_js_object("rubtle").unwrap();
+
+    match obj.get(&rubtle, "broken") {
+        Err(Error::Exception(js)) => assert_eq!("RangeError", js.name),
+        rval => panic!("Unexpected result {:?}", rval),
+    }
+
+    match obj.set(&rubtle, "name", &Value::from("rubtle")) {
+        Err(Error::Exception(js)) => assert_eq!("TypeError", js.name),
+        rval => panic!("Unexpected result {:?}", rval),
+    }
+
+    assert!(obj.delete(&rubtle, "broken").is_err());
+}
+
+#[test]
+fn push_and_copy_handle() {
+    let rubtle = Rubtle::new();
+
+    rubtle.eval("var rubtle = [1, 2];").unwrap();
+
+    let obj = rubtle.get_global_js_object("rubtle").unwrap();
+
+    assert_eq!(
+        Some(Value::Array(vec![Value::from(1), Value::from(2)])),
+        obj.to_value(&rubtle)
+    );
+
+    rubtle.push_js_object(&obj).unwrap();
+
+    assert_eq!(Some(obj.clone()), rubtle.pop_js_object());
+    assert!(rubtle.get_global_js_object("undefined").is_none());
+}
+
+#[test]
+fn drop_handle_after_rubtle() {
+    let rubtle = Rubtle::new();
+
+    rubtle.eval("var rubtle = {};").unwrap();
+
+    let obj = rubtle.get_global_js_object("rubtle").unwrap();
+
+    drop(rubtle);
+    drop(obj);
+}
+
+#[test]
+fn use_handle_with_other_rubtle() {
+    let rubtle = Rubtle::new();
+    let other = Rubtle::new();
+
+    rubtle.eval("var rubtle = [1, 2];").unwrap();
+
+    let obj = rubtle.get_global_js_object("rubtle").unwrap();
+
+    assert_eq!(Err(Error::Heap("Handle belongs to another heap")), obj.len(&other));
+    assert!(other.push_js_object(&obj).is_err());
+    assert!(!obj.is_array(&other));
+
+    drop(rubtle);
+
+    assert_eq!(Err(Error::Heap("Heap of handle is gone")), obj.len(&other));
+
+    unsafe {
+        assert_eq!(0, ffi::duk_get_top(other.ctx));
+    }
+}
+
+#[test]
+fn return_handle_of_other_rubtle() {
+    let rubtle = Rubtle::new();
+    let other = Rubtle::new();
+
+    rubtle.eval("var rubtle = [1, 2];").unwrap();
+
+    let obj = rubtle.get_global_js_object("rubtle").unwrap();
+
+    /* Pushed as undefined via ToJs */
+    other.set_global_value("rubtle", &obj);
+
+    assert_eq!(Value::from("undefined"), other.eval("typeof rubtle").unwrap());
+
+    /* Thrown as error from callbacks */
+    other.set_global_function("rubtle", move |_inv| -> CallbackResult<Value> {
+        Ok(Value::Ref(obj.clone()))
+    });
+
+    match other.eval("rubtle()") {
+        Err(Error::Exception(_)) => (),
+        rval => panic!("Expected exception, got {:?}", rval),
+    }
+
+    unsafe {
+        assert_eq!(0, ffi::duk_get_top(other.ctx));
+    }
+}
@@ -0,0 +1,220 @@
+///
+/// @package Rubtle-Lib
+///
+/// @file Rubtle tests - json
+/// @copyright 2020-present Christoph Kappel <christoph@unexist.dev>
+/// @version $Id$
+///
+/// This program can be distributed under the terms of the GNU GPLv2.
+/// See the file LICENSE for details.
+///
+use std::time::{Duration, UNIX_EPOCH};
+
+use crate::{Error, JsonFormat, JsonOptions, ObjectMap, Rubtle, Value};
+
+fn object() -> Value {
+    let mut hash = ObjectMap::new();
+
+    hash.insert("name", Value::from("rubtle"));
+    hash.insert("list", Value::from(&vec![1, 2]));
+    hash.insert("none", Value::None);
+
+    Value::Object(hash)
+}
+
+///
+/// Encode
+///
+
+#[test]
+fn json_encode_object() {
+    let rubtle = Rubtle::new();
+
+    assert_eq!(
+        r#"{"name":"rubtle","list":[1,2]}"#,
+        rubtle.json_encode(&object()).unwrap()
+    );
+}
+
+#[test]
+fn json_encode_pretty() {
+    let rubtle = Rubtle::new();
+    let options = JsonOptions::new().with_indent(4);
+
+    assert_eq!(
+        "{\n    \"name\": \"rubtle\",\n    \"list\": [\n        1,\n        2\n    ]\n}",
+        rubtle.json_encode_with(&object(), &options).unwrap()
+    );
+}
+
+#[test]
+fn json_encode_date() {
+    let rubtle = Rubtle::new();
+
+    assert_eq!(
+        r#""1970-01-01T00:00:01.500Z""#,
+        rubtle
+            .json_encode(&Value::from(UNIX_EPOCH + Duration::from_millis(1500)))
+            .unwrap()
+    );
+}
+
+#[test]
+fn json_encode_undefined_fails() {
+    let rubtle = Rubtle::new();
+
+    match rubtle.json_encode(&Value::None) {
+        Err(Error::Conversion { .. }) => (),
+        rval => panic!("Expected conversion error, got {:?}", rval),
+    }
+
+    unsafe {
+        assert_eq!(0, ffi::duk_get_top(rubtle.ctx));
+    }
+}
+
+#[test]
+fn jx_and_jc_encode() {
+    let rubtle = Rubtle::new();
+    let rval = Value::Array(vec![
+        Value::None,
+        Value::from(f64::INFINITY),
+        Value::from(vec![1u8, 255]),
+    ]);
+
+    assert_eq!(
+        "[undefined,Infinity,|01ff|]",
+        rubtle
+            .json_encode_with(&rval, &JsonOptions::new().with_format(JsonFormat::Jx))
+            .unwrap()
+    );
+    assert_eq!(
+        r#"[{"_undef":true},{"_inf":true},{"_buf":"01ff"}]"#,
+        rubtle
+            .json_encode_with(&rval, &JsonOptions::new().with_format(JsonFormat::Jc))
+            .unwrap()
+    );
+}
+
+///
+/// Decode
+///
+
+#[test]
+fn json_decode_and_encode() {
+    let rubtle = Rubtle::new();
+    let json = r#"{"b":[1,"x",null],"a":{"c":false}}"#;
+
+    let rval = rubtle.json_decode(json).unwrap();
+
+    assert_eq!(json, rubtle.json_encode(&rval).unwrap());
+}
+
+#[test]
+fn json_decode_invalid() {
+    let rubtle = Rubtle::new();
+
+    match rubtle.json_decode("{ rubtle: 1 }") {
+        Err(Error::Exception(err)) => assert_eq!("SyntaxError", err.name),
+        rval => panic!("Expected syntax error, got {:?}", rval),
+    }
+
+    unsafe {
+        assert_eq!(0, ffi::duk_get_top(rubtle.ctx));
+    }
+}
+
+#[test]
+fn jx_decode() {
+    let rubtle = Rubtle::new();
+    let options = JsonOptions::new().with_format(JsonFormat::Jx);
+
+    assert_eq!(
+        Value::Array(vec![Value::None, Value::from(f64::NEG_INFINITY)]),
+        rubtle.json_decode_with("[undefined, -Infinity]", &options).unwrap()
+    );
+}
+
+///
+/// Globals
+///
+
+#[test]
+fn json_with_replaced_globals() {
+    let rubtle = Rubtle::new();
+
+    rubtle
+        .eval("JSON.stringify = function() { return 'rubtle'; }; JSON.parse = undefined;")
+        .unwrap();
+
+    assert_eq!("[1]", rubtle.json_encode(&Value::from(&vec![1])).unwrap());
+    assert_eq!(Value::from(true), rubtle.json_decode("true").unwrap());
+
+    rubtle.eval("JSON = undefined; Duktape = undefined;").unwrap();
+
+    let options = JsonOptions::new().with_format(JsonFormat::Jx);
+
+    assert_eq!("[1]", rubtle.json_encode(&Value::from(&vec![1])).unwrap());
+    assert_eq!(
+        "undefined",
+        rubtle.json_encode_with(&Value::None, &options).unwrap()
+    );
+    assert_eq!(
+        Value::None,
+        rubtle.json_decode_with("undefined", &options).unwrap()
+    );
+
+    unsafe {
+        assert_eq!(0, ffi::duk_get_top(rubtle.ctx));
+    }
+}
+
+///
+/// Value
+///
+
+#[test]
+fn value_to_json_string_and_back() {
+    let json = object().to_json_string().unwrap();
+
+    let mut expected = object();
+
+    if let Value::Object(ref mut hash) = expected {
+        hash.remove("none");
+    }
+
+    assert_eq!(expected, Value::from_json_str(&json).unwrap());
+}
+
+#[test]
+fn value_with_js_handle_fails() {
+    let rubtle = Rubtle::new();
+
+    let func = rubtle.eval("(function() {})").unwrap();
+
+    assert!(func.to_json_string().is_err());
+    assert_eq!(
+        Err(Error::Conversion {
+            expected: "string",
+            actual: "undefined"
+        }),
+        rubtle.json_encode(&func)
+    );
+}
+
+#[test]
+fn json_encode_handle_of_other_rubtle_fails() {
+    let rubtle = Rubtle::new();
+    let other = Rubtle::new();
+
+    let rval = rubtle.eval("[function() {}]").unwrap();
+
+    assert_eq!(
+        Err(Error::Heap("Handle belongs to another heap")),
+        other.json_encode(&rval)
+    );
+
+    unsafe {
+        assert_eq!(0, ffi::duk_get_top(other.ctx));
+    }
+}
@@ -0,0 +1,135 @@
+///
+/// @package Rubtle-Lib
+///
+/// @file Rubtle tests - memory
+/// @copyright 2020-present Christoph Kappel <christoph@unexist.dev>
+/// @version $Id$
+///
+/// This program can be distributed under the terms of the GNU GPLv2.
+/// See the file LICENSE for details.
+///
+use crate::{Error, Limit, Rubtle, RubtleBuilder, Value};
+
+///
+/// Memory limits
+///
+
+#[test]
+fn create_rubtle_without_allocator() {
+    let rubtle = Rubtle::new();
+
+    assert!(rubtle.memory_usage().is_none());
+}
+
+#[test]
+fn create_rubtle_with_memory_tracking() {
+    let rubtle = RubtleBuilder::new().with_memory_tracking().build();
+
+    let before = rubtle.memory_usage().unwrap();
+
+    assert!(0 < before.allocated);
+    assert_eq!(None, before.limit);
+
+    rubtle.eval(
+        r#"
+        var rubtle = [];
+
+        for (var i = 0; i < 10000; i++) {
+            rubtle.push('rubtle' + i);
+        }
+    "#,
+    ).unwrap();
+
+    let after = rubtle.memory_usage().unwrap();
+
+    assert!(before.allocated < after.allocated);
+    assert!(after.allocated <= after.peak);
+}
+
+#[test]
+fn try_build_with_too_small_memory_limit() {
+    let rval = RubtleBuilder::new().with_memory_limit(16).try_build();
+
+    assert!(matches!(rval, Err(Error::Limit(Limit::Memory))));
+}
+
+#[test]
+fn eval_with_memory_limit() {
+    let rubtle = RubtleBuilder::new()
+        .with_memory_limit(1024 * 1024)
+        .build();
+
+    let err = rubtle.eval(
+        r#"
+        var rubtle = [];
+
+        while (true) {
+            rubtle.push('rubtle' + rubtle.length);
+        }
+    "#,
+    ).unwrap_err();
+
+    assert_eq!(Error::Limit(Limit::Memory), err);
+
+    let usage = rubtle.memory_usage().unwrap();
+
+    assert!(usage.peak <= 1024 * 1024);
+
+    /* Heap must still be usable */
+    rubtle.eval("rubtle = null;").unwrap();
+
+    assert_eq!(Value::from(2), rubtle.eval("1 + 1").unwrap());
+}
+
+#[test]
+fn eval_with_caught_memory_error() {
+    let rubtle = RubtleBuilder::new()
+        .with_memory_limit(1024 * 1024)
+        .build();
+
+    let rval = rubtle.eval(
+        r#"
+        var rubtle = [];
+
+        try {
+            while (true) {
+                rubtle.push('rubtle' + rubtle.length);
+            }
+        } catch (e) {
+            rubtle = null;
+        }
+
+        'caught';
+    "#,
+    ).unwrap();
+
+    assert_eq!(Value::from("caught"), rval);
+}
+
+#[test]
+fn eval_with_other_error_after_memory_error() {
+    let rubtle = RubtleBuilder::new()
+        .with_memory_limit(1024 * 1024)
+        .build();
+
+    let err = rubtle.eval(
+        r#"
+        var rubtle = [];
+
+        try {
+            while (true) {
+                rubtle.push('rubtle' + rubtle.length);
+            }
+        } catch (e) {
+            rubtle = null;
+        }
+
+        throw new TypeError('rubtle');
+    "#,
+    ).unwrap_err();
+
+    match err {
+        Error::Exception(js) => assert_eq!("TypeError", js.name),
+        err => panic!("Expected exception, got {:?}", err),
+    }
+}
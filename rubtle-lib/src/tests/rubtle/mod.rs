@@ -1,7 +1,23 @@
 mod array;
 mod basic;
+mod bytes;
+mod cbor;
+mod conversion;
+mod convert;
+mod cycle;
+mod date;
 mod eval;
+mod external;
+mod function;
+mod gc;
 mod global;
 mod helper;
+mod interrupt;
+mod js_object;
+mod json;
+mod memory;
 mod object;
 mod object_builder;
+mod script;
+#[cfg(feature = "serde")]
+mod serialize;
@@ -79,7 +79,7 @@ fn get_global_object_value_bool() {
         r#"
         var rubtle = { "rubtle1": true, "rubtle2": false };
     "#,
-    );
+    ).unwrap();
 
     let rval = rubtle.get_global_value("rubtle").unwrap();
 
@@ -101,7 +101,7 @@ fn get_global_object_value_i32() {
         r#"
         var rubtle = { "rubtle1": 1, "rubtle2": 2 };
     "#,
-    );
+    ).unwrap();
 
     let rval = rubtle.get_global_value("rubtle").unwrap();
 
@@ -123,7 +123,7 @@ fn get_global_object_value_f64() {
         r#"
         var rubtle = { "rubtle1": 1.0, "rubtle2": 2.0 };
     "#,
-    );
+    ).unwrap();
 
     let rval = rubtle.get_global_value("rubtle").unwrap();
 
@@ -145,7 +145,7 @@ fn get_global_object_value_str() {
         r#"
         var rubtle = { "rubtle1": "rubtle", "rubtle2": "rubtle" };
     "#,
-    );
+    ).unwrap();
 
     let rval = rubtle.get_global_value("rubtle").unwrap();
 
@@ -157,4 +157,67 @@ fn get_global_object_value_str() {
     let rval2 = Value::from(&hash);
 
     assert_eq!(rval, rval2);
-}
\ No newline at end of file
+}
+
+#[test]
+fn get_global_object_with_null_value() {
+    let rubtle = Rubtle::new();
+
+    rubtle.eval(
+        r#"
+        var rubtle = { "rubtle1": null, "rubtle2": undefined };
+    "#,
+    ).unwrap();
+
+    let rval = rubtle.get_global_value("rubtle").unwrap();
+
+    let mut hash = HashMap::new();
+
+    hash.insert(String::from("rubtle1"), Value::Null);
+    hash.insert(String::from("rubtle2"), Value::None);
+
+    assert_eq!(Value::Object(hash.into()), rval);
+}
+
+#[test]
+fn set_global_object_with_null_value() {
+    let rubtle = Rubtle::new();
+
+    let mut hash = HashMap::new();
+
+    hash.insert(String::from("rubtle"), Value::Null);
+
+    rubtle.set_global_value("rubtle", &Value::Object(hash.into()));
+
+    assert_eq!(
+        Value::from(true),
+        rubtle.eval("null === rubtle.rubtle && 'rubtle' in rubtle").unwrap()
+    );
+}
+
+#[test]
+fn get_global_object_in_property_order() {
+    let rubtle = Rubtle::new();
+
+    rubtle.eval(
+        r#"
+        var rubtle = { b: 1, a: 2, 10: 3, 2: 4 };
+    "#,
+    ).unwrap();
+
+    let rval = rubtle.get_global_value("rubtle").unwrap();
+
+    match rval {
+        Value::Object(ref hash) => {
+            assert_eq!(vec!["2", "10", "b", "a"], hash.keys().collect::<Vec<_>>())
+        }
+        _ => panic!("Expected object"),
+    }
+
+    rubtle.set_global_value("rubtle2", &rval);
+
+    assert_eq!(
+        Value::from(true),
+        rubtle.eval("JSON.stringify(rubtle) === JSON.stringify(rubtle2)").unwrap()
+    );
+}
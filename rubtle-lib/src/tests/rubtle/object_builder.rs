@@ -8,7 +8,7 @@
 /// This program can be distributed under the terms of the GNU GPLv2.
 /// See the file LICENSE for details.
 ///
-use crate::{Rubtle, Value, CallbackResult, ObjectBuilder};
+use crate::{Rubtle, Value, CallbackResult, Error, ObjectBuilder};
 
 use crate::tests::rubtle::helper::js_assert;
 use crate::tests::rubtle::helper::js_printer;
@@ -43,7 +43,7 @@ fn set_global_object_with_ctor() {
         var counter = new Counter();
         assert(typeof counter != 'undefined', "Damn!");
     "#,
-    );
+    ).unwrap();
 }
 
 #[test]
@@ -80,7 +80,7 @@ fn set_global_object_with_ctor_and_method() {
         assert(typeof counter != 'undefined', "Damn!");
         counter.inc();
     "#,
-    );
+    ).unwrap();
 }
 
 #[test]
@@ -127,7 +127,7 @@ fn set_global_object_with_ctor_and_methods() {
         assert(3 == value, "Damn!");
         counter.print();
     "#,
-    );
+    ).unwrap();
 }
 
 #[test]
@@ -168,7 +168,7 @@ fn set_global_object_with_ctor_and_method_with_return_value() {
         print(value);
         assert(3 == value, "Damn!");
     "#,
-    );
+    ).unwrap();
 }
 
 #[test]
@@ -210,7 +210,7 @@ fn set_global_object_with_ctor_with_arguments_and_method_with_return_value() {
         var value = counter.inc();
         assert(6 == value, "Damn!");
     "#,
-    );
+    ).unwrap();
 }
 
 #[test]
@@ -256,5 +256,92 @@ fn set_global_object_with_ctor_with_arguments_and_method_with_arguments_and_retu
         var value = counter.inc(8);
         assert(10 == value, "Damn!");
     "#,
-    );
-}
\ No newline at end of file
+    ).unwrap();
+}
+
+#[test]
+fn set_global_object_with_method_returning_error() {
+    #[derive(Default)]
+    struct UserData {
+        value: i32,
+    };
+
+    let mut object = ObjectBuilder::<UserData>::new()
+        .with_constructor(|inv| {
+            let udata = inv.udata.as_mut().unwrap();
+
+            udata.value = 1;
+        })
+        .with_method("fail", |_inv| -> CallbackResult<Value> {
+            Err(Error::range_error("Out of range"))
+        })
+        .build();
+
+    let rubtle = Rubtle::new();
+
+    rubtle.set_global_object("Counter", &mut object);
+
+    rubtle.set_global_function("assert", js_assert);
+
+    rubtle.eval(
+        r#"
+        var counter = new Counter();
+
+        try {
+            counter.fail();
+            assert(false, "Damn!");
+        } catch (e) {
+            assert(e instanceof RangeError, "Damn!");
+            assert('Out of range' == e.message, "Damn!");
+        }
+    "#,
+    ).unwrap();
+}
+
+#[test]
+fn set_global_object_with_panicking_ctor_and_method() {
+    #[derive(Default)]
+    struct UserData {
+        value: i32,
+    };
+
+    let mut object = ObjectBuilder::<UserData>::new()
+        .with_constructor(|inv| {
+            let udata = inv.udata.as_mut().unwrap();
+            let args = inv.args.as_ref().unwrap();
+
+            udata.value = args.first().unwrap().as_number().unwrap() as i32;
+        })
+        .with_method("fail", |inv| -> CallbackResult<Value> {
+            let udata = inv.udata.as_ref().unwrap();
+
+            panic!("Value is {}", udata.value);
+        })
+        .build();
+
+    let rubtle = Rubtle::new();
+
+    rubtle.set_global_object("Counter", &mut object);
+
+    rubtle.set_global_function("assert", js_assert);
+
+    rubtle.eval(
+        r#"
+        try {
+            new Counter();
+            assert(false, "Damn!");
+        } catch (e) {
+            assert(e instanceof Error, "Damn!");
+        }
+
+        var counter = new Counter(5);
+
+        try {
+            counter.fail();
+            assert(false, "Damn!");
+        } catch (e) {
+            assert('Value is 5' == e.message, "Damn!");
+        }
+    "#,
+    ).unwrap();
+}
@@ -0,0 +1,211 @@
+///
+/// @package Rubtle-Lib
+///
+/// @file Rubtle tests - script
+/// @copyright 2020-present Christoph Kappel <christoph@unexist.dev>
+/// @version $Id$
+///
+/// This program can be distributed under the terms of the GNU GPLv2.
+/// See the file LICENSE for details.
+///
+use crate::{Error, Rubtle, Value};
+
+///
+/// Scripts
+///
+
+#[test]
+fn compile_and_run_script() {
+    let rubtle = Rubtle::new();
+
+    let script = rubtle.compile(
+        r#"
+        var rubtle = (typeof rubtle == 'undefined' ? 0 : rubtle) + 1;
+        rubtle;
+    "#,
+        "rubtle.js",
+    ).unwrap();
+
+    assert_eq!("rubtle.js", script.filename());
+
+    for i in 1..5 {
+        assert_eq!(Value::from(i), script.run(&rubtle).unwrap());
+    }
+
+    assert_eq!(Value::from(4), rubtle.get_global_value("rubtle").unwrap());
+}
+
+#[test]
+fn compile_script_with_syntax_error() {
+    let rubtle = Rubtle::new();
+
+    let err = rubtle.compile(
+        r#"
+        var rubtle = ;
+    "#,
+        "rubtle.js",
+    ).unwrap_err();
+
+    match err {
+        Error::Syntax(js) => assert_eq!(Some(String::from("rubtle.js")), js.file_name),
+        _ => panic!("Expected syntax error"),
+    }
+}
+
+#[test]
+fn run_script_with_thrown_error() {
+    let rubtle = Rubtle::new();
+
+    let script = rubtle.compile(
+        r#"
+        throw new Error('rubtle');
+    "#,
+        "rubtle.js",
+    ).unwrap();
+
+    let err = script.run(&rubtle).unwrap_err();
+    let js = err.as_js_error().unwrap();
+
+    assert_eq!(Some(String::from("rubtle.js")), js.file_name);
+    assert_eq!(Some(2), js.line_number);
+    assert!(js.stack.as_ref().unwrap().contains("rubtle.js"));
+}
+
+#[test]
+fn drop_script_after_rubtle() {
+    let script = {
+        let rubtle = Rubtle::new();
+
+        rubtle.compile("1 + 1", "rubtle.js").unwrap()
+    };
+
+    drop(script);
+}
+
+#[test]
+fn run_script_with_other_rubtle() {
+    let rubtle = Rubtle::new();
+    let other = Rubtle::new();
+
+    let script = rubtle.compile("1 + 1", "rubtle.js").unwrap();
+
+    match script.run(&other) {
+        Err(Error::Heap(_)) => (),
+        rval => panic!("Expected heap error, got {:?}", rval),
+    }
+
+    let rval = rubtle.eval("(function() { return 1; })").unwrap();
+    let func = rval.as_function().unwrap().as_js_function().unwrap();
+
+    assert!(func.call(&other, &Value::None, &[]).is_err());
+    assert!(func.call(&rubtle, &rval, &[]).is_ok());
+}
+
+///
+/// Bytecode
+///
+
+#[test]
+fn dump_and_load_script() {
+    let bytecode = {
+        let rubtle = Rubtle::new();
+
+        let script = rubtle.compile(
+            r#"
+            function square(i) {
+                return i * i;
+            }
+
+            square(4);
+        "#,
+            "rubtle.js",
+        ).unwrap();
+
+        script.dump(&rubtle).unwrap()
+    };
+
+    let rubtle = Rubtle::new();
+    let script = unsafe { rubtle.load(&bytecode).unwrap() };
+
+    assert_eq!("rubtle.js", script.filename());
+    assert_eq!(Value::from(16), script.run(&rubtle).unwrap());
+    assert_eq!(Value::from(9), rubtle.eval("square(3)").unwrap());
+}
+
+#[test]
+fn load_script_with_version_mismatch() {
+    let rubtle = Rubtle::new();
+    let script = rubtle.compile("1 + 1", "rubtle.js").unwrap();
+
+    let mut bytecode = script.dump(&rubtle).unwrap();
+
+    bytecode[5] ^= 0xff;
+
+    match unsafe { rubtle.load(&bytecode) } {
+        Err(Error::Bytecode(_)) => (),
+        _ => panic!("Expected bytecode error"),
+    }
+}
+
+#[test]
+fn load_script_with_invalid_bytecode() {
+    let rubtle = Rubtle::new();
+
+    match unsafe { rubtle.load(b"rubtle") } {
+        Err(Error::Bytecode(_)) => (),
+        _ => panic!("Expected bytecode error"),
+    }
+
+    let script = rubtle.compile("1 + 1", "rubtle.js").unwrap();
+    let mut bytecode = script.dump(&rubtle).unwrap();
+
+    /* Clobber duktape marker; caught by the checksum */
+    bytecode[25 + "rubtle.js".len()] = 0;
+
+    match unsafe { rubtle.load(&bytecode) } {
+        Err(Error::Bytecode(_)) => (),
+        _ => panic!("Expected bytecode error"),
+    }
+
+    match unsafe { rubtle.load(&bytecode[..10]) } {
+        Err(Error::Bytecode(_)) => (),
+        _ => panic!("Expected bytecode error"),
+    }
+}
+
+#[test]
+fn load_script_with_build_mismatch() {
+    let rubtle = Rubtle::new();
+    let script = rubtle.compile("1 + 1", "rubtle.js").unwrap();
+
+    let mut bytecode = script.dump(&rubtle).unwrap();
+
+    /* Toggle packed value flag */
+    bytecode[9] ^= 0x01;
+
+    match unsafe { rubtle.load(&bytecode) } {
+        Err(Error::Bytecode(msg)) => assert!(msg.starts_with("Bytecode from build")),
+        _ => panic!("Expected bytecode error"),
+    }
+}
+
+#[test]
+fn load_script_with_corrupted_bytecode() {
+    let rubtle = Rubtle::new();
+    let script = rubtle.compile("1 + 1", "rubtle.js").unwrap();
+
+    let mut bytecode = script.dump(&rubtle).unwrap();
+    let len = bytecode.len();
+
+    match unsafe { rubtle.load(&bytecode[..len - 1]) } {
+        Err(Error::Bytecode(msg)) => assert!(msg.starts_with("Bytecode has")),
+        _ => panic!("Expected bytecode error"),
+    }
+
+    bytecode[len - 1] ^= 0xff;
+
+    match unsafe { rubtle.load(&bytecode) } {
+        Err(Error::Bytecode(msg)) => assert_eq!("Bytecode checksum mismatch", msg),
+        _ => panic!("Expected bytecode error"),
+    }
+}
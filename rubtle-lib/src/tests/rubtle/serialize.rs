@@ -0,0 +1,159 @@
+///
+/// @package Rubtle-Lib
+///
+/// @file Rubtle tests - serialize
+/// @copyright 2020-present Christoph Kappel <christoph@unexist.dev>
+/// @version $Id$
+///
+/// This program can be distributed under the terms of the GNU GPLv2.
+/// See the file LICENSE for details.
+///
+use std::collections::HashMap;
+
+use serde_derive::{Deserialize, Serialize};
+
+use crate::{from_value, to_value, Error, Rubtle, Value};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+enum Kind {
+    Click,
+    Key(char),
+    Move { x: i32, y: i32 },
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Event {
+    name: String,
+    kinds: Vec<Kind>,
+    delay: Option<f64>,
+    tags: HashMap<String, Value>,
+}
+
+fn event() -> Event {
+    let mut tags = HashMap::new();
+
+    tags.insert(String::from("rubtle"), Value::from(true));
+
+    Event {
+        name: String::from("rubtle"),
+        kinds: vec![Kind::Click, Kind::Key('r'), Kind::Move { x: 1, y: -2 }],
+        delay: None,
+        tags,
+    }
+}
+
+///
+/// Serialize
+///
+
+#[test]
+fn serialize_to_value_and_back() {
+    let rval = to_value(&event()).unwrap();
+
+    match rval {
+        Value::Object(ref hash) => assert_eq!(Some(&Value::from("rubtle")), hash.get("name")),
+        _ => panic!("Expected object"),
+    }
+
+    assert_eq!(event(), from_value::<Event>(rval).unwrap());
+}
+
+#[test]
+fn push_serialize_and_pop_deserialize() {
+    let rubtle = Rubtle::new();
+
+    rubtle.push_serialize(&event()).unwrap();
+    let rval: Event = rubtle.pop_deserialize().unwrap();
+
+    assert_eq!(event(), rval);
+}
+
+#[test]
+fn deserialize_from_js() {
+    let rubtle = Rubtle::new();
+
+    rubtle
+        .eval(
+            r#"
+        var rubtle = {
+            name: 'rubtle',
+            kinds: ['Click', { Key: 'x' }, { Move: { x: 4, y: 5 } }],
+            tags: {}
+        };
+    "#,
+        )
+        .unwrap();
+
+    let rval: Event = from_value(rubtle.get_global_value("rubtle").unwrap()).unwrap();
+
+    assert_eq!(vec![Kind::Click, Kind::Key('x'), Kind::Move { x: 4, y: 5 }], rval.kinds);
+    assert_eq!(None, rval.delay);
+
+    rubtle.push_value(&rubtle.get_global_value("rubtle").unwrap());
+
+    assert_eq!(rval, rubtle.pop_deserialize::<Event>().unwrap());
+}
+
+#[test]
+fn serialize_to_js() {
+    let rubtle = Rubtle::new();
+
+    rubtle.push_serialize(&event()).unwrap();
+
+    unsafe {
+        ffi::duk_put_global_string(rubtle.ctx, b"rubtle\0".as_ptr() as *const _);
+    }
+
+    assert_eq!(
+        Value::from("r"),
+        rubtle.eval("rubtle.kinds[1].Key").unwrap()
+    );
+    assert_eq!(
+        Value::from(-2),
+        rubtle.eval("rubtle.kinds[2].Move.y").unwrap()
+    );
+    assert_eq!(Value::Null, rubtle.eval("rubtle.delay").unwrap());
+}
+
+#[test]
+fn deserialize_cyclic_object() {
+    let rubtle = Rubtle::new();
+
+    rubtle
+        .eval("var rubtle = {}; void (rubtle.self = rubtle);")
+        .unwrap();
+
+    unsafe {
+        ffi::duk_get_global_string(rubtle.ctx, b"rubtle\0".as_ptr() as *const _);
+    }
+
+    match rubtle.pop_deserialize::<Value>() {
+        Err(Error::Conversion { .. }) => (),
+        _ => panic!("Expected conversion error"),
+    }
+
+    unsafe {
+        assert_eq!(0, ffi::duk_get_top(rubtle.ctx));
+    }
+}
+
+#[test]
+fn deserialize_with_wrong_type() {
+    let rubtle = Rubtle::new();
+
+    rubtle.push_value(&Value::from("rubtle"));
+
+    assert!(rubtle.pop_deserialize::<Vec<i32>>().is_err());
+
+    rubtle.push_value(&Value::Array(vec![Value::from(1.5)]));
+
+    match rubtle.pop_deserialize::<Vec<i32>>() {
+        Err(Error::Serde(_)) => (),
+        _ => panic!("Expected serde error"),
+    }
+
+    /* Stack is left balanced after errors */
+    unsafe {
+        assert_eq!(0, ffi::duk_get_top(rubtle.ctx));
+    }
+}
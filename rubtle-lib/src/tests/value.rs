@@ -8,9 +8,12 @@
 /// This program can be distributed under the terms of the GNU GPLv2.
 /// See the file LICENSE for details.
 ///
-use crate::{Value, Invocation, CallbackResult, Function};
+use crate::{Value, Invocation, CallbackResult, Function, Error, ObjectMap};
 
 use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::convert::TryInto;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 ///
 /// Create value
@@ -22,7 +25,18 @@ fn create_none_value() {
 
     assert!(rval.is_none());
 
-    assert_eq!((), rval.into());
+    assert_eq!(Ok(()), rval.try_into());
+}
+
+#[test]
+fn create_null_value() {
+    let rval = Value::Null;
+
+    assert!(rval.is_null());
+    assert!(!rval.is_none());
+
+    assert_eq!(Some(()), rval.as_null());
+    assert_eq!(Some(String::from("null")), rval.coerce_string());
 }
 
 #[test]
@@ -31,7 +45,7 @@ fn create_boolean_value() {
     let rval = Value::from(val);
 
     assert!(rval.is_boolean());
-    assert_eq!(val, rval.into());
+    assert_eq!(Ok(val), rval.try_into());
 }
 
 #[test]
@@ -40,7 +54,7 @@ fn create_number_value_i32() {
     let rval = Value::from(val);
 
     assert!(rval.is_number());
-    assert_eq!(val, rval.into());
+    assert_eq!(Ok(val), rval.try_into());
 }
 
 #[test]
@@ -49,7 +63,7 @@ fn create_number_value_f64() {
     let rval = Value::from(val);
 
     assert!(rval.is_number());
-    assert_eq!(val, rval.into());
+    assert_eq!(Ok(val), rval.try_into());
 }
 
 #[test]
@@ -59,7 +73,7 @@ fn create_string_value() {
 
     assert!(rval.is_string());
 
-    let rval2: &str = rval.into();
+    let rval2: &str = (&rval).try_into().unwrap();
     assert_eq!(val, rval2);
 }
 
@@ -74,7 +88,7 @@ fn create_array_value_bool() {
 
     assert!(rval.is_array());
 
-    let ary: Vec<bool> = rval.into();
+    let ary: Vec<bool> = rval.try_into().unwrap();
 
     assert_eq!(val, ary.as_slice());
 }
@@ -86,7 +100,7 @@ fn create_array_value_i32() {
 
     assert!(rval.is_array());
 
-    let ary: Vec<i32> = rval.into();
+    let ary: Vec<i32> = rval.try_into().unwrap();
 
     assert_eq!(val, ary.as_slice());
 }
@@ -98,7 +112,7 @@ fn create_array_value_f64() {
 
     assert!(rval.is_array());
 
-    let ary: Vec<f64> = rval.into();
+    let ary: Vec<f64> = rval.try_into().unwrap();
 
     assert_eq!(val, ary.as_slice());
 }
@@ -110,7 +124,7 @@ fn create_array_value_str() {
 
     assert!(rval.is_array());
 
-    let ary: Vec<&str> = rval.into();
+    let ary: Vec<String> = rval.try_into().unwrap();
 
     assert_eq!(val, ary.as_slice());
 }
@@ -130,7 +144,7 @@ fn create_object_bool() {
 
     assert!(rval.is_object());
 
-    let hash: HashMap<String, bool> = rval.into();
+    let hash: HashMap<String, bool> = rval.try_into().unwrap();
 
     for (k, v) in val {
         assert!(hash.contains_key(k));
@@ -149,7 +163,7 @@ fn create_object_i32() {
 
     assert!(rval.is_object());
 
-    let hash: HashMap<String, i32> = rval.into();
+    let hash: HashMap<String, i32> = rval.try_into().unwrap();
 
     for (k, v) in val {
         assert!(hash.contains_key(k));
@@ -168,7 +182,7 @@ fn create_object_f64() {
 
     assert!(rval.is_object());
 
-    let hash: HashMap<String, f64> = rval.into();
+    let hash: HashMap<String, f64> = rval.try_into().unwrap();
 
     for (k, v) in val {
         assert!(hash.contains_key(k));
@@ -187,7 +201,7 @@ fn create_object_str() {
 
     assert!(rval.is_object());
 
-    let hash: HashMap<String, &str> = rval.into();
+    let hash: HashMap<String, String> = rval.try_into().unwrap();
 
     for (k, v) in val {
         assert!(hash.contains_key(k));
@@ -195,6 +209,98 @@ fn create_object_str() {
     }
 }
 
+#[test]
+fn create_bytes_value() {
+    let val: Vec<u8> = vec![1, 2, 3];
+    let rval = Value::from(val.clone());
+
+    assert!(rval.is_bytes());
+    assert_eq!(Some(&val[..]), rval.as_bytes());
+    assert_eq!(rval, Value::from(&val[..]));
+
+    assert_eq!(Ok(val), Vec::<u8>::try_from(rval));
+}
+
+#[test]
+fn create_date_value() {
+    let val = UNIX_EPOCH + Duration::from_millis(1234);
+    let rval = Value::from(val);
+
+    assert!(rval.is_date());
+    assert_eq!(Some(val), rval.as_date());
+
+    assert_eq!(Ok(val), SystemTime::try_from(rval));
+}
+
+///
+/// Fallible conversions
+///
+
+#[test]
+fn convert_with_wrong_type() {
+    assert_eq!(
+        Err(Error::Conversion { expected: "number", actual: "string" }),
+        i32::try_from(Value::from("4"))
+    );
+    assert_eq!(
+        Err(Error::Conversion { expected: "boolean", actual: "undefined" }),
+        bool::try_from(Value::None)
+    );
+    assert_eq!(
+        Err(Error::Conversion { expected: "string", actual: "null" }),
+        String::try_from(Value::Null)
+    );
+    assert_eq!(
+        Err(Error::Conversion { expected: "array", actual: "object" }),
+        Vec::<i32>::try_from(Value::Object(ObjectMap::new()))
+    );
+    assert_eq!(
+        Err(Error::Conversion { expected: "number", actual: "boolean" }),
+        Vec::<f64>::try_from(Value::Array(vec![Value::from(1.0), Value::from(true)]))
+    );
+}
+
+#[test]
+fn convert_integers_with_range_check() {
+    assert_eq!(Ok(-128), i8::try_from(Value::from(-128)));
+    assert_eq!(Ok(255), u8::try_from(Value::from(255)));
+    assert_eq!(Ok(u32::MAX), u32::try_from(Value::from(u32::MAX)));
+    assert_eq!(Ok(1 << 53), i64::try_from(Value::from(9007199254740992.0)));
+    assert_eq!(Ok(4), usize::try_from(Value::from(4)));
+
+    assert_eq!(
+        Err(Error::Conversion { expected: "u8", actual: "number out of range" }),
+        u8::try_from(Value::from(256))
+    );
+    assert_eq!(
+        Err(Error::Conversion { expected: "u16", actual: "number out of range" }),
+        u16::try_from(Value::from(-1))
+    );
+    assert_eq!(
+        Err(Error::Conversion { expected: "i32", actual: "number" }),
+        i32::try_from(Value::from(1.5))
+    );
+    assert_eq!(
+        Err(Error::Conversion { expected: "i64", actual: "number out of range" }),
+        i64::try_from(Value::from(9223372036854775808.0))
+    );
+    assert_eq!(
+        Err(Error::Conversion { expected: "u64", actual: "number" }),
+        u64::try_from(Value::from(std::f64::NAN))
+    );
+}
+
+#[test]
+fn convert_floats_with_range_check() {
+    assert_eq!(Ok(1.5), f32::try_from(Value::from(1.5)));
+    assert!(f32::try_from(Value::from(std::f64::INFINITY)).unwrap().is_infinite());
+
+    assert_eq!(
+        Err(Error::Conversion { expected: "f32", actual: "number out of range" }),
+        f32::try_from(Value::from(1e300))
+    );
+}
+
 ///
 /// Create function
 ///
@@ -210,6 +316,17 @@ fn create_function_value() {
     assert!(rval.is_function());
 }
 
+#[test]
+fn clone_function_value() {
+    let val = |_inv: Invocation<i8>| -> CallbackResult<Value> { Ok(Value::None) };
+
+    let rval = Value::from(Function::from(val));
+    let rval2 = Value::from(Function::from(val));
+
+    assert_eq!(rval, rval.clone());
+    assert_ne!(rval, rval2);
+}
+
 ///
 /// Convert values
 ///
@@ -252,7 +369,7 @@ fn convert_string() {
 
 #[test]
 fn coerce_none_to_string() {
-    let strval = "None";
+    let strval = "undefined";
     let val = ();
     let rval = Value::from(val);
 
@@ -285,3 +402,127 @@ fn coerce_string_to_string() {
 
     assert_eq!(strval, rval.coerce_string().unwrap());
 }
+
+#[test]
+fn coerce_array_to_string() {
+    let rval = Value::Array(vec![
+        Value::from(1),
+        Value::Array(vec![Value::from(2), Value::Null, Value::None]),
+        Value::Object(ObjectMap::new()),
+    ]);
+
+    assert_eq!("1,2,,,[object Object]", rval.coerce_string().unwrap());
+}
+
+#[test]
+fn coerce_numbers_to_string() {
+    let cases = [
+        (1e21, "1e+21"),
+        (123456789012345680000.0, "123456789012345680000"),
+        (1e-7, "1e-7"),
+        (-1.5e-7, "-1.5e-7"),
+        (0.000001, "0.000001"),
+        (1.5e300, "1.5e+300"),
+        (5e-324, "5e-324"),
+        (-0.0, "0"),
+        (0.1 + 0.2, "0.30000000000000004"),
+        (f64::NAN, "NaN"),
+        (f64::NEG_INFINITY, "-Infinity"),
+    ];
+
+    for (val, strval) in cases.iter() {
+        assert_eq!(*strval, Value::from(*val).coerce_string().unwrap());
+    }
+}
+
+#[test]
+fn coerce_string_to_number() {
+    let cases = [
+        ("", 0.0),
+        (" \u{a0}12\u{feff}\n", 12.0),
+        ("0x1F", 31.0),
+        ("0b101", 5.0),
+        ("0o17", 15.0),
+        (".5", 0.5),
+        ("5.", 5.0),
+        ("+.5e1", 5.0),
+        ("00012", 12.0),
+        ("-Infinity", f64::NEG_INFINITY),
+        ("1e1000", f64::INFINITY),
+    ];
+
+    for (strval, val) in cases.iter() {
+        assert_eq!(Some(*val), Value::from(*strval).coerce_number());
+    }
+
+    for strval in ["1_0", "infinity", "inf", "nan", "1e", "e3", "0x", "-0x10", "--1"].iter() {
+        assert!(Value::from(*strval).coerce_number().unwrap().is_nan(), "{}", strval);
+    }
+}
+
+#[test]
+fn coerce_values_to_number() {
+    assert!(Value::None.coerce_number().unwrap().is_nan());
+    assert_eq!(Some(0.0), Value::Null.coerce_number());
+    assert_eq!(Some(1.0), Value::from(true).coerce_number());
+    assert_eq!(Some(5.0), Value::Array(vec![Value::from(5)]).coerce_number());
+    assert_eq!(
+        Some(1500.0),
+        Value::from(UNIX_EPOCH + Duration::from_millis(1500)).coerce_number()
+    );
+    assert!(Value::Object(ObjectMap::new()).coerce_number().unwrap().is_nan());
+}
+
+#[test]
+fn coerce_values_to_boolean() {
+    assert!(!Value::None.coerce_boolean());
+    assert!(!Value::from(0.0).coerce_boolean());
+    assert!(!Value::from(f64::NAN).coerce_boolean());
+    assert!(!Value::from("").coerce_boolean());
+    assert!(Value::from("0").coerce_boolean());
+    assert!(Value::Array(Vec::new()).coerce_boolean());
+    assert!(Value::from(Vec::<u8>::new()).coerce_boolean());
+}
+
+#[test]
+fn keep_object_map_in_property_order() {
+    let mut hash = ObjectMap::new();
+
+    hash.insert("b", Value::from(1));
+    hash.insert("10", Value::from(2));
+    hash.insert("a", Value::from(3));
+    hash.insert("2", Value::from(4));
+    hash.insert("01", Value::from(5));
+
+    assert_eq!(vec!["2", "10", "b", "a", "01"], hash.keys().collect::<Vec<_>>());
+
+    assert_eq!(Some(Value::from(1)), hash.insert("b", Value::from(6)));
+    assert_eq!(Some(Value::from(2)), hash.remove("10"));
+    assert_eq!(None, hash.remove("10"));
+
+    assert_eq!(vec!["2", "b", "a", "01"], hash.keys().collect::<Vec<_>>());
+    assert_eq!(Value::from(6), hash["b"]);
+    assert_eq!(Some(&Value::from(5)), hash.get("01"));
+}
+
+#[test]
+fn keep_object_map_index_keys_sorted() {
+    let mut hash: ObjectMap = (0..1000)
+        .rev()
+        .map(|idx| (idx.to_string(), Value::from(idx)))
+        .collect();
+
+    hash.insert("rubtle", Value::from(true));
+    hash.remove("500");
+    hash.insert("500", Value::from(500));
+    hash.insert("4294967295", Value::from(false));
+
+    let keys: Vec<String> = hash.keys().map(String::from).collect();
+    let mut expected: Vec<String> = (0..1000).map(|idx: i32| idx.to_string()).collect();
+
+    expected.push(String::from("rubtle"));
+    expected.push(String::from("4294967295"));
+
+    assert_eq!(expected, keys);
+    assert_eq!(Value::from(500), hash["500"]);
+}
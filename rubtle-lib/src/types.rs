@@ -10,10 +10,11 @@
 //
 use std::result::Result;
 
+use crate::convert::ToJs;
 use crate::{Error, Invocation, Value};
 
 pub type CallbackResult<T> = Result<T, Error>;
-pub type Callback<T> = Box<dyn Fn(Invocation<T>) -> CallbackResult<Value>>;
+pub type Callback<T> = Box<dyn Fn(Invocation<T>) -> CallbackResult<Box<dyn ToJs>>>;
 
 /* Special object builder types */
 pub type ObjectBuilderCtor<T> = Box<dyn FnMut(&mut Invocation<T>)>;
@@ -8,20 +8,54 @@
 /// This program can be distributed under the terms of the GNU GPLv2.
 /// See the file LICENSE for details.
 ///
-use std::convert::From;
+use std::convert::{From, TryFrom};
 use std::collections::HashMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+use crate::external_buffer::ExternalBytes;
 use crate::function::Function;
+use crate::{Error, JsObject, JsonOptions, ObjectMap, Rubtle};
+
+thread_local! {
+    /// Scratch heap for the JSON helpers; created once per thread
+    static JSON_HEAP: Rubtle = Rubtle::new();
+}
+
+/// JS type binary data is pushed as
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BufferType {
+    /// Plain duktape buffer
+    Plain,
+    ArrayBuffer,
+    Uint8Array,
+    DataView,
+
+    /// Node.js Buffer
+    NodeBuffer,
+}
+
+impl Default for BufferType {
+    fn default() -> Self {
+        BufferType::Uint8Array
+    }
+}
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Value {
     None,
+    Null,
     Boolean(bool),
     Number(f64),
     Str(String),
     Array(Vec<Value>),
-    Object(HashMap<String, Value>),
+    Object(ObjectMap),
     Function(Function<i8>),
+    Bytes(Vec<u8>),
+    External(ExternalBytes),
+    Date(SystemTime),
+
+    /// Object reached again during a conversion with references
+    Ref(JsObject),
 }
 
 impl Value {
@@ -42,6 +76,22 @@ impl Value {
         }
     }
 
+    ///
+    /// Check whether value is null
+    ///
+    /// Returns
+    ///
+    /// `true` if the value is null; otherwise `false`
+    ///
+
+    pub fn is_null(&self) -> bool {
+        if let Value::Null = self {
+            true
+        } else {
+            false
+        }
+    }
+
     ///
     /// Check whether value is a boolean
     ///
@@ -122,6 +172,38 @@ impl Value {
         }
     }
 
+    ///
+    /// Check whether value is binary data
+    ///
+    /// Returns
+    ///
+    /// `true` if the value is binary data; otherwise `false`
+    ///
+
+    pub fn is_bytes(&self) -> bool {
+        if let Value::Bytes(_) = *self {
+            true
+        } else {
+            false
+        }
+    }
+
+    ///
+    /// Check whether value is a date
+    ///
+    /// Returns
+    ///
+    /// `true` if the value is a date; otherwise `false`
+    ///
+
+    pub fn is_date(&self) -> bool {
+        if let Value::Date(_) = *self {
+            true
+        } else {
+            false
+        }
+    }
+
     ///
     /// Check whether value is a string
     ///
@@ -138,6 +220,22 @@ impl Value {
         }
     }
 
+    ///
+    /// Check whether value is a reference
+    ///
+    /// Returns
+    ///
+    /// `true` if the value is a reference to a JS object; otherwise `false`
+    ///
+
+    pub fn is_ref(&self) -> bool {
+        if let Value::Ref(_) = *self {
+            true
+        } else {
+            false
+        }
+    }
+
     ///
     /// Return inner none value
     ///
@@ -154,6 +252,22 @@ impl Value {
         }
     }
 
+    ///
+    /// Return inner null value
+    ///
+    /// Returns
+    ///
+    /// `Option` either with value or without
+    ///
+
+    pub fn as_null(&self) -> Option<()> {
+        if let Value::Null = *self {
+            Some(())
+        } else {
+            None
+        }
+    }
+
     ///
     /// Return inner boolean value
     ///
@@ -203,22 +317,281 @@ impl Value {
     }
 
     ///
-    /// Coerce value to string
+    /// Return inner binary data
+    ///
+    /// Returns
+    ///
+    /// `Option` either with value or without
+    ///
+
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        if let Value::Bytes(ref value) = *self {
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    ///
+    /// Return inner date value
+    ///
+    /// Returns
+    ///
+    /// `Option` either with value or without
+    ///
+
+    pub fn as_date(&self) -> Option<SystemTime> {
+        if let Value::Date(value) = *self {
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    ///
+    /// Return inner function value
+    ///
+    /// Returns
+    ///
+    /// `Option` either with value or without
+    ///
+
+    pub fn as_function(&self) -> Option<&Function<i8>> {
+        if let Value::Function(ref value) = *self {
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    ///
+    /// Return inner object reference
+    ///
+    /// Returns
+    ///
+    /// `Option` either with value or without
+    ///
+
+    pub fn as_js_object(&self) -> Option<&JsObject> {
+        if let Value::Ref(ref value) = *self {
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    ///
+    /// Return JS type name of value
+    ///
+    /// Returns
+    ///
+    /// Name of the type as reported in conversion errors
+    ///
+
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::None => "undefined",
+            Value::Null => "null",
+            Value::Boolean(_) => "boolean",
+            Value::Number(_) => "number",
+            Value::Str(_) => "string",
+            Value::Array(_) => "array",
+            Value::Object(_) | Value::Ref(_) => "object",
+            Value::Function(_) => "function",
+            Value::Bytes(_) | Value::External(_) => "Uint8Array",
+            Value::Date(_) => "Date",
+        }
+    }
+
+    ///
+    /// Create conversion error for value
+    ///
+    /// # Arguments
+    ///
+    /// * `expected` - Name of the expected type
     ///
     /// Returns
     ///
-    /// Coerced `String`
+    /// `Error::Conversion` naming both types
+    ///
+
+    pub(crate) fn conversion_error(&self, expected: &'static str) -> Error {
+        Error::Conversion {
+            expected,
+            actual: self.type_name(),
+        }
+    }
+
+    ///
+    /// Coerce value to string like the ToString operation of ECMAScript does
+    ///
+    /// Returns
+    ///
+    /// `Option` with the coerced `String`; `None` if the result depends on
+    /// the heap like for dates, buffers, references and JS functions. Use
+    /// `Rubtle::coerce_string` for these
+    ///
+    /// # Example
+    ///
+    ///     use rubtle_lib::Value;
+    ///
+    ///     let rval = Value::Array(vec![Value::from(1e21), Value::Null, Value::from(0.5)]);
+    ///
+    ///     assert_eq!(Some(String::from("1e+21,,0.5")), rval.coerce_string());
     ///
 
     pub fn coerce_string(&self) -> Option<String> {
         match self {
-            Value::None => Some(String::from("None")),
-            Value::Number(val) => Some(val.to_string()),
+            Value::None => Some(String::from("undefined")),
+            Value::Null => Some(String::from("null")),
+            Value::Number(val) => Some(number_to_string(*val)),
             Value::Boolean(val) => Some(val.to_string()),
             Value::Str(val) => Some(val.clone()),
-            Value::Array(_) => Some(String::from("Array")),
-            Value::Object(_) => Some(String::from("Object")),
-            Value::Function(_) => Some(String::from("Function")),
+            Value::Array(vec) => vec
+                .iter()
+                .map(|val| match val {
+                    Value::None | Value::Null => Some(String::new()),
+                    _ => val.coerce_string(),
+                })
+                .collect::<Option<Vec<_>>>()
+                .map(|strings| strings.join(",")),
+            Value::Object(_) => Some(String::from("[object Object]")),
+            Value::Function(func) if !func.is_js_function() => {
+                Some(String::from("function () { [native code] }"))
+            }
+            Value::Function(_)
+            | Value::Bytes(_)
+            | Value::External(_)
+            | Value::Date(_)
+            | Value::Ref(_) => None,
+        }
+    }
+
+    ///
+    /// Coerce value to number like the ToNumber operation of ECMAScript does
+    ///
+    /// Returns
+    ///
+    /// `Option` with the coerced number; `None` if the result depends on
+    /// the heap like for references
+    ///
+    /// # Example
+    ///
+    ///     use rubtle_lib::Value;
+    ///
+    ///     assert_eq!(Some(31.0), Value::from(" 0x1F\n").coerce_number());
+    ///     assert!(Value::from("1_000").coerce_number().unwrap().is_nan());
+    ///
+
+    pub fn coerce_number(&self) -> Option<f64> {
+        match self {
+            Value::None => Some(f64::NAN),
+            Value::Null => Some(0.0),
+            Value::Boolean(val) => Some(if *val { 1.0 } else { 0.0 }),
+            Value::Number(val) => Some(*val),
+            Value::Str(val) => Some(string_to_number(val)),
+            Value::Array(_) => self.coerce_string().map(|string| string_to_number(&string)),
+            Value::Date(val) => Some(to_time_value(val)),
+            Value::Object(_) | Value::Function(_) | Value::Bytes(_) | Value::External(_) => {
+                Some(f64::NAN)
+            }
+            Value::Ref(_) => None,
+        }
+    }
+
+    ///
+    /// Coerce value to boolean like the ToBoolean operation of ECMAScript does
+    ///
+    /// Returns
+    ///
+    /// `false` for undefined, null, zero, NaN and empty strings; otherwise `true`
+    ///
+
+    pub fn coerce_boolean(&self) -> bool {
+        match self {
+            Value::None | Value::Null => false,
+            Value::Boolean(val) => *val,
+            Value::Number(val) => !(0.0 == *val || val.is_nan()),
+            Value::Str(val) => !val.is_empty(),
+            _ => true,
+        }
+    }
+
+    ///
+    /// Encode value as JSON in a scratch heap
+    ///
+    /// The scratch heap is shared by all calls on the same thread, so only
+    /// the first call pays for creating it
+    ///
+    /// Returns
+    ///
+    /// Either the JSON `String` or the `Error`; values holding JS handles
+    /// must be encoded with `Rubtle::json_encode` on their own heap
+    ///
+    /// # Example
+    ///
+    ///     use rubtle_lib::Value;
+    ///
+    ///     assert_eq!("[true,null]", Value::Array(vec![Value::from(true), Value::Null])
+    ///         .to_json_string().unwrap());
+    ///
+
+    pub fn to_json_string(&self) -> Result<String, Error> {
+        self.to_json_string_with(&JsonOptions::default())
+    }
+
+    ///
+    /// Encode value as JSON in a scratch heap with given options
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - Format and indentation of the output
+    ///
+    /// Returns
+    ///
+    /// Either the encoded `String` or the `Error`
+    ///
+
+    pub fn to_json_string_with(&self, options: &JsonOptions) -> Result<String, Error> {
+        if self.has_js_handles() {
+            return Err(self.conversion_error("value without JS handles"));
+        }
+
+        JSON_HEAP.with(|rubtle| rubtle.json_encode_with(self, options))
+    }
+
+    ///
+    /// Decode JSON into value in a scratch heap
+    ///
+    /// # Arguments
+    ///
+    /// * `json` - JSON to decode
+    ///
+    /// Returns
+    ///
+    /// Either the decoded `Value` or the thrown `SyntaxError` as `Error`
+    ///
+
+    pub fn from_json_str(json: &str) -> Result<Value, Error> {
+        JSON_HEAP.with(|rubtle| rubtle.json_decode(json))
+    }
+
+    ///
+    /// Check whether value holds handles of a JS heap
+    ///
+    /// Returns
+    ///
+    /// `true` if the value or any nested value is a reference or JS function;
+    /// otherwise `false`
+    ///
+
+    fn has_js_handles(&self) -> bool {
+        match self {
+            Value::Ref(_) => true,
+            Value::Function(func) => func.is_js_function(),
+            Value::Array(vec) => vec.iter().any(Value::has_js_handles),
+            Value::Object(hash) => hash.values().any(Value::has_js_handles),
+            _ => false,
         }
     }
 }
@@ -227,12 +600,13 @@ impl Value {
 /// Empty tuple
 ///
 
-impl From<Value> for () {
-    fn from(src: Value) -> () {
-        if let Value::None = src {
-            ()
-        } else {
-            unimplemented!();
+impl TryFrom<Value> for () {
+    type Error = Error;
+
+    fn try_from(src: Value) -> Result<(), Error> {
+        match src {
+            Value::None => Ok(()),
+            _ => Err(src.conversion_error("undefined")),
         }
     }
 }
@@ -247,12 +621,13 @@ impl From<()> for Value {
 /// Boolean
 ///
 
-impl From<Value> for bool {
-    fn from(src: Value) -> bool {
-        if let Value::Boolean(val) = src {
-            val
-        } else {
-            false
+impl TryFrom<Value> for bool {
+    type Error = Error;
+
+    fn try_from(src: Value) -> Result<bool, Error> {
+        match src {
+            Value::Boolean(val) => Ok(val),
+            _ => Err(src.conversion_error("boolean")),
         }
     }
 }
@@ -267,14 +642,30 @@ impl From<bool> for Value {
 /// Number
 ///
 
-macro_rules! convert_num_type {
+macro_rules! convert_int_type {
     ($num_type: ty) => {
-        impl From<Value> for $num_type {
-            fn from(src: Value) -> $num_type {
-                if let Value::Number(val) = src {
-                    val as $num_type
-                } else {
-                    -1 as $num_type
+        impl TryFrom<Value> for $num_type {
+            type Error = Error;
+
+            fn try_from(src: Value) -> Result<$num_type, Error> {
+                match src {
+                    /* Upper bound is exclusive, since MAX rounds up for 64 bit types */
+                    Value::Number(val)
+                        if 0.0 == val.fract()
+                            && val >= <$num_type>::MIN as f64
+                            && val < <$num_type>::MAX as f64 + 1.0 =>
+                    {
+                        Ok(val as $num_type)
+                    }
+                    Value::Number(val) if 0.0 == val.fract() => Err(Error::Conversion {
+                        expected: stringify!($num_type),
+                        actual: "number out of range",
+                    }),
+                    Value::Number(_) => Err(Error::Conversion {
+                        expected: stringify!($num_type),
+                        actual: "number",
+                    }),
+                    _ => Err(src.conversion_error("number")),
                 }
             }
         }
@@ -287,19 +678,67 @@ macro_rules! convert_num_type {
     };
 }
 
-convert_num_type!(i32);
-convert_num_type!(f64);
+convert_int_type!(i8);
+convert_int_type!(i16);
+convert_int_type!(i32);
+convert_int_type!(i64);
+convert_int_type!(u8);
+convert_int_type!(u16);
+convert_int_type!(u32);
+convert_int_type!(u64);
+convert_int_type!(usize);
+
+impl TryFrom<Value> for f32 {
+    type Error = Error;
+
+    fn try_from(src: Value) -> Result<f32, Error> {
+        match src {
+            Value::Number(val) if !val.is_finite() || val.abs() <= f32::MAX as f64 => {
+                Ok(val as f32)
+            }
+            Value::Number(_) => Err(Error::Conversion {
+                expected: "f32",
+                actual: "number out of range",
+            }),
+            _ => Err(src.conversion_error("number")),
+        }
+    }
+}
+
+impl From<f32> for Value {
+    fn from(src: f32) -> Self {
+        Value::Number(src as f64)
+    }
+}
+
+impl TryFrom<Value> for f64 {
+    type Error = Error;
+
+    fn try_from(src: Value) -> Result<f64, Error> {
+        match src {
+            Value::Number(val) => Ok(val),
+            _ => Err(src.conversion_error("number")),
+        }
+    }
+}
+
+impl From<f64> for Value {
+    fn from(src: f64) -> Self {
+        Value::Number(src)
+    }
+}
 
 ///
 /// String
 ///
 
-impl From<Value> for String {
-    fn from(src: Value) -> String {
-        if let Value::Str(val) = src {
-            val
-        } else {
-            unimplemented!();
+impl TryFrom<Value> for String {
+    type Error = Error;
+
+    fn try_from(src: Value) -> Result<String, Error> {
+        match src {
+            Value::Str(val) => Ok(val),
+            _ => Err(src.conversion_error("string")),
         }
     }
 }
@@ -310,12 +749,13 @@ impl From<String> for Value {
     }
 }
 
-impl From<Value> for &str {
-    fn from(src: Value) -> &'static str {
-        if let Value::Str(val) = src {
-            Box::leak(val.into_boxed_str())
-        } else {
-            unimplemented!();
+impl<'a> TryFrom<&'a Value> for &'a str {
+    type Error = Error;
+
+    fn try_from(src: &'a Value) -> Result<&'a str, Error> {
+        match src {
+            Value::Str(val) => Ok(val),
+            _ => Err(src.conversion_error("string")),
         }
     }
 }
@@ -332,22 +772,21 @@ impl From<&str> for Value {
 
 macro_rules! convert_array_type {
     ($array_type: ty) => {
-        impl<'rubtle> From<Value> for Vec<$array_type> {
-            fn from(src: Value) -> Vec<$array_type> {
-                if let Value::Array(val) = src {
-                    let mut ary = Vec::new();
-
-                    for v in val {
-                        ary.push(Value::into(v))
-                    }
+        impl TryFrom<Value> for Vec<$array_type> {
+            type Error = Error;
 
-                    ary
-                } else {
-                    unimplemented!();
+            fn try_from(src: Value) -> Result<Vec<$array_type>, Error> {
+                match src {
+                    Value::Array(val) => val.into_iter().map(<$array_type>::try_from).collect(),
+                    _ => Err(src.conversion_error("array")),
                 }
             }
         }
+    };
+}
 
+macro_rules! convert_array_ref_type {
+    ($array_type: ty) => {
         impl<'rubtle> From<&Vec<$array_type>> for Value {
             fn from(src: &Vec<$array_type>) -> Self {
                 let mut ary = Vec::new();
@@ -365,7 +804,242 @@ macro_rules! convert_array_type {
 convert_array_type!(bool);
 convert_array_type!(i32);
 convert_array_type!(f64);
-convert_array_type!(&'rubtle str);
+convert_array_type!(String);
+
+convert_array_ref_type!(bool);
+convert_array_ref_type!(i32);
+convert_array_ref_type!(f64);
+convert_array_ref_type!(&'rubtle str);
+
+///
+/// Bytes
+///
+
+impl TryFrom<Value> for Vec<u8> {
+    type Error = Error;
+
+    fn try_from(src: Value) -> Result<Vec<u8>, Error> {
+        match src {
+            Value::Bytes(val) => Ok(val),
+            Value::Array(val) => val.into_iter().map(u8::try_from).collect(),
+            _ => Err(src.conversion_error("Uint8Array")),
+        }
+    }
+}
+
+impl From<Vec<u8>> for Value {
+    fn from(src: Vec<u8>) -> Self {
+        Value::Bytes(src)
+    }
+}
+
+impl From<&[u8]> for Value {
+    fn from(src: &[u8]) -> Self {
+        Value::Bytes(src.to_vec())
+    }
+}
+
+///
+/// Date
+///
+
+impl TryFrom<Value> for SystemTime {
+    type Error = Error;
+
+    fn try_from(src: Value) -> Result<SystemTime, Error> {
+        match src {
+            Value::Date(val) => Ok(val),
+            _ => Err(src.conversion_error("Date")),
+        }
+    }
+}
+
+impl From<SystemTime> for Value {
+    fn from(src: SystemTime) -> Self {
+        Value::Date(src)
+    }
+}
+
+///
+/// Convert number to string like Number::toString with radix 10 does
+///
+/// # Arguments
+///
+/// * `val` - Number to convert
+///
+/// # Returns
+///
+/// Shortest `String` that reads back as the same number
+///
+
+fn number_to_string(val: f64) -> String {
+    if val.is_nan() {
+        return String::from("NaN");
+    } else if 0.0 == val {
+        return String::from("0");
+    } else if val.is_infinite() {
+        return String::from(if 0.0 < val { "Infinity" } else { "-Infinity" });
+    } else if 0.0 > val {
+        return format!("-{}", number_to_string(-val));
+    }
+
+    /* Shortest round-trip digits and exponent, e.g. 1.25e3 */
+    let sci = format!("{:e}", val);
+    let (mantissa, exp) = sci.split_at(sci.find('e').unwrap_or(sci.len()));
+    let digits: String = mantissa.chars().filter(|c| '.' != *c).collect();
+    let k = digits.len() as i32;
+    let n = exp[1..].parse::<i32>().unwrap_or(0) + 1;
+
+    if k <= n && n <= 21 {
+        format!("{}{}", digits, "0".repeat((n - k) as usize))
+    } else if 0 < n && n <= 21 {
+        format!("{}.{}", &digits[..n as usize], &digits[n as usize..])
+    } else if -6 < n && n <= 0 {
+        format!("0.{}{}", "0".repeat(-n as usize), digits)
+    } else {
+        let sign = if 0 > n - 1 { '-' } else { '+' };
+
+        if 1 == k {
+            format!("{}e{}{}", digits, sign, (n - 1).abs())
+        } else {
+            format!("{}.{}e{}{}", &digits[..1], &digits[1..], sign, (n - 1).abs())
+        }
+    }
+}
+
+///
+/// Convert string to number like the StringToNumber operation does
+///
+/// # Arguments
+///
+/// * `string` - String to convert
+///
+/// # Returns
+///
+/// Converted number; NaN if the string is no numeric literal
+///
+
+fn string_to_number(string: &str) -> f64 {
+    /* JS whitespace is Unicode whitespace without NEL but with BOM */
+    let string = string.trim_matches(|c: char| {
+        (c.is_whitespace() && '\u{85}' != c) || '\u{feff}' == c
+    });
+
+    if string.is_empty() {
+        return 0.0;
+    }
+
+    let radix = match string.get(..2) {
+        Some("0x") | Some("0X") => 16,
+        Some("0o") | Some("0O") => 8,
+        Some("0b") | Some("0B") => 2,
+        _ => 10,
+    };
+
+    if 10 != radix {
+        let digits = &string[2..];
+
+        if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix)) {
+            return f64::NAN;
+        }
+
+        return match u128::from_str_radix(digits, radix) {
+            Ok(val) => val as f64,
+            Err(_) => digits.chars().fold(0.0, |acc, c| {
+                acc * radix as f64 + c.to_digit(radix).unwrap_or(0) as f64
+            }),
+        };
+    }
+
+    let unsigned = string.trim_start_matches(|c| '+' == c || '-' == c);
+
+    if 1 < string.len() - unsigned.len() {
+        return f64::NAN;
+    } else if "Infinity" == unsigned {
+        return if string.starts_with('-') {
+            f64::NEG_INFINITY
+        } else {
+            f64::INFINITY
+        };
+    }
+
+    /* Digits with optional fraction and exponent; at least one digit */
+    let (mantissa, exp) = match unsigned.find(|c| 'e' == c || 'E' == c) {
+        Some(pos) => (&unsigned[..pos], Some(&unsigned[pos + 1..])),
+        None => (unsigned, None),
+    };
+
+    let mut parts = mantissa.splitn(2, '.');
+    let int_part = parts.next().unwrap_or("");
+    let frac_part = parts.next().unwrap_or("");
+
+    let is_digits = |part: &str| part.chars().all(|c| c.is_ascii_digit());
+    let is_mantissa = is_digits(int_part)
+        && is_digits(frac_part)
+        && !(int_part.is_empty() && frac_part.is_empty());
+    let is_exp = exp.map_or(true, |exp| {
+        let exp = exp.strip_prefix(|c| '+' == c || '-' == c).unwrap_or(exp);
+
+        !exp.is_empty() && is_digits(exp)
+    });
+
+    if is_mantissa && is_exp {
+        string.parse::<f64>().unwrap_or(f64::NAN)
+    } else {
+        f64::NAN
+    }
+}
+
+///
+/// Convert time to JS time value
+///
+/// # Arguments
+///
+/// * `time` - Time to convert
+///
+/// # Returns
+///
+/// Milliseconds since the epoch; sub-millisecond parts are floored
+///
+
+pub(crate) fn to_time_value(time: &SystemTime) -> f64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(since) => since.as_millis() as f64,
+        Err(err) => {
+            let before = err.duration();
+            let millis = before.as_millis() as f64;
+
+            /* Floor towards the past like JS does */
+            if 0 == before.subsec_nanos() % 1_000_000 {
+                -millis
+            } else {
+                -millis - 1.0
+            }
+        }
+    }
+}
+
+///
+/// Convert JS time value to time
+///
+/// # Arguments
+///
+/// * `timeval` - Milliseconds since the epoch
+///
+/// # Returns
+///
+/// `Option` with the time; `None` for invalid dates
+///
+
+pub(crate) fn from_time_value(timeval: f64) -> Option<SystemTime> {
+    if !timeval.is_finite() {
+        None
+    } else if 0.0 <= timeval {
+        UNIX_EPOCH.checked_add(Duration::from_millis(timeval as u64))
+    } else {
+        UNIX_EPOCH.checked_sub(Duration::from_millis(-timeval as u64))
+    }
+}
 
 ///
 /// Object
@@ -373,31 +1047,27 @@ convert_array_type!(&'rubtle str);
 
 macro_rules! convert_object_type {
     ($obj_type: ty) => {
-        impl<'rubtle> From<Value> for HashMap<String, $obj_type> {
-            fn from(src: Value) -> HashMap<String, $obj_type> {
-                if let Value::Object(val) = src {
-                    let mut hash: HashMap<String, $obj_type> = HashMap::new();
-
-                    for (k, v) in val {
-                        hash.insert(k, v.into());
-                    }
+        impl TryFrom<Value> for HashMap<String, $obj_type> {
+            type Error = Error;
 
-                    hash
-                } else {
-                    unimplemented!();
+            fn try_from(src: Value) -> Result<HashMap<String, $obj_type>, Error> {
+                match src {
+                    Value::Object(val) => val
+                        .into_iter()
+                        .map(|(k, v)| <$obj_type>::try_from(v).map(|v| (k, v)))
+                        .collect(),
+                    _ => Err(src.conversion_error("object")),
                 }
             }
         }
+    };
+}
 
+macro_rules! convert_object_ref_type {
+    ($obj_type: ty) => {
         impl<'rubtle> From<&HashMap<&'rubtle str, $obj_type>> for Value {
             fn from(src: &HashMap<&'rubtle str, $obj_type>) -> Self {
-                let mut hash = HashMap::new();
-
-                for (k, v) in src {
-                    hash.insert(k.to_string(), Value::from(*v));
-                }
-
-                Value::Object(hash)
+                Value::Object(src.iter().map(|(k, v)| (*k, Value::from(*v))).collect())
             }
         }
     }
@@ -406,18 +1076,24 @@ macro_rules! convert_object_type {
 convert_object_type!(bool);
 convert_object_type!(i32);
 convert_object_type!(f64);
-convert_object_type!(&'rubtle str);
+convert_object_type!(String);
+
+convert_object_ref_type!(bool);
+convert_object_ref_type!(i32);
+convert_object_ref_type!(f64);
+convert_object_ref_type!(&'rubtle str);
 
 ///
 /// Function
 ///
 
-impl From<Value> for Function<i8> {
-    fn from(src: Value) -> Function<i8> {
-        if let Value::Function(val) = src {
-            val
-        } else {
-            unimplemented!();
+impl TryFrom<Value> for Function<i8> {
+    type Error = Error;
+
+    fn try_from(src: Value) -> Result<Function<i8>, Error> {
+        match src {
+            Value::Function(val) => Ok(val),
+            _ => Err(src.conversion_error("function")),
         }
     }
 }
@@ -426,4 +1102,4 @@ impl From<Function<i8>> for Value {
     fn from(src: Function<i8>) -> Self {
         Value::Function(src)
     }
-}
\ No newline at end of file
+}
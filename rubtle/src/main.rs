@@ -22,9 +22,9 @@ fn js_printer(inv: Invocation<i8>) -> CallbackResult<Value> {
     let args = inv.args.unwrap();
 
     for val in args.iter() {
-        match val.coerce_string() {
-            Some(s) => println!("<JS> {:?}", s),
-            None => eprintln!("Error unwrap value"),
+        match inv.rubtle.coerce_string(val) {
+            Ok(s) => println!("<JS> {:?}", s),
+            Err(err) => eprintln!("Error unwrap value: {}", err),
         }
     }
 
@@ -107,7 +107,14 @@ fn main() {
         init_rubtle(&rubtle);
 
         match contents {
-            Ok(val) => rubtle.eval(&val),
+            Ok(val) => {
+                if let Err(err) = rubtle.eval(&val) {
+                    match err.as_js_error().and_then(|js| js.stack.as_ref()) {
+                        Some(stack) => eprintln!("<JS> {}", stack),
+                        None => eprintln!("<JS> {}", err),
+                    }
+                }
+            }
             Err(_) => eprintln!("File read failed"),
         }
     } else {